@@ -1,3 +1,4 @@
+#include <algorithm>
 #include <cassert>
 #include <cstdlib>
 #include <ctime>
@@ -523,6 +524,7 @@ namespace HLTAS
 	void Input::Clear()
 	{
 		Properties.clear();
+		PropertyList.clear();
 		Frames.clear();
 		ErrorMessage.clear();
 	}
@@ -565,6 +567,11 @@ namespace HLTAS
 		return Properties;
 	}
 
+	const std::vector<std::pair<std::string, std::string>>& Input::GetPropertyList() const
+	{
+		return PropertyList;
+	}
+
 	const std::vector<Frame>& Input::GetFrames() const
 	{
 		return Frames;
@@ -577,17 +584,38 @@ namespace HLTAS
 
 	void Input::SetProperty(const std::string& property, const std::string& value)
 	{
+		auto is_property = [&](const std::pair<std::string, std::string>& p) { return p.first == property; };
+
+		// Replace the first occurrence and drop the rest, keeping the position in the list.
+		auto it = std::find_if(PropertyList.begin(), PropertyList.end(), is_property);
+		if (it == PropertyList.end()) {
+			PropertyList.emplace_back(property, value);
+		} else {
+			it->second = value;
+			PropertyList.erase(std::remove_if(it + 1, PropertyList.end(), is_property), PropertyList.end());
+		}
+
+		Properties[property] = value;
+	}
+
+	void Input::AddProperty(const std::string& property, const std::string& value)
+	{
+		PropertyList.emplace_back(property, value);
 		Properties[property] = value;
 	}
 
 	void Input::RemoveProperty(const std::string& property)
 	{
+		auto is_property = [&](const std::pair<std::string, std::string>& p) { return p.first == property; };
+
 		Properties.erase(property);
+		PropertyList.erase(std::remove_if(PropertyList.begin(), PropertyList.end(), is_property), PropertyList.end());
 	}
 
 	void Input::ClearProperties()
 	{
 		Properties.clear();
+		PropertyList.clear();
 	}
 
 	void Input::PushFrame(const Frame& frame)
@@ -621,6 +649,11 @@ extern "C" void hltas_input_set_property(void* input, const char* property, cons
 	hltas_input->SetProperty(property, value);
 }
 
+extern "C" void hltas_input_add_property(void* input, const char* property, const char* value) {
+	HLTAS::Input* hltas_input = static_cast<HLTAS::Input*>(input);
+	hltas_input->AddProperty(property, value);
+}
+
 extern "C" void hltas_input_push_frame(void* input, const hltas_frame* c_frame) {
 	HLTAS::Input* hltas_input = static_cast<HLTAS::Input*>(input);
 
@@ -639,6 +672,26 @@ extern "C" const char* hltas_input_get_property(const void* input, const char* p
 	return nullptr;
 }
 
+extern "C" const char* hltas_input_get_property_name(const void* input, size_t index) {
+	const HLTAS::Input* hltas_input = static_cast<const HLTAS::Input*>(input);
+
+	const auto& properties = hltas_input->GetPropertyList();
+	if (index >= properties.size())
+		return nullptr;
+
+	return properties[index].first.data();
+}
+
+extern "C" const char* hltas_input_get_property_value(const void* input, size_t index) {
+	const HLTAS::Input* hltas_input = static_cast<const HLTAS::Input*>(input);
+
+	const auto& properties = hltas_input->GetPropertyList();
+	if (index >= properties.size())
+		return nullptr;
+
+	return properties[index].second.data();
+}
+
 extern "C" int hltas_input_get_frame(const void* input, size_t index, hltas_frame* c_frame) {
 	const HLTAS::Input* hltas_input = static_cast<const HLTAS::Input*>(input);
 
@@ -3,6 +3,7 @@
 #include <ctime>
 #include <string>
 #include <unordered_map>
+#include <utility>
 #include <vector>
 
 extern "C" {
@@ -10,6 +11,9 @@ extern "C" {
 
 	void hltas_input_set_property(void* input, const char* property, const char* value);
 	const char* hltas_input_get_property(const void* input, const char* property);
+	void hltas_input_add_property(void* input, const char* property, const char* value);
+	const char* hltas_input_get_property_name(const void* input, size_t index);
+	const char* hltas_input_get_property_value(const void* input, size_t index);
 	void hltas_input_push_frame(void* input, const hltas_frame* frame);
 	int hltas_input_get_frame(const void* input, size_t index, hltas_frame* frame);
 	void hltas_input_set_error_message(void* input, const char* message);
@@ -455,10 +459,12 @@ namespace HLTAS
 
 		int GetVersion() const;
 		const std::unordered_map<std::string, std::string>& GetProperties() const;
+		const std::vector<std::pair<std::string, std::string>>& GetPropertyList() const;
 		const std::vector<Frame>& GetFrames() const;
 		const std::string& GetErrorMessage() const;
 
 		void SetProperty(const std::string& property, const std::string& value);
+		void AddProperty(const std::string& property, const std::string& value);
 		void RemoveProperty(const std::string& property);
 		void ClearProperties();
 
@@ -471,6 +477,7 @@ namespace HLTAS
 	protected:
 		int Version;
 		std::unordered_map<std::string, std::string> Properties;
+		std::vector<std::pair<std::string, std::string>> PropertyList;
 		std::vector<Frame> Frames;
 		std::string ErrorMessage;
 	};
@@ -4,16 +4,18 @@
 #include <array>
 #include <string>
 #include <utility>
+#include <vector>
 
 #include "hltas.hpp"
 
-const std::array<const char*, 23> parse_files = {
+const std::array<const char*, 24> parse_files = {
 	"bhop_20fps.hltas",
 	"bhop.hltas",
 	"bkz_goldbhop.hltas",
 	"blolly.hltas",
 	"change.hltas",
 	"cs_estate.hltas",
+	"custom_properties.hltas",
 	"destructo-hops.hltas",
 	"extra-letters.hltas",
 	"goldbhop.hltas",
@@ -229,6 +231,26 @@ s03l-D----|------|------|0.001|90|-|5315\n\
 	validate(input);
 }
 
+TEST_CASE("Parse, write, parse custom properties") {
+	// Known properties come first, followed by the custom ones in their original order.
+	const std::vector<std::pair<std::string, std::string>> expected = {
+		{ "demo", "bhop" },
+		{ "hlstrafe_version", "1" },
+		{ "runner", "someone" },
+		{ "route_revision", "12" },
+		{ "runner", "someone else" },
+	};
+
+	HLTAS::Input input;
+	REQUIRE(input.Open("../test-data/parse/custom_properties.hltas").Code == HLTAS::ErrorCode::OK);
+	CHECK(input.GetPropertyList() == expected);
+	CHECK(input.GetProperties().at("runner") == "someone else");
+
+	REQUIRE(input.Save("../test-data/write-output/custom_properties.hltas").Code == HLTAS::ErrorCode::OK);
+	REQUIRE(input.Open("../test-data/write-output/custom_properties.hltas").Code == HLTAS::ErrorCode::OK);
+	CHECK(input.GetPropertyList() == expected);
+}
+
 TEST_CASE("Max accel yaw offset yaw strafing") {
 	const char *script = "version 1\n\
 hlstrafe_version 5\n\
@@ -44,7 +44,8 @@ cfg_if::cfg_if! {
 
 #[cfg(not(test))]
 use hltas_cpp::{
-    hltas_input_get_frame, hltas_input_get_property, hltas_input_push_frame,
+    hltas_input_add_property, hltas_input_get_frame, hltas_input_get_property,
+    hltas_input_get_property_name, hltas_input_get_property_value, hltas_input_push_frame,
     hltas_input_set_error_message, hltas_input_set_property,
 };
 
@@ -65,6 +66,28 @@ pub extern "C" fn hltas_input_get_property(
     unreachable!()
 }
 #[cfg(test)]
+pub extern "C" fn hltas_input_add_property(
+    _input: *mut c_void,
+    _property: *const c_char,
+    _value: *const c_char,
+) {
+    unreachable!()
+}
+#[cfg(test)]
+pub extern "C" fn hltas_input_get_property_name(
+    _input: *const c_void,
+    _index: usize,
+) -> *const c_char {
+    unreachable!()
+}
+#[cfg(test)]
+pub extern "C" fn hltas_input_get_property_value(
+    _input: *const c_void,
+    _index: usize,
+) -> *const c_char {
+    unreachable!()
+}
+#[cfg(test)]
 pub extern "C" fn hltas_input_push_frame(
     _input: *mut c_void,
     _frame: *const hltas_cpp::hltas_frame,
@@ -180,6 +203,21 @@ impl From<read::Context> for hltas_cpp::ErrorCode {
             NegativeYawspeed => NEGATIVE_YAWSPEED_VALUE,
             NoYawOffset => NO_YAW_OFFSET,
             NoYawOffsetAcceleration => NO_YAW_OFFSET_ACCELERATION,
+            TooFewAutoActionsCharacters
+            | InvalidAutoActions
+            | InvalidMovementKeys
+            | InvalidActionKeys
+            | InvalidFrameTime
+            | InvalidYawField
+            | InvalidPitch
+            | InvalidFrameCount
+            | InvalidChangeTarget
+            | NoChangeFinalValue
+            | NoChangeDuration
+            | InvalidOverrideValues
+            | InvalidLookAtEntity
+            | InvalidLookAtCoordinates => FAILFRAME,
+            InvalidSeeds | InvalidHLStrafeVersion | NoFrames => FAILLINE,
         }
     }
 }
@@ -431,7 +469,7 @@ pub struct AllocatedData {
 ///
 /// # Panics
 ///
-/// Panics if `line` is `Line::Comment`.
+/// Panics if `line` is `Line::Comment` or `Line::Unknown`.
 pub unsafe fn hltas_frame_from_non_comment_line(
     line: &Line,
 ) -> (hltas_cpp::hltas_frame, ManuallyDrop<AllocatedData>) {
@@ -440,6 +478,7 @@ pub unsafe fn hltas_frame_from_non_comment_line(
 
     match line {
         Line::Comment(_) => panic!("can't convert a comment line"),
+        Line::Unknown(_) => panic!("can't convert an unknown line"),
         Line::FrameBulk(frame_bulk) => {
             match frame_bulk.auto_actions.movement {
                 Some(AutoMovement::SetYaw(yaw)) => {
@@ -728,6 +767,12 @@ unsafe fn hltas_rs_from_str(input: *mut c_void, script: &str) -> hltas_cpp::Erro
                     load_command.as_ptr(),
                 );
             }
+            // Custom properties can repeat, so add them rather than set them.
+            for (name, value) in hltas.properties.custom {
+                let name = CString::new(name).unwrap();
+                let value = CString::new(value).unwrap();
+                hltas_input_add_property(input, name.as_ptr(), value.as_ptr());
+            }
 
             let mut comments = String::new();
             for line in hltas.lines {
@@ -919,6 +964,44 @@ unsafe fn hltas_rs_to_writer(
         };
     };
 
+    let mut custom = Vec::new();
+    let mut property_index = 0;
+    loop {
+        let name_ptr = hltas_input_get_property_name(input, property_index);
+        let value_ptr = hltas_input_get_property_value(input, property_index);
+        if name_ptr.is_null() {
+            break;
+        }
+        property_index += 1;
+
+        let name = if let Ok(name) = CStr::from_ptr(name_ptr).to_str() {
+            name
+        } else {
+            return hltas_cpp::ErrorDescription {
+                Code: hltas_cpp::ErrorCode::FAILWRITE,
+                LineNumber: 0,
+            };
+        };
+
+        if matches!(
+            name,
+            "demo" | "save" | "seed" | "frametime0ms" | "hlstrafe_version" | "load_command"
+        ) {
+            continue;
+        }
+
+        let value = if let Ok(value) = CStr::from_ptr(value_ptr).to_str() {
+            value
+        } else {
+            return hltas_cpp::ErrorDescription {
+                Code: hltas_cpp::ErrorCode::FAILWRITE,
+                LineNumber: 0,
+            };
+        };
+
+        custom.push((name.to_owned(), value.to_owned()));
+    }
+
     let mut hltas = HLTAS {
         properties: Properties {
             demo: demo.map(ToOwned::to_owned),
@@ -927,6 +1010,7 @@ unsafe fn hltas_rs_to_writer(
             frametime_0ms: frametime_0ms.map(ToOwned::to_owned),
             hlstrafe_version,
             load_command: load_command.map(ToOwned::to_owned),
+            custom,
         },
         lines: Vec::new(),
     };
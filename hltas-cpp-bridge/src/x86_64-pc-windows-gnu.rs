@@ -13,6 +13,25 @@ extern "C" {
         property: *const ::std::os::raw::c_char,
     ) -> *const ::std::os::raw::c_char;
 }
+extern "C" {
+    pub fn hltas_input_add_property(
+        input: *mut ::std::os::raw::c_void,
+        property: *const ::std::os::raw::c_char,
+        value: *const ::std::os::raw::c_char,
+    );
+}
+extern "C" {
+    pub fn hltas_input_get_property_name(
+        input: *const ::std::os::raw::c_void,
+        index: usize,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn hltas_input_get_property_value(
+        input: *const ::std::os::raw::c_void,
+        index: usize,
+    ) -> *const ::std::os::raw::c_char;
+}
 extern "C" {
     pub fn hltas_input_push_frame(input: *mut ::std::os::raw::c_void, frame: *const hltas_frame);
 }
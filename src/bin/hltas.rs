@@ -0,0 +1,88 @@
+extern crate hltas;
+
+use std::{
+    env::args,
+    fs::{read_to_string, File},
+    process::exit,
+};
+
+use hltas::{diff, merge, timing, HLTAS};
+
+const USAGE: &str = "\
+Usage:
+    hltas diff <first.hltas> <second.hltas>
+        Compare the properties of two scripts and the scripts frame by frame. Exits with 0 if the
+        scripts are equivalent and with 1 if they differ.
+
+    hltas merge <base.hltas> <ours.hltas> <theirs.hltas>
+        Merge the changes to base from ours and theirs frame by frame, writing the result to
+        ours. Exits with 0 if the merge is clean and with 1 if there are conflicts, which are
+        written with conflict markers.
+
+        To use it as a git merge driver, add this to the git config:
+
+            [merge \"hltas\"]
+                name = HLTAS frame-level merge
+                driver = hltas merge %O %A %B
+
+        and this to .gitattributes:
+
+            *.hltas merge=hltas
+
+    hltas stats <script.hltas>
+        Print the frame count, the script and game time, the timed segments between
+        bxt_timer_start and bxt_timer_stop and the demo recordings of a script.";
+
+fn read(filename: &str) -> Result<HLTAS, String> {
+    let contents =
+        read_to_string(filename).map_err(|e| format!("error reading {}: {}", filename, e))?;
+    HLTAS::from_str(&contents).map_err(|e| format!("{}: {}", filename, e.to_diagnostic()))
+}
+
+/// Runs the subcommand, returning the exit code.
+fn run(args: &[String]) -> Result<i32, String> {
+    match args {
+        [command, first, second] if command == "diff" => {
+            let diff = diff::diff(&read(first)?, &read(second)?);
+            println!("{}", diff);
+            Ok(if diff.is_equivalent() { 0 } else { 1 })
+        }
+        [command, base, ours, theirs] if command == "merge" => {
+            let merge = merge::merge(&read(base)?, &read(ours)?, &read(theirs)?);
+
+            let file = File::create(ours).map_err(|e| format!("error creating {}: {}", ours, e))?;
+            merge
+                .to_writer(file)
+                .map_err(|e| format!("error writing {}: {}", ours, e))?;
+
+            if let Some(conflict) = &merge.properties_conflict {
+                eprintln!("conflicting properties: {}", conflict.names.join(", "));
+            }
+            for conflict in merge.conflicts() {
+                eprintln!(
+                    "conflict in frames {}..{} of the base script",
+                    conflict.frames.start, conflict.frames.end
+                );
+            }
+            Ok(if merge.is_clean() { 0 } else { 1 })
+        }
+        [command, filename] if command == "stats" => {
+            let analysis =
+                timing::analyze(&read(filename)?).map_err(|e| format!("{}: {}", filename, e))?;
+            println!("{}", analysis);
+            Ok(0)
+        }
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => exit(code),
+        Err(message) => {
+            eprintln!("{}", message);
+            exit(2);
+        }
+    }
+}
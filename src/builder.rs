@@ -0,0 +1,614 @@
+//! Builders for [`FrameBulk`] and [`AutoActions`].
+//!
+//! The types can hold combinations that the parser never produces, like LGAGST without a jump or
+//! a ducktap, or constant yawspeed strafing towards a point. The builders enforce the same rules
+//! as the parser when [`build()`](FrameBulkBuilder::build) is called, so scripts generated with
+//! them always read back the same.
+//!
+//! Methods taking `times` accept a [`Times`] or a `u32`, where `0` means unlimited, like in the
+//! script.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{
+//!     builder::FrameBulkBuilder,
+//!     types::{StrafeDir, StrafeType},
+//! };
+//!
+//! let frame_bulk = FrameBulkBuilder::new("0.001")
+//!     .strafe(StrafeType::MaxAccel, StrafeDir::Yaw(90.))
+//!     .lgagst(0)
+//!     .jump(0)
+//!     .duck_before_collision(0)
+//!     .frame_count(100)
+//!     .command("echo hello")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(frame_bulk.frame_count.get(), 100);
+//! assert_eq!(frame_bulk.console_command.as_deref(), Some("echo hello"));
+//! ```
+//!
+//! Invalid combinations are reported with the same context as when parsing:
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{builder::AutoActionsBuilder, read::Context};
+//!
+//! let error = AutoActionsBuilder::new().jump(0).ducktap(0).build().unwrap_err();
+//! assert_eq!(error.context(), Some(Context::BothAutoJumpAndDuckTap));
+//! ```
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    num::NonZeroU32,
+};
+
+use crate::{
+    read::Context,
+    types::{
+        ActionKeys, AutoActions, AutoMovement, DuckBeforeCollision, DuckBeforeGround, DuckWhenJump,
+        FrameBulk, JumpBug, LeaveGroundAction, LeaveGroundActionSpeed, LeaveGroundActionType,
+        MovementKeys, StrafeDir, StrafeSettings, StrafeType, Times,
+    },
+    validate::{validate_frame_bulk, validate_movement, Field, Problem, ValidationError},
+};
+
+/// Error returned when building an invalid [`FrameBulk`] or [`AutoActions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Both autojump and ducktap are enabled.
+    BothAutoJumpAndDuckTap,
+    /// LGAGST is enabled without autojump or ducktap.
+    NoLeaveGroundAction,
+    /// Times are set on the autojump or ducktap used by LGAGST; they belong to LGAGST.
+    TimesOnLeaveGroundAction,
+    /// Both a yaw angle and strafing are set. Strafing takes its angle from the direction.
+    BothYawAndStrafing,
+    /// The frame count is zero.
+    ZeroFrameCount,
+    /// A value can't be written, such as a negative yawspeed or a non-numeric frame time.
+    Invalid {
+        /// Part of the frame bulk that holds the value.
+        field: Field,
+        /// What is wrong with the value.
+        problem: Problem,
+    },
+}
+
+impl BuildError {
+    /// Returns the parsing error context for the matching error the parser reports, if there is
+    /// one.
+    pub fn context(&self) -> Option<Context> {
+        match self {
+            BuildError::BothAutoJumpAndDuckTap => Some(Context::BothAutoJumpAndDuckTap),
+            BuildError::NoLeaveGroundAction => Some(Context::NoLeaveGroundAction),
+            BuildError::TimesOnLeaveGroundAction => Some(Context::TimesOnLeaveGroundAction),
+            BuildError::BothYawAndStrafing => Some(Context::InvalidYawField),
+            BuildError::ZeroFrameCount => Some(Context::InvalidFrameCount),
+            BuildError::Invalid { field, problem } => ValidationError {
+                line_index: None,
+                field: field.clone(),
+                problem: *problem,
+            }
+            .context(),
+        }
+    }
+}
+
+impl From<(Field, Problem)> for BuildError {
+    #[inline]
+    fn from((field, problem): (Field, Problem)) -> Self {
+        Self::Invalid { field, problem }
+    }
+}
+
+/// Builder for [`AutoActions`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutoActionsBuilder {
+    strafe: Option<StrafeSettings>,
+    yaw: Option<f32>,
+    lgagst: Option<(LeaveGroundActionSpeed, Times)>,
+    jump: Option<Times>,
+    ducktap: Option<(bool, Times)>,
+    jump_bug: Option<JumpBug>,
+    duck_before_collision: Option<DuckBeforeCollision>,
+    duck_before_ground: Option<DuckBeforeGround>,
+    duck_when_jump: Option<DuckWhenJump>,
+}
+
+impl AutoActionsBuilder {
+    /// Creates a builder with no auto-actions.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables strafing.
+    ///
+    /// The angles, the point or the count go into `dir`, and the yawspeed or the yaw offset go
+    /// into `type_`.
+    #[inline]
+    pub fn strafe(mut self, type_: StrafeType, dir: StrafeDir) -> Self {
+        self.strafe = Some(StrafeSettings { type_, dir });
+        self
+    }
+
+    /// Sets the yaw angle, without strafing.
+    #[inline]
+    pub fn yaw(mut self, yaw: f32) -> Self {
+        self.yaw = Some(yaw);
+        self
+    }
+
+    /// Enables LGAGST, which needs [`jump()`](Self::jump) or [`ducktap()`](Self::ducktap).
+    #[inline]
+    pub fn lgagst(mut self, times: impl Into<Times>) -> Self {
+        self.lgagst = Some((LeaveGroundActionSpeed::Optimal, times.into()));
+        self
+    }
+
+    /// Enables LGAGST ignoring maxspeed reduction, which needs [`jump()`](Self::jump) or
+    /// [`ducktap()`](Self::ducktap).
+    #[inline]
+    pub fn lgagst_full_maxspeed(mut self, times: impl Into<Times>) -> Self {
+        self.lgagst = Some((
+            LeaveGroundActionSpeed::OptimalWithFullMaxspeed,
+            times.into(),
+        ));
+        self
+    }
+
+    /// Enables autojump.
+    #[inline]
+    pub fn jump(mut self, times: impl Into<Times>) -> Self {
+        self.jump = Some(times.into());
+        self
+    }
+
+    /// Enables ducktap.
+    #[inline]
+    pub fn ducktap(mut self, times: impl Into<Times>) -> Self {
+        self.ducktap = Some((false, times.into()));
+        self
+    }
+
+    /// Enables 0 ms ducktap.
+    #[inline]
+    pub fn ducktap_0ms(mut self, times: impl Into<Times>) -> Self {
+        self.ducktap = Some((true, times.into()));
+        self
+    }
+
+    /// Enables jumpbug.
+    #[inline]
+    pub fn jump_bug(mut self, times: impl Into<Times>) -> Self {
+        self.jump_bug = Some(JumpBug {
+            times: times.into(),
+        });
+        self
+    }
+
+    /// Enables duck before collision, not including ceilings.
+    #[inline]
+    pub fn duck_before_collision(mut self, times: impl Into<Times>) -> Self {
+        self.duck_before_collision = Some(DuckBeforeCollision {
+            times: times.into(),
+            including_ceilings: false,
+        });
+        self
+    }
+
+    /// Enables duck before collision, including ceilings.
+    #[inline]
+    pub fn duck_before_collision_including_ceilings(mut self, times: impl Into<Times>) -> Self {
+        self.duck_before_collision = Some(DuckBeforeCollision {
+            times: times.into(),
+            including_ceilings: true,
+        });
+        self
+    }
+
+    /// Enables duck before ground.
+    #[inline]
+    pub fn duck_before_ground(mut self, times: impl Into<Times>) -> Self {
+        self.duck_before_ground = Some(DuckBeforeGround {
+            times: times.into(),
+        });
+        self
+    }
+
+    /// Enables duck when jump.
+    #[inline]
+    pub fn duck_when_jump(mut self, times: impl Into<Times>) -> Self {
+        self.duck_when_jump = Some(DuckWhenJump {
+            times: times.into(),
+        });
+        self
+    }
+
+    /// Builds the [`AutoActions`], checking them like the parser does.
+    pub fn build(self) -> Result<AutoActions, BuildError> {
+        let movement = match (self.strafe, self.yaw) {
+            (Some(_), Some(_)) => return Err(BuildError::BothYawAndStrafing),
+            (Some(settings), None) => Some(AutoMovement::Strafe(settings)),
+            (None, Some(yaw)) => Some(AutoMovement::SetYaw(yaw)),
+            (None, None) => None,
+        };
+        validate_movement(movement)?;
+
+        let type_ = match (self.jump, self.ducktap) {
+            (Some(_), Some(_)) => return Err(BuildError::BothAutoJumpAndDuckTap),
+            (Some(times), None) => Some((LeaveGroundActionType::Jump, times)),
+            (None, Some((zero_ms, times))) => {
+                Some((LeaveGroundActionType::DuckTap { zero_ms }, times))
+            }
+            (None, None) => None,
+        };
+
+        let leave_ground_action = match (self.lgagst, type_) {
+            (Some(_), None) => return Err(BuildError::NoLeaveGroundAction),
+            (Some(_), Some((_, Times::Limited(_)))) => {
+                return Err(BuildError::TimesOnLeaveGroundAction)
+            }
+            (Some((speed, times)), Some((type_, Times::UnlimitedWithinFrameBulk))) => {
+                Some(LeaveGroundAction {
+                    speed,
+                    times,
+                    type_,
+                })
+            }
+            (None, Some((type_, times))) => Some(LeaveGroundAction {
+                speed: LeaveGroundActionSpeed::Any,
+                times,
+                type_,
+            }),
+            (None, None) => None,
+        };
+
+        Ok(AutoActions {
+            movement,
+            leave_ground_action,
+            jump_bug: self.jump_bug,
+            duck_before_collision: self.duck_before_collision,
+            duck_before_ground: self.duck_before_ground,
+            duck_when_jump: self.duck_when_jump,
+        })
+    }
+}
+
+/// Builder for [`FrameBulk`].
+#[derive(Debug, Clone)]
+pub struct FrameBulkBuilder {
+    auto_actions: AutoActionsBuilder,
+    movement_keys: MovementKeys,
+    action_keys: ActionKeys,
+    frame_time: String,
+    pitch: Option<f32>,
+    frame_count: u32,
+    console_command: Option<String>,
+}
+
+impl FrameBulkBuilder {
+    /// Creates a builder for a frame bulk with one frame of the given frame time and nothing
+    /// else.
+    pub fn new(frame_time: impl Into<String>) -> Self {
+        Self {
+            auto_actions: AutoActionsBuilder::new(),
+            movement_keys: MovementKeys::default(),
+            action_keys: ActionKeys::default(),
+            frame_time: frame_time.into(),
+            pitch: None,
+            frame_count: 1,
+            console_command: None,
+        }
+    }
+
+    /// Replaces the auto-actions with the ones from the given builder.
+    #[inline]
+    pub fn auto_actions(mut self, auto_actions: AutoActionsBuilder) -> Self {
+        self.auto_actions = auto_actions;
+        self
+    }
+
+    /// Enables strafing. See [`AutoActionsBuilder::strafe()`].
+    #[inline]
+    pub fn strafe(mut self, type_: StrafeType, dir: StrafeDir) -> Self {
+        self.auto_actions = self.auto_actions.strafe(type_, dir);
+        self
+    }
+
+    /// Sets the yaw angle, without strafing.
+    #[inline]
+    pub fn yaw(mut self, yaw: f32) -> Self {
+        self.auto_actions = self.auto_actions.yaw(yaw);
+        self
+    }
+
+    /// Enables LGAGST. See [`AutoActionsBuilder::lgagst()`].
+    #[inline]
+    pub fn lgagst(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.lgagst(times);
+        self
+    }
+
+    /// Enables LGAGST ignoring maxspeed reduction. See
+    /// [`AutoActionsBuilder::lgagst_full_maxspeed()`].
+    #[inline]
+    pub fn lgagst_full_maxspeed(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.lgagst_full_maxspeed(times);
+        self
+    }
+
+    /// Enables autojump.
+    #[inline]
+    pub fn jump(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.jump(times);
+        self
+    }
+
+    /// Enables ducktap.
+    #[inline]
+    pub fn ducktap(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.ducktap(times);
+        self
+    }
+
+    /// Enables 0 ms ducktap.
+    #[inline]
+    pub fn ducktap_0ms(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.ducktap_0ms(times);
+        self
+    }
+
+    /// Enables jumpbug.
+    #[inline]
+    pub fn jump_bug(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.jump_bug(times);
+        self
+    }
+
+    /// Enables duck before collision, not including ceilings.
+    #[inline]
+    pub fn duck_before_collision(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.duck_before_collision(times);
+        self
+    }
+
+    /// Enables duck before collision, including ceilings.
+    #[inline]
+    pub fn duck_before_collision_including_ceilings(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self
+            .auto_actions
+            .duck_before_collision_including_ceilings(times);
+        self
+    }
+
+    /// Enables duck before ground.
+    #[inline]
+    pub fn duck_before_ground(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.duck_before_ground(times);
+        self
+    }
+
+    /// Enables duck when jump.
+    #[inline]
+    pub fn duck_when_jump(mut self, times: impl Into<Times>) -> Self {
+        self.auto_actions = self.auto_actions.duck_when_jump(times);
+        self
+    }
+
+    /// Sets the movement keys.
+    #[inline]
+    pub fn movement_keys(mut self, movement_keys: MovementKeys) -> Self {
+        self.movement_keys = movement_keys;
+        self
+    }
+
+    /// Sets the action keys.
+    #[inline]
+    pub fn action_keys(mut self, action_keys: ActionKeys) -> Self {
+        self.action_keys = action_keys;
+        self
+    }
+
+    /// Sets the pitch angle.
+    #[inline]
+    pub fn pitch(mut self, pitch: f32) -> Self {
+        self.pitch = Some(pitch);
+        self
+    }
+
+    /// Sets the number of frames.
+    #[inline]
+    pub fn frame_count(mut self, frame_count: u32) -> Self {
+        self.frame_count = frame_count;
+        self
+    }
+
+    /// Appends a console command, separated from the previous ones with `;`.
+    pub fn command(mut self, command: impl AsRef<str>) -> Self {
+        let command = command.as_ref();
+        match &mut self.console_command {
+            Some(commands) => {
+                commands.push(';');
+                commands.push_str(command);
+            }
+            None => self.console_command = Some(command.to_owned()),
+        }
+        self
+    }
+
+    /// Builds the [`FrameBulk`], checking it like the parser does.
+    pub fn build(self) -> Result<FrameBulk, BuildError> {
+        let frame_bulk = FrameBulk {
+            auto_actions: self.auto_actions.build()?,
+            movement_keys: self.movement_keys,
+            action_keys: self.action_keys,
+            frame_time: self.frame_time,
+            pitch: self.pitch,
+            frame_count: NonZeroU32::new(self.frame_count).ok_or(BuildError::ZeroFrameCount)?,
+            console_command: self.console_command,
+        };
+        validate_frame_bulk(&frame_bulk)?;
+        Ok(frame_bulk)
+    }
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::BothAutoJumpAndDuckTap => {
+                write!(f, "both autojump and ducktap are enabled")
+            }
+            BuildError::NoLeaveGroundAction => {
+                write!(f, "LGAGST is enabled without autojump or ducktap")
+            }
+            BuildError::TimesOnLeaveGroundAction => write!(
+                f,
+                "times are set on the autojump or ducktap used by LGAGST instead of on LGAGST"
+            ),
+            BuildError::BothYawAndStrafing => write!(f, "both a yaw angle and strafing are set"),
+            BuildError::ZeroFrameCount => write!(f, "the frame count is zero"),
+            BuildError::Invalid { field, problem } => write!(f, "invalid {}: {}", field, problem),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Line;
+
+    /// Checks that the frame bulk reads back the same.
+    fn round_trip(frame_bulk: FrameBulk) {
+        let line = Line::FrameBulk(frame_bulk);
+        let mut buf = Vec::new();
+        crate::write::gen_line(&mut buf, &line).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(crate::read::line(&text).unwrap().1, line, "{}", text);
+    }
+
+    #[test]
+    fn leave_ground_action() {
+        let aa = AutoActionsBuilder::new().lgagst(2).ducktap_0ms(0).build();
+        assert_eq!(
+            aa.unwrap().leave_ground_action,
+            Some(LeaveGroundAction {
+                speed: LeaveGroundActionSpeed::Optimal,
+                times: Times::from(2),
+                type_: LeaveGroundActionType::DuckTap { zero_ms: true },
+            })
+        );
+
+        let aa = AutoActionsBuilder::new().jump(3).build();
+        assert_eq!(
+            aa.unwrap().leave_ground_action,
+            Some(LeaveGroundAction {
+                speed: LeaveGroundActionSpeed::Any,
+                times: Times::from(3),
+                type_: LeaveGroundActionType::Jump,
+            })
+        );
+
+        let err = |builder: AutoActionsBuilder| builder.build().unwrap_err();
+        assert_eq!(
+            err(AutoActionsBuilder::new().jump(0).ducktap(0)),
+            BuildError::BothAutoJumpAndDuckTap
+        );
+        assert_eq!(
+            err(AutoActionsBuilder::new().lgagst_full_maxspeed(0)),
+            BuildError::NoLeaveGroundAction
+        );
+        assert_eq!(
+            err(AutoActionsBuilder::new().lgagst(0).jump(1)),
+            BuildError::TimesOnLeaveGroundAction
+        );
+    }
+
+    #[test]
+    fn movement() {
+        let err = |builder: AutoActionsBuilder| builder.build().unwrap_err();
+        assert_eq!(
+            err(AutoActionsBuilder::new()
+                .yaw(10.)
+                .strafe(StrafeType::MaxAccel, StrafeDir::Best)),
+            BuildError::BothYawAndStrafing
+        );
+        assert_eq!(
+            err(AutoActionsBuilder::new().strafe(
+                StrafeType::ConstYawspeed(10.),
+                StrafeDir::Point { x: 0., y: 0. }
+            )),
+            BuildError::Invalid {
+                field: Field::Strafing,
+                problem: Problem::UnsupportedConstantYawspeedDir,
+            }
+        );
+        assert_eq!(
+            err(AutoActionsBuilder::new().strafe(StrafeType::ConstYawspeed(-1.), StrafeDir::Left))
+                .context(),
+            Some(Context::NegativeYawspeed)
+        );
+    }
+
+    #[test]
+    fn frame_bulk() {
+        let frame_bulk = FrameBulkBuilder::new("0.004")
+            .strafe(StrafeType::MaxAccel, StrafeDir::Line { yaw: 45. })
+            .lgagst(0)
+            .jump(0)
+            .jump_bug(1)
+            .duck_before_collision_including_ceilings(0)
+            .duck_before_ground(2)
+            .duck_when_jump(0)
+            .movement_keys(MovementKeys {
+                forward: true,
+                ..MovementKeys::default()
+            })
+            .action_keys(ActionKeys {
+                use_: true,
+                ..ActionKeys::default()
+            })
+            .pitch(-10.)
+            .frame_count(50)
+            .command("echo a")
+            .command("echo b")
+            .build()
+            .unwrap();
+
+        assert_eq!(frame_bulk.frame_count.get(), 50);
+        assert_eq!(frame_bulk.console_command.as_deref(), Some("echo a;echo b"));
+        round_trip(frame_bulk);
+
+        round_trip(FrameBulkBuilder::new("0.001").yaw(90.).build().unwrap());
+        round_trip(
+            FrameBulkBuilder::new("0.001")
+                .strafe(StrafeType::ConstYawspeed(210.), StrafeDir::Right)
+                .ducktap(3)
+                .build()
+                .unwrap(),
+        );
+
+        assert_eq!(
+            FrameBulkBuilder::new("0.001").frame_count(0).build(),
+            Err(BuildError::ZeroFrameCount)
+        );
+        assert_eq!(
+            FrameBulkBuilder::new("fast").build().unwrap_err().context(),
+            Some(Context::InvalidFrameTime)
+        );
+        assert_eq!(
+            FrameBulkBuilder::new("0.001").command("a\nb").build(),
+            Err(BuildError::Invalid {
+                field: Field::ConsoleCommand,
+                problem: Problem::LineBreak,
+            })
+        );
+    }
+}
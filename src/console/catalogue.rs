@@ -0,0 +1,1119 @@
+//! Catalogue of known console commands and cvars.
+//!
+//! A [`Catalogue`] knows the argument shapes of commands and cvars, and checks [`Command`]s for
+//! wrong argument counts, non-numeric values where numbers are expected and names that are likely
+//! misspellings of known ones. Unknown names that aren't close to any known one are left alone,
+//! since they can be aliases or commands of a mod.
+//!
+//! [`Catalogue::new()`] has common engine and Bunnymod XT commands and cvars. More can be added
+//! with [`Catalogue::extend_from_str()`], or from a file with [`Catalogue::from_path()`] and
+//! [`Catalogue::extend_from_path()`], in text with one entry per line:
+//!
+//! ```text
+//! // Comments start with //.
+//! command bxt_setpos number number number
+//! command record string [int]
+//! command echo string...
+//! cvar sensitivity number
+//! button attack
+//! ```
+//!
+//! - `command <name> <parameters>` takes the listed parameters: `int`, `number` or `string`.
+//!   Optional parameters are in brackets and go after the required ones. A last parameter ending
+//!   with `...` can repeat any number of times.
+//! - `cvar <name> <type>` takes the value of the given type, or nothing to print the value.
+//! - `button <name>` adds the `+name` and `-name` commands.
+//!
+//! Names are case-insensitive, like in the game. An entry replaces an earlier entry with the same
+//! name, so the built-in entries can be changed too.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::console::{catalogue::Catalogue, Command};
+//!
+//! let mut catalogue = Catalogue::new();
+//!
+//! let command = Command::parse("bxt_timer_strat").unwrap();
+//! let issues = catalogue.check(&command);
+//! assert_eq!(
+//!     issues[0].to_string(),
+//!     "unknown command `bxt_timer_strat`, did you mean `bxt_timer_start`?"
+//! );
+//!
+//! let command = Command::parse("bxt_setpos 128 -1880").unwrap();
+//! assert_eq!(
+//!     catalogue.check(&command)[0].to_string(),
+//!     "`bxt_setpos` takes 3 arguments, found 2"
+//! );
+//!
+//! // Add entries, for example read from a file.
+//! catalogue.extend_from_str("cvar my_speed number").unwrap();
+//! let command = Command::parse("my_speed fast").unwrap();
+//! assert_eq!(
+//!     catalogue.check(&command)[0].to_string(),
+//!     "argument 1 of `my_speed` must be a number, found `fast`"
+//! );
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use crate::{console::Command, HLTAS};
+
+/// The built-in entries, in the format of [`Catalogue::extend_from_str()`].
+const BUILTIN: &str = "\
+// Engine commands.
+command alias [string] string...
+command bind string [string]
+command cancelselect
+command changelevel string [string]
+command cmd string...
+command condebug
+command connect string
+command disconnect
+command echo string...
+command exec string
+command give string
+command god
+command impulse int
+command invnext
+command invprev
+command kill
+command lastinv
+command load string
+command map string
+command noclip
+command notarget
+command pause
+command playdemo string
+command quit
+command exit
+command record string [int]
+command reload
+command restart
+command save string
+command say string...
+command screenshot
+command setpause
+command slot1
+command slot2
+command slot3
+command slot4
+command slot5
+command slot6
+command slot7
+command slot8
+command slot9
+command slot10
+command snapshot
+command stop
+command timedemo string
+command toggleconsole
+command unbind string
+command unbindall
+command unpause
+command unsetpause
+command wait
+
+// Half-Life weapons.
+command weapon_357
+command weapon_9mmar
+command weapon_9mmhandgun
+command weapon_crossbow
+command weapon_crowbar
+command weapon_egon
+command weapon_gauss
+command weapon_glock
+command weapon_handgrenade
+command weapon_hornetgun
+command weapon_mp5
+command weapon_rpg
+command weapon_satchel
+command weapon_shotgun
+command weapon_snark
+command weapon_tripmine
+
+// Engine and game cvars.
+cvar cl_backspeed number
+cvar cl_bob number
+cvar cl_forwardspeed number
+cvar cl_pitchspeed number
+cvar cl_showfps int
+cvar cl_sidespeed number
+cvar cl_upspeed number
+cvar cl_yawspeed number
+cvar coop int
+cvar crosshair int
+cvar deathmatch int
+cvar default_fov number
+cvar developer int
+cvar edgefriction number
+cvar fps_max number
+cvar fps_override int
+cvar host_framerate number
+cvar hud_draw number
+cvar hud_fastswitch int
+cvar m_pitch number
+cvar m_yaw number
+cvar mp_footsteps int
+cvar net_graph int
+cvar r_norefresh int
+cvar sensitivity number
+cvar skill int
+cvar sv_accelerate number
+cvar sv_airaccelerate number
+cvar sv_bounce number
+cvar sv_cheats int
+cvar sv_friction number
+cvar sv_gravity number
+cvar sv_maxspeed number
+cvar sv_maxvelocity number
+cvar sv_rollangle number
+cvar sv_stepsize number
+cvar sv_stopspeed number
+cvar sv_wateraccelerate number
+cvar sv_waterfriction number
+cvar sys_ticrate number
+cvar volume number
+cvar zoom_sensitivity_ratio number
+
+// Buttons.
+button alt1
+button attack
+button attack2
+button back
+button duck
+button forward
+button jlook
+button jump
+button klook
+button left
+button lookdown
+button lookup
+button mlook
+button movedown
+button moveleft
+button moveright
+button moveup
+button reload
+button right
+button showscores
+button speed
+button strafe
+button use
+
+// Bunnymod XT commands.
+command _bxt_input int
+command _bxt_input_edit int
+command _bxt_reset_frametime_remainder
+command bxt_append string
+command bxt_autorecord string
+command bxt_cap_start
+command bxt_cap_stop
+command bxt_ch_set_armor number
+command bxt_ch_set_health number
+command bxt_ch_set_pos number number number
+command bxt_ch_set_vel number number number
+command bxt_ch_teleport_to_entity int
+command bxt_record string
+command bxt_resetplayer
+command bxt_set_angles number number [number]
+command bxt_setpos number number number
+command bxt_tas_editor int
+command bxt_tas_exportscript string
+command bxt_tas_loadscript string
+command bxt_tas_split string
+command bxt_taslog
+command bxt_timer_reset
+command bxt_timer_start
+command bxt_timer_stop
+command w int
+
+// Bunnymod XT cvars.
+cvar _bxt_min_frametime number
+cvar _bxt_norefresh int
+cvar _bxt_taslog int
+cvar bxt_anglespeed_cap int
+cvar bxt_autojump int
+cvar bxt_autopause int
+cvar bxt_bhopcap int
+cvar bxt_collision_depth_map int
+cvar bxt_disable_autosave int
+cvar bxt_disable_changelevel int
+cvar bxt_disable_hud int
+cvar bxt_fade_remove int
+cvar bxt_force_zmax number
+cvar bxt_hfr_multiplayer_check int
+cvar bxt_hud int
+cvar bxt_hud_armor int
+cvar bxt_hud_distance int
+cvar bxt_hud_health int
+cvar bxt_hud_jumpspeed int
+cvar bxt_hud_origin int
+cvar bxt_hud_speedometer int
+cvar bxt_hud_timer int
+cvar bxt_hud_useables int
+cvar bxt_hud_velocity int
+cvar bxt_interprocess_enable int
+cvar bxt_novis int
+cvar bxt_shake_remove int
+cvar bxt_show_nodes int
+cvar bxt_show_triggers int
+cvar bxt_speed_scaling int
+cvar bxt_tas_norefresh_until_last_frames int
+cvar bxt_tas_playback_speed number
+cvar bxt_timer_autostop int
+cvar bxt_wallhack int
+";
+
+/// Known console commands and cvars with their argument shapes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalogue {
+    /// Entries by lowercase name.
+    entries: BTreeMap<String, Entry>,
+}
+
+/// A known console command or cvar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// A command with the given parameters.
+    Command(Signature),
+    /// A cvar holding a value of the given type.
+    Cvar(ArgType),
+    /// A `+` or `-` button command.
+    Button,
+}
+
+/// Parameters of a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Signature {
+    /// Types of the required parameters.
+    pub required: Vec<ArgType>,
+    /// Types of the optional parameters, which come after the required ones.
+    pub optional: Vec<ArgType>,
+    /// Type of the parameter that can repeat any number of times at the end.
+    pub rest: Option<ArgType>,
+}
+
+/// Type of an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArgType {
+    /// A whole number, like `-1`.
+    Integer,
+    /// A number, like `0.5`.
+    Number,
+    /// Any string.
+    String,
+}
+
+/// A problem with a command found by [`Catalogue::check()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The command is unknown, but close to a known one.
+    Misspelled {
+        /// The command name.
+        name: String,
+        /// The known name it's close to.
+        suggestion: String,
+    },
+    /// The command has the wrong number of arguments.
+    ArgumentCount {
+        /// The command name.
+        name: String,
+        /// The smallest number of arguments the command takes.
+        min: usize,
+        /// The largest number of arguments the command takes, if it's limited.
+        max: Option<usize>,
+        /// The number of arguments given.
+        found: usize,
+    },
+    /// An argument has the wrong type.
+    InvalidArgument {
+        /// The command name.
+        name: String,
+        /// Index of the argument, starting from 0 for the first argument after the name.
+        index: usize,
+        /// The argument.
+        value: String,
+        /// The type the argument should have.
+        expected: ArgType,
+    },
+}
+
+/// Error returned when parsing catalogue entries fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogueError {
+    /// Line number of the invalid entry, starting from 1.
+    pub line: usize,
+    /// What's wrong with the entry.
+    pub kind: CatalogueErrorKind,
+}
+
+/// Error returned by [`Catalogue::from_path()`] and [`Catalogue::extend_from_path()`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// An entry in the file is invalid.
+    Parse(CatalogueError),
+}
+
+/// What's wrong with a catalogue entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogueErrorKind {
+    /// The entry doesn't start with `command`, `cvar` or `button`.
+    UnknownKind(String),
+    /// The entry has no name.
+    MissingName,
+    /// A parameter type isn't `int`, `number` or `string`.
+    UnknownType(String),
+    /// A required parameter comes after an optional one, or a parameter comes after a repeating
+    /// one.
+    MisplacedParameter(String),
+    /// A cvar doesn't have exactly one type.
+    CvarType,
+    /// A button has parameters.
+    ButtonParameters,
+}
+
+impl ArgType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(ArgType::Integer),
+            "number" => Some(ArgType::Number),
+            "string" => Some(ArgType::String),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the argument is a valid value of this type.
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            ArgType::Integer => {
+                let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+                !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+            }
+            ArgType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            ArgType::String => true,
+        }
+    }
+}
+
+/// Returns the optimal string alignment distance between two strings: the number of inserted,
+/// deleted or substituted characters and swapped adjacent characters needed to turn one into the
+/// other.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Rows of the distance matrix for the previous two and the current prefix of `a`.
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = previous;
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Default for Catalogue {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Catalogue {
+    /// Creates a catalogue with the built-in engine and Bunnymod XT entries.
+    pub fn new() -> Self {
+        let mut catalogue = Self::empty();
+        catalogue.extend_from_str(BUILTIN).unwrap();
+        catalogue
+    }
+
+    /// Creates a catalogue with the built-in entries and the entries in the file at `path`.
+    ///
+    /// The file has the format of [`Catalogue::extend_from_str()`], and its entries replace the
+    /// built-in entries with the same name.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let mut catalogue = Self::new();
+        catalogue.extend_from_path(path)?;
+        Ok(catalogue)
+    }
+
+    /// Creates a catalogue without any entries.
+    pub fn empty() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Adds an entry, replacing an entry with the same name if there is one.
+    ///
+    /// Buttons are added without the `+` or `-`.
+    pub fn insert(&mut self, name: &str, entry: Entry) {
+        let name = name.to_ascii_lowercase();
+        if entry == Entry::Button {
+            self.entries.insert(format!("+{}", name), Entry::Button);
+            self.entries.insert(format!("-{}", name), Entry::Button);
+        } else {
+            self.entries.insert(name, entry);
+        }
+    }
+
+    /// Adds entries from text with one entry per line.
+    ///
+    /// See the [module documentation](self) for the format. Nothing is added if there's an error.
+    pub fn extend_from_str(&mut self, text: &str) -> Result<(), CatalogueError> {
+        let mut entries = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let error = |kind| CatalogueError {
+                line: index + 1,
+                kind,
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let kind = words.next().unwrap();
+            let name = words
+                .next()
+                .ok_or_else(|| error(CatalogueErrorKind::MissingName))?;
+            let parameters: Vec<&str> = words.collect();
+
+            let entry = match kind {
+                "command" => {
+                    let mut signature = Signature::default();
+                    for &parameter in &parameters {
+                        let (type_name, optional, rest) =
+                            if let Some(type_name) = parameter.strip_suffix("...") {
+                                (type_name, false, true)
+                            } else if let Some(type_name) = parameter
+                                .strip_prefix('[')
+                                .and_then(|x| x.strip_suffix(']'))
+                            {
+                                (type_name, true, false)
+                            } else {
+                                (parameter, false, false)
+                            };
+
+                        let type_ = ArgType::from_name(type_name).ok_or_else(|| {
+                            error(CatalogueErrorKind::UnknownType(type_name.to_owned()))
+                        })?;
+
+                        let misplaced = signature.rest.is_some()
+                            || (!optional && !rest && !signature.optional.is_empty());
+                        if misplaced {
+                            return Err(error(CatalogueErrorKind::MisplacedParameter(
+                                parameter.to_owned(),
+                            )));
+                        }
+
+                        if rest {
+                            signature.rest = Some(type_);
+                        } else if optional {
+                            signature.optional.push(type_);
+                        } else {
+                            signature.required.push(type_);
+                        }
+                    }
+                    Entry::Command(signature)
+                }
+                "cvar" => match parameters[..] {
+                    [type_name] => Entry::Cvar(ArgType::from_name(type_name).ok_or_else(|| {
+                        error(CatalogueErrorKind::UnknownType(type_name.to_owned()))
+                    })?),
+                    _ => return Err(error(CatalogueErrorKind::CvarType)),
+                },
+                "button" => {
+                    if !parameters.is_empty() {
+                        return Err(error(CatalogueErrorKind::ButtonParameters));
+                    }
+                    Entry::Button
+                }
+                _ => return Err(error(CatalogueErrorKind::UnknownKind(kind.to_owned()))),
+            };
+
+            entries.push((name, entry));
+        }
+
+        for (name, entry) in entries {
+            self.insert(name, entry);
+        }
+        Ok(())
+    }
+
+    /// Adds the entries in the file at `path`, see [`Catalogue::extend_from_str()`].
+    ///
+    /// Nothing is added if reading the file fails or any of its entries is invalid.
+    pub fn extend_from_path(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        let text = fs::read_to_string(path)?;
+        self.extend_from_str(&text)?;
+        Ok(())
+    }
+
+    /// Returns the entry for the command or cvar with the given name.
+    ///
+    /// Buttons are found with the `+` or `-`.
+    pub fn get(&self, name: &str) -> Option<&Entry> {
+        self.entries.get(&name.to_ascii_lowercase())
+    }
+
+    /// Returns the known name closest to the given unknown name, if it's close enough to be a
+    /// likely misspelling.
+    ///
+    /// Names shorter than 4 characters are never considered misspelled, and longer names can
+    /// differ by one character, or by two starting from 8 characters.
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        let length = name.chars().count();
+        if length < 4 || self.entries.contains_key(&name) {
+            return None;
+        }
+
+        let max_distance = if length < 8 { 1 } else { 2 };
+        self.entries
+            .keys()
+            .filter(|known| known.chars().count().abs_diff(length) <= max_distance)
+            .map(|known| (distance(&name, known), known))
+            .filter(|&(distance, _)| distance <= max_distance)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, known)| known.as_str())
+    }
+
+    /// Checks a command against the catalogue.
+    ///
+    /// Empty and commented-out commands and unknown commands that aren't close to a known one have
+    /// no issues.
+    pub fn check(&self, command: &Command) -> Vec<Issue> {
+        let tokens = command.tokens();
+        let (name, args) = match tokens.split_first() {
+            Some((name, args)) => (name.value, args),
+            None => return Vec::new(),
+        };
+
+        let cvar_signature;
+        let signature = match self.get(name) {
+            Some(Entry::Command(signature)) => signature,
+            Some(&Entry::Cvar(type_)) => {
+                cvar_signature = Signature {
+                    optional: vec![type_],
+                    ..Signature::default()
+                };
+                &cvar_signature
+            }
+            Some(Entry::Button) => return Vec::new(),
+            None => {
+                return self
+                    .suggest(name)
+                    .map(|suggestion| Issue::Misspelled {
+                        name: name.to_owned(),
+                        suggestion: suggestion.to_owned(),
+                    })
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        let min = signature.required.len();
+        let max = signature
+            .rest
+            .is_none()
+            .then_some(min + signature.optional.len());
+        if args.len() < min || max.is_some_and(|max| args.len() > max) {
+            return vec![Issue::ArgumentCount {
+                name: name.to_owned(),
+                min,
+                max,
+                found: args.len(),
+            }];
+        }
+
+        let types = signature
+            .required
+            .iter()
+            .chain(&signature.optional)
+            .chain(signature.rest.iter().cycle());
+        args.iter()
+            .zip(types)
+            .enumerate()
+            .filter(|(_, (arg, type_))| !type_.accepts(arg.value))
+            .map(|(index, (arg, &expected))| Issue::InvalidArgument {
+                name: name.to_owned(),
+                index,
+                value: arg.value.to_owned(),
+                expected,
+            })
+            .collect()
+    }
+
+    /// Checks the console commands of all frame bulks, returning the issues with the indices of
+    /// the frame bulks in [`HLTAS::lines`].
+    ///
+    /// Names defined with `alias` anywhere in the script are never considered misspelled.
+    pub fn check_hltas(&self, hltas: &HLTAS) -> Vec<(usize, Issue)> {
+        let console_commands: Vec<_> = hltas
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_index, line)| {
+                let frame_bulk = line.frame_bulk()?;
+                frame_bulk.console_command.as_ref()?;
+                Some((line_index, frame_bulk.console_commands()))
+            })
+            .collect();
+
+        let aliases: HashSet<String> = console_commands
+            .iter()
+            .flat_map(|(_, console_command)| console_command.commands())
+            .filter(|command| command.name() == Some("alias"))
+            .filter_map(|command| command.args().next().map(str::to_ascii_lowercase))
+            .collect();
+
+        let mut issues = Vec::new();
+        for (line_index, console_command) in &console_commands {
+            for command in console_command.commands() {
+                for issue in self.check(command) {
+                    if let Issue::Misspelled { name, .. } = &issue {
+                        if aliases.contains(&name.to_ascii_lowercase()) {
+                            continue;
+                        }
+                    }
+                    issues.push((*line_index, issue));
+                }
+            }
+        }
+        issues
+    }
+}
+
+impl Display for ArgType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgType::Integer => write!(f, "an integer"),
+            ArgType::Number => write!(f, "a number"),
+            ArgType::String => write!(f, "a string"),
+        }
+    }
+}
+
+/// Returns "1 argument" or "`count` arguments".
+fn arguments(count: usize) -> String {
+    if count == 1 {
+        "1 argument".to_owned()
+    } else {
+        format!("{} arguments", count)
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Misspelled { name, suggestion } => write!(
+                f,
+                "unknown command `{}`, did you mean `{}`?",
+                name, suggestion
+            ),
+            Issue::ArgumentCount {
+                name,
+                min,
+                max,
+                found,
+            } => {
+                let expected = match *max {
+                    Some(max) if max == *min => arguments(max),
+                    Some(max) if *min == 0 => format!("at most {}", arguments(max)),
+                    Some(max) => format!("{} to {}", min, arguments(max)),
+                    None => format!("at least {}", arguments(*min)),
+                };
+                write!(f, "`{}` takes {}, found {}", name, expected, found)
+            }
+            Issue::InvalidArgument {
+                name,
+                index,
+                value,
+                expected,
+            } => write!(
+                f,
+                "argument {} of `{}` must be {}, found `{}`",
+                index + 1,
+                name,
+                expected,
+                value
+            ),
+        }
+    }
+}
+
+impl Display for CatalogueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            CatalogueErrorKind::UnknownKind(kind) => write!(
+                f,
+                "unknown entry kind `{}`, expected `command`, `cvar` or `button`",
+                kind
+            ),
+            CatalogueErrorKind::MissingName => write!(f, "missing name"),
+            CatalogueErrorKind::UnknownType(type_) => write!(
+                f,
+                "unknown parameter type `{}`, expected `int`, `number` or `string`",
+                type_
+            ),
+            CatalogueErrorKind::MisplacedParameter(parameter) => write!(
+                f,
+                "parameter `{}` can't come after optional or repeating parameters",
+                parameter
+            ),
+            CatalogueErrorKind::CvarType => write!(f, "a cvar needs exactly one type"),
+            CatalogueErrorKind::ButtonParameters => write!(f, "a button can't have parameters"),
+        }
+    }
+}
+
+impl error::Error for CatalogueError {}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "error reading the catalogue: {}", e),
+            LoadError::Parse(e) => write!(f, "invalid catalogue entry: {}", e),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    #[inline]
+    fn from(x: io::Error) -> Self {
+        Self::Io(x)
+    }
+}
+
+impl From<CatalogueError> for LoadError {
+    #[inline]
+    fn from(x: CatalogueError) -> Self {
+        Self::Parse(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::for_each_test_script;
+
+    fn check(catalogue: &Catalogue, text: &str) -> Vec<Issue> {
+        catalogue.check(&Command::parse(text).unwrap())
+    }
+
+    #[test]
+    fn builtin_parses() {
+        let catalogue = Catalogue::new();
+        assert_eq!(
+            catalogue.get("stop"),
+            Some(&Entry::Command(Signature::default()))
+        );
+        assert_eq!(
+            catalogue.get("SENSITIVITY"),
+            Some(&Entry::Cvar(ArgType::Number))
+        );
+        assert_eq!(catalogue.get("+attack"), Some(&Entry::Button));
+        assert_eq!(catalogue.get("-attack"), Some(&Entry::Button));
+        assert_eq!(catalogue.get("attack"), None);
+    }
+
+    #[test]
+    fn distance_() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("stop", "stop"), 0);
+        assert_eq!(distance("stpo", "stop"), 1);
+        assert_eq!(distance("sotp", "stop"), 1);
+        assert_eq!(distance("sop", "stop"), 1);
+        assert_eq!(distance("stopp", "stop"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn misspellings() {
+        let catalogue = Catalogue::new();
+
+        assert_eq!(
+            check(&catalogue, "bxt_timer_strat"),
+            [Issue::Misspelled {
+                name: "bxt_timer_strat".to_owned(),
+                suggestion: "bxt_timer_start".to_owned(),
+            }]
+        );
+        assert_eq!(
+            check(&catalogue, "+atack"),
+            [Issue::Misspelled {
+                name: "+atack".to_owned(),
+                suggestion: "+attack".to_owned(),
+            }]
+        );
+        assert_eq!(
+            check(&catalogue, "sensitivty 1"),
+            [Issue::Misspelled {
+                name: "sensitivty".to_owned(),
+                suggestion: "sensitivity".to_owned(),
+            }]
+        );
+
+        // Short or far names are left alone.
+        assert!(check(&catalogue, "stp").is_empty());
+        assert!(check(&catalogue, "my_alias").is_empty());
+        assert!(check(&catalogue, "weapon_knife").is_empty());
+        assert!(check(&catalogue, "STOP").is_empty());
+        assert!(check(&catalogue, "//stpo").is_empty());
+    }
+
+    #[test]
+    fn arguments() {
+        let catalogue = Catalogue::new();
+
+        assert!(check(&catalogue, "bxt_setpos 128 -1880 -536.5").is_empty());
+        assert_eq!(
+            check(&catalogue, "bxt_setpos 128 -1880"),
+            [Issue::ArgumentCount {
+                name: "bxt_setpos".to_owned(),
+                min: 3,
+                max: Some(3),
+                found: 2,
+            }]
+        );
+        assert_eq!(
+            check(&catalogue, "bxt_setpos 128 x y"),
+            [
+                Issue::InvalidArgument {
+                    name: "bxt_setpos".to_owned(),
+                    index: 1,
+                    value: "x".to_owned(),
+                    expected: ArgType::Number,
+                },
+                Issue::InvalidArgument {
+                    name: "bxt_setpos".to_owned(),
+                    index: 2,
+                    value: "y".to_owned(),
+                    expected: ArgType::Number,
+                },
+            ]
+        );
+
+        assert!(check(&catalogue, "sensitivity").is_empty());
+        assert!(check(&catalogue, "sensitivity \"0.5\"").is_empty());
+        assert_eq!(check(&catalogue, "sensitivity 1 2").len(), 1);
+        assert_eq!(
+            check(&catalogue, "impulse 1.5"),
+            [Issue::InvalidArgument {
+                name: "impulse".to_owned(),
+                index: 0,
+                value: "1.5".to_owned(),
+                expected: ArgType::Integer,
+            }]
+        );
+        assert_eq!(check(&catalogue, "sensitivity nan").len(), 1);
+
+        assert!(check(&catalogue, "record demo").is_empty());
+        assert!(check(&catalogue, "record demo 2").is_empty());
+        assert_eq!(check(&catalogue, "record").len(), 1);
+        assert!(check(&catalogue, "echo").is_empty());
+        assert!(check(&catalogue, "echo a b c").is_empty());
+        assert!(check(&catalogue, "+attack 13").is_empty());
+    }
+
+    #[test]
+    fn messages() {
+        let catalogue = Catalogue::new();
+        let message = |text| check(&catalogue, text)[0].to_string();
+
+        assert_eq!(message("stop now"), "`stop` takes 0 arguments, found 1");
+        assert_eq!(message("impulse"), "`impulse` takes 1 argument, found 0");
+        assert_eq!(
+            message("volume 1 2"),
+            "`volume` takes at most 1 argument, found 2"
+        );
+        assert_eq!(
+            message("record"),
+            "`record` takes 1 to 2 arguments, found 0"
+        );
+        assert_eq!(
+            message("impulse x"),
+            "argument 1 of `impulse` must be an integer, found `x`"
+        );
+    }
+
+    #[test]
+    fn extend() {
+        let mut catalogue = Catalogue::empty();
+        catalogue
+            .extend_from_str(
+                "// Team commands.\n\
+                 \n\
+                 command team_setup int [number] [number] string...\n\
+                 cvar team_cvar string\n\
+                 button Team_Button\n",
+            )
+            .unwrap();
+
+        assert!(check(&catalogue, "team_setup 1").is_empty());
+        assert!(check(&catalogue, "team_setup 1 2 3 a b").is_empty());
+        assert_eq!(check(&catalogue, "team_setup 1 a").len(), 1);
+        assert!(check(&catalogue, "TEAM_CVAR anything").is_empty());
+        assert!(check(&catalogue, "+team_button").is_empty());
+
+        // Later entries replace earlier ones.
+        catalogue.extend_from_str("cvar team_cvar int").unwrap();
+        assert_eq!(check(&catalogue, "team_cvar anything").len(), 1);
+    }
+
+    #[test]
+    fn extend_errors() {
+        let mut catalogue = Catalogue::empty();
+        let error = |catalogue: &mut Catalogue, text| catalogue.extend_from_str(text).unwrap_err();
+
+        assert_eq!(
+            error(&mut catalogue, "cvar a int\nalias a b"),
+            CatalogueError {
+                line: 2,
+                kind: CatalogueErrorKind::UnknownKind("alias".to_owned()),
+            }
+        );
+        assert_eq!(
+            error(&mut catalogue, "command").kind,
+            CatalogueErrorKind::MissingName
+        );
+        assert_eq!(
+            error(&mut catalogue, "command a float").kind,
+            CatalogueErrorKind::UnknownType("float".to_owned())
+        );
+        assert_eq!(
+            error(&mut catalogue, "command a [int] int").kind,
+            CatalogueErrorKind::MisplacedParameter("int".to_owned())
+        );
+        assert_eq!(
+            error(&mut catalogue, "command a int... int").kind,
+            CatalogueErrorKind::MisplacedParameter("int".to_owned())
+        );
+        assert_eq!(
+            error(&mut catalogue, "cvar a").kind,
+            CatalogueErrorKind::CvarType
+        );
+        assert_eq!(
+            error(&mut catalogue, "button a int").kind,
+            CatalogueErrorKind::ButtonParameters
+        );
+
+        // Nothing is added on errors.
+        assert_eq!(catalogue, Catalogue::empty());
+    }
+
+    #[test]
+    fn aliases() {
+        let hltas = HLTAS::from_str(
+            "version 1\n\
+             frames\n\
+             ----------|------|------|0.001|-|-|1|alias bxt_timer_strat bxt_timer_start\n\
+             ----------|------|------|0.001|-|-|1|bxt_timer_strat;stpo 1;bxt_timer_stpo\n\
+             ----------|------|------|0.001|-|-|1\n\
+             ----------|------|------|0.001|-|-|1|impulse x",
+        )
+        .unwrap();
+
+        let issues: Vec<_> = Catalogue::new()
+            .check_hltas(&hltas)
+            .into_iter()
+            .map(|(line_index, issue)| (line_index, issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (1, "unknown command `stpo`, did you mean `stop`?".to_owned()),
+                (
+                    1,
+                    "unknown command `bxt_timer_stpo`, did you mean `bxt_timer_stop`?".to_owned()
+                ),
+                (
+                    3,
+                    "argument 1 of `impulse` must be an integer, found `x`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_path() {
+        let catalogue = Catalogue::from_path("test-data/catalogue/custom.txt").unwrap();
+        assert_eq!(
+            catalogue.get("my_alias"),
+            Some(&Entry::Command(Signature {
+                required: vec![ArgType::Integer],
+                ..Signature::default()
+            }))
+        );
+        assert_eq!(catalogue.get("stop"), Some(&Entry::Cvar(ArgType::String)));
+        assert_eq!(catalogue.get("+my_jump"), Some(&Entry::Button));
+        assert_eq!(catalogue.get("+attack"), Some(&Entry::Button));
+
+        let mut catalogue = Catalogue::empty();
+        match catalogue.extend_from_path("test-data/catalogue/invalid.txt") {
+            Err(LoadError::Parse(e)) => assert_eq!(
+                e,
+                CatalogueError {
+                    line: 2,
+                    kind: CatalogueErrorKind::CvarType,
+                }
+            ),
+            result => panic!("expected a parse error, got {:?}", result),
+        }
+        assert_eq!(catalogue, Catalogue::empty());
+
+        assert!(matches!(
+            Catalogue::from_path("test-data/catalogue/nonexistent.txt"),
+            Err(LoadError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_data_has_no_misspellings() {
+        let catalogue = Catalogue::new();
+        for_each_test_script(|_, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+            let misspellings: Vec<_> = catalogue
+                .check_hltas(&hltas)
+                .into_iter()
+                .filter(|(_, issue)| matches!(issue, Issue::Misspelled { .. }))
+                .collect();
+            assert_eq!(misspellings, []);
+        });
+    }
+}
@@ -0,0 +1,635 @@
+//! Console commands of frame bulks.
+//!
+//! [`FrameBulk::console_command`] stores the console command as written. [`ConsoleCommand`] splits
+//! it into individual [`Command`]s with their names and arguments the way the GoldSrc command
+//! buffer does, and writes it back byte-for-byte, so commands can be added and removed without
+//! touching the rest:
+//! - Commands are separated by `;`, except inside quotes and after a `//`. Such a `//` turns the
+//!   rest of the console command into a single command, so nothing after it runs as a separate
+//!   command.
+//! - Tokens are separated by whitespace. A token in quotes can contain whitespace, `;` and `//`;
+//!   there's no way to escape a quote. An unterminated quote runs until the end of the command.
+//! - A `//` at the start of a token comments out the rest of the command.
+//! - The first token is the command name. Names starting with `+` or `-`, like `+attack`, press
+//!   and release a button.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::console::{Command, ConsoleCommand};
+//!
+//! let mut console_command = ConsoleCommand::parse("bxt_setpos 128 -1880 -536;_bxt_norefresh 1");
+//!
+//! let commands = console_command.commands();
+//! assert_eq!(commands.len(), 2);
+//! assert_eq!(commands[0].name(), Some("bxt_setpos"));
+//! assert_eq!(commands[0].args().collect::<Vec<_>>(), ["128", "-1880", "-536"]);
+//!
+//! console_command.remove(1);
+//! console_command
+//!     .push(Command::new("echo", &["hello world"]).unwrap())
+//!     .unwrap();
+//! assert_eq!(
+//!     console_command.to_string(),
+//!     "bxt_setpos 128 -1880 -536;echo \"hello world\""
+//! );
+//!
+//! // Commented-out commands have no name.
+//! let console_command = ConsoleCommand::parse("//r_norefresh 1");
+//! assert_eq!(console_command.commands()[0].name(), None);
+//! assert_eq!(console_command.commands()[0].comment(), Some("//r_norefresh 1"));
+//! ```
+//!
+//! [`FrameBulk::console_command`]: crate::types::FrameBulk::console_command
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+pub mod catalogue;
+
+/// A console command split into individual commands.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ConsoleCommand {
+    commands: Vec<Command>,
+}
+
+/// A single command of a [`ConsoleCommand`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// Text of the command as written, without the separating `;`.
+    text: String,
+}
+
+/// A token of a [`Command`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token<'a> {
+    /// The token without the quotes.
+    pub value: &'a str,
+    /// Whether the token is in quotes.
+    pub quoted: bool,
+    /// Byte range of the token in [`Command::text()`], including the quotes.
+    pub span: Range<usize>,
+}
+
+/// Prefix of a command name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Prefix {
+    /// `+`, pressing a button.
+    Plus,
+    /// `-`, releasing a button.
+    Minus,
+}
+
+/// Error returned when creating or adding a [`Command`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError {
+    /// The text contains a `;` outside of quotes, which would split it into several commands.
+    MultipleCommands,
+    /// The text contains a line break, which can't be part of a frame bulk.
+    LineBreak,
+    /// An argument contains a `"`, which can't be quoted.
+    Quote,
+    /// A command can't come after a command with a `//` outside of quotes, which would make them
+    /// a single command.
+    AfterComment,
+}
+
+/// Returns the byte offset of the first `//` outside of quotes.
+///
+/// The command buffer doesn't split commands at `;` after it.
+fn find_comment_marker(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut in_quotes = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b'/' if !in_quotes && bytes.get(i + 1) == Some(&b'/') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the byte ranges of the commands in a console command.
+fn split(text: &str) -> Vec<Range<usize>> {
+    let end = find_comment_marker(text).unwrap_or(text.len());
+    let mut ranges = split_at_semicolons(&text[..end]);
+    // The comment runs until the end of the text.
+    ranges.last_mut().unwrap().end = text.len();
+    ranges
+}
+
+/// Returns the byte ranges of the parts of the text between `;` outside of quotes.
+///
+/// Unlike the command buffer, this doesn't stop splitting at a `//`.
+pub(crate) fn split_at_semicolons(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (i, &b) in text.as_bytes().iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b';' if !in_quotes => {
+                ranges.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ranges.push(start..text.len());
+    ranges
+}
+
+/// Returns whether the byte is whitespace to the tokenizer.
+fn is_whitespace(b: u8) -> bool {
+    b <= b' '
+}
+
+/// Returns the tokens of a command and the start of the comment, if there is one.
+fn tokenize(text: &str) -> (Vec<Token<'_>>, Option<usize>) {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < bytes.len() && is_whitespace(bytes[i]) {
+            i += 1;
+        }
+        if i == bytes.len() {
+            return (tokens, None);
+        }
+        if bytes[i..].starts_with(b"//") {
+            return (tokens, Some(i));
+        }
+
+        let start = i;
+        if bytes[i] == b'"' {
+            let end = text[i + 1..].find('"').map_or(text.len(), |x| i + 1 + x);
+            i = (end + 1).min(text.len());
+            tokens.push(Token {
+                value: &text[start + 1..end],
+                quoted: true,
+                span: start..i,
+            });
+        } else {
+            while i < bytes.len() && !is_whitespace(bytes[i]) {
+                i += 1;
+            }
+            tokens.push(Token {
+                value: &text[start..i],
+                quoted: false,
+                span: start..i,
+            });
+        }
+    }
+}
+
+impl ConsoleCommand {
+    /// Splits a console command into commands.
+    ///
+    /// Every string is a valid console command. An empty string has no commands.
+    pub fn parse(text: &str) -> Self {
+        if text.is_empty() {
+            return Self::default();
+        }
+
+        Self {
+            commands: split(text)
+                .into_iter()
+                .map(|range| Command {
+                    text: text[range].to_owned(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the commands, including empty and commented-out ones.
+    #[inline]
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Returns the names of the commands, skipping empty and commented-out ones.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().filter_map(Command::name)
+    }
+
+    /// Returns `true` if there are no commands.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Adds a command at the end.
+    ///
+    /// A trailing empty command, like after a final `;`, is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CommandError::AfterComment`] if the last command has a `//` outside of quotes.
+    pub fn push(&mut self, command: Command) -> Result<(), CommandError> {
+        if self
+            .commands
+            .last()
+            .is_some_and(|last| last.text.bytes().all(is_whitespace))
+        {
+            self.commands.pop();
+        }
+
+        self.insert(self.commands.len(), command)
+    }
+
+    /// Inserts a command at the given index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CommandError::AfterComment`] if the command would come after a command with a
+    /// `//` outside of quotes, or if it has one itself and wouldn't be the last command.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of commands.
+    pub fn insert(&mut self, index: usize, command: Command) -> Result<(), CommandError> {
+        assert!(index <= self.commands.len());
+
+        let previous_has_comment =
+            index > 0 && find_comment_marker(&self.commands[index - 1].text).is_some();
+        let comment_not_last =
+            index < self.commands.len() && find_comment_marker(&command.text).is_some();
+        if previous_has_comment || comment_not_last {
+            return Err(CommandError::AfterComment);
+        }
+
+        self.commands.insert(index, command);
+        Ok(())
+    }
+
+    /// Removes and returns the command at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn remove(&mut self, index: usize) -> Command {
+        self.commands.remove(index)
+    }
+
+    /// Keeps only the commands for which the predicate returns `true`.
+    #[inline]
+    pub fn retain(&mut self, f: impl FnMut(&Command) -> bool) {
+        self.commands.retain(f)
+    }
+}
+
+impl Display for ConsoleCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            f.write_str(&command.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl Command {
+    /// Creates a command from its name and arguments, quoting them where needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or an argument contains a line break or a `"` that can't be
+    /// quoted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::console::Command;
+    ///
+    /// let command = Command::new("bind", &["f", "echo a; echo b"]).unwrap();
+    /// assert_eq!(command.text(), "bind f \"echo a; echo b\"");
+    /// assert_eq!(command.args().collect::<Vec<_>>(), ["f", "echo a; echo b"]);
+    /// ```
+    pub fn new(name: &str, args: &[&str]) -> Result<Self, CommandError> {
+        let mut text = String::new();
+        for (i, token) in Some(&name).into_iter().chain(args).enumerate() {
+            if token.contains(['\n', '\r']) {
+                return Err(CommandError::LineBreak);
+            }
+
+            if i > 0 {
+                text.push(' ');
+            }
+
+            let needs_quotes = token.is_empty()
+                || token.bytes().any(|b| is_whitespace(b) || b == b';')
+                || token.contains("//");
+            if token.contains('"') {
+                // Unquoted tokens can contain quotes, but they affect where commands split.
+                if needs_quotes
+                    || token.starts_with('"')
+                    || token.bytes().filter(|&b| b == b'"').count() % 2 == 1
+                {
+                    return Err(CommandError::Quote);
+                }
+                text.push_str(token);
+            } else if needs_quotes {
+                text.push('"');
+                text.push_str(token);
+                text.push('"');
+            } else {
+                text.push_str(token);
+            }
+        }
+
+        Ok(Self { text })
+    }
+
+    /// Creates a command from its text as written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text contains a line break or more than one command.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::console::{Command, CommandError};
+    ///
+    /// let command = Command::parse("+attack").unwrap();
+    /// assert_eq!(command.name(), Some("+attack"));
+    ///
+    /// assert_eq!(Command::parse("stop;quit"), Err(CommandError::MultipleCommands));
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, CommandError> {
+        if text.contains(['\n', '\r']) {
+            return Err(CommandError::LineBreak);
+        }
+        if split(text).len() > 1 {
+            return Err(CommandError::MultipleCommands);
+        }
+
+        Ok(Self {
+            text: text.to_owned(),
+        })
+    }
+
+    /// Returns the text of the command as written.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the tokens of the command, up to the comment.
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        tokenize(&self.text).0
+    }
+
+    /// Returns the command name, or `None` if the command is empty or commented out.
+    pub fn name(&self) -> Option<&str> {
+        self.tokens().first().map(|token| token.value)
+    }
+
+    /// Returns the command name without the `+` or `-` prefix.
+    pub fn base_name(&self) -> Option<&str> {
+        self.name()
+            .map(|name| name.strip_prefix(['+', '-']).unwrap_or(name))
+    }
+
+    /// Returns the prefix of the command name.
+    pub fn prefix(&self) -> Option<Prefix> {
+        match self.name()?.as_bytes().first() {
+            Some(b'+') => Some(Prefix::Plus),
+            Some(b'-') => Some(Prefix::Minus),
+            _ => None,
+        }
+    }
+
+    /// Returns the arguments of the command.
+    pub fn args(&self) -> impl Iterator<Item = &str> {
+        self.tokens().into_iter().skip(1).map(|token| token.value)
+    }
+
+    /// Returns the comment, starting with `//`, if there is one.
+    pub fn comment(&self) -> Option<&str> {
+        tokenize(&self.text).1.map(|start| &self.text[start..])
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::MultipleCommands => {
+                write!(f, "the text contains more than one command")
+            }
+            CommandError::LineBreak => write!(f, "the text contains a line break"),
+            CommandError::Quote => write!(f, "an argument contains a quote that can't be quoted"),
+            CommandError::AfterComment => write!(
+                f,
+                "the command would come after a `//`, which would make it part of another command"
+            ),
+        }
+    }
+}
+
+impl error::Error for CommandError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(console_command: &ConsoleCommand) -> Vec<&str> {
+        console_command
+            .commands()
+            .iter()
+            .map(Command::text)
+            .collect()
+    }
+
+    #[test]
+    fn split_commands() {
+        let console_command = ConsoleCommand::parse("echo \"a;b\" ; stop;;  pause //x;quit");
+        assert_eq!(
+            texts(&console_command),
+            ["echo \"a;b\" ", " stop", "", "  pause //x;quit"]
+        );
+        assert_eq!(
+            console_command.names().collect::<Vec<_>>(),
+            ["echo", "stop", "pause"]
+        );
+        assert_eq!(console_command.commands()[3].comment(), Some("//x;quit"));
+
+        assert!(ConsoleCommand::parse("").is_empty());
+        assert_eq!(texts(&ConsoleCommand::parse(";")), ["", ""]);
+        assert_eq!(
+            texts(&ConsoleCommand::parse("echo \"//\";a")),
+            ["echo \"//\"", "a"]
+        );
+        assert_eq!(
+            texts(&ConsoleCommand::parse("echo a//b;c")),
+            ["echo a//b;c"]
+        );
+    }
+
+    #[test]
+    fn lossless() {
+        for text in [
+            "",
+            ";",
+            "  stop ;; quit  ",
+            "bxt_setpos 128 -1880 -536;_bxt_norefresh 1",
+            "//r_norefresh 1",
+            "echo \"unterminated;quit",
+            "say \"a b\"c d;+attack\t;-attack//x",
+        ] {
+            assert_eq!(ConsoleCommand::parse(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let command = Command::parse(" say  \"a b\"c d //e f").unwrap();
+        assert_eq!(
+            command.tokens(),
+            [
+                Token {
+                    value: "say",
+                    quoted: false,
+                    span: 1..4,
+                },
+                Token {
+                    value: "a b",
+                    quoted: true,
+                    span: 6..11,
+                },
+                Token {
+                    value: "c",
+                    quoted: false,
+                    span: 11..12,
+                },
+                Token {
+                    value: "d",
+                    quoted: false,
+                    span: 13..14,
+                },
+            ]
+        );
+        assert_eq!(command.comment(), Some("//e f"));
+
+        let command = Command::parse("echo \"a b").unwrap();
+        assert_eq!(command.args().collect::<Vec<_>>(), ["a b"]);
+        assert_eq!(command.tokens()[1].span, 5..9);
+
+        let command = Command::parse("echo a//b").unwrap();
+        assert_eq!(command.args().collect::<Vec<_>>(), ["a//b"]);
+        assert_eq!(command.comment(), None);
+    }
+
+    #[test]
+    fn prefix() {
+        let command = Command::parse("+attack").unwrap();
+        assert_eq!(command.prefix(), Some(Prefix::Plus));
+        assert_eq!(command.base_name(), Some("attack"));
+
+        let command = Command::parse("-duck").unwrap();
+        assert_eq!(command.prefix(), Some(Prefix::Minus));
+        assert_eq!(command.base_name(), Some("duck"));
+
+        let command = Command::parse("stop").unwrap();
+        assert_eq!(command.prefix(), None);
+        assert_eq!(command.base_name(), Some("stop"));
+
+        let command = Command::parse("  ").unwrap();
+        assert_eq!(command.prefix(), None);
+        assert_eq!(command.base_name(), None);
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(Command::new("stop", &[]).unwrap().text(), "stop");
+        assert_eq!(
+            Command::new("echo", &["", "a//b", "x;y", "a\"b\""])
+                .unwrap()
+                .text(),
+            "echo \"\" \"a//b\" \"x;y\" a\"b\""
+        );
+        assert_eq!(Command::new("echo", &["a\"b"]), Err(CommandError::Quote));
+        assert_eq!(Command::new("echo", &["a \"b\""]), Err(CommandError::Quote));
+        assert_eq!(Command::new("echo", &["\"a\""]), Err(CommandError::Quote));
+        assert_eq!(
+            Command::new("echo", &["a\nb"]),
+            Err(CommandError::LineBreak)
+        );
+
+        let command = Command::new("echo", &["", "a//b", "x;y"]).unwrap();
+        let console_command = ConsoleCommand::parse(command.text());
+        assert_eq!(console_command.commands(), std::slice::from_ref(&command));
+        assert_eq!(command.args().collect::<Vec<_>>(), ["", "a//b", "x;y"]);
+    }
+
+    #[test]
+    fn parse_command() {
+        assert_eq!(Command::parse("a;b"), Err(CommandError::MultipleCommands));
+        assert_eq!(Command::parse("a\n"), Err(CommandError::LineBreak));
+        assert!(Command::parse("a //b;c").is_ok());
+        assert!(Command::parse("echo \"a;b\"").is_ok());
+    }
+
+    #[test]
+    fn edit() {
+        let mut console_command = ConsoleCommand::parse("stop;");
+        console_command
+            .push(Command::parse("quit").unwrap())
+            .unwrap();
+        assert_eq!(console_command.to_string(), "stop;quit");
+
+        let mut console_command = ConsoleCommand::default();
+        console_command
+            .push(Command::parse("stop").unwrap())
+            .unwrap();
+        assert_eq!(console_command.to_string(), "stop");
+
+        console_command
+            .insert(0, Command::parse(" bxt_timer_stop ").unwrap())
+            .unwrap();
+        assert_eq!(console_command.to_string(), " bxt_timer_stop ;stop");
+
+        console_command.retain(|command| command.name() != Some("stop"));
+        assert_eq!(console_command.to_string(), " bxt_timer_stop ");
+        assert_eq!(console_command.remove(0).name(), Some("bxt_timer_stop"));
+        assert!(console_command.is_empty());
+    }
+
+    #[test]
+    fn comments_stay_last() {
+        let mut console_command = ConsoleCommand::parse("stop;//quit");
+        assert_eq!(
+            console_command.push(Command::parse("pause").unwrap()),
+            Err(CommandError::AfterComment)
+        );
+        console_command
+            .insert(1, Command::parse("pause").unwrap())
+            .unwrap();
+        assert_eq!(console_command.to_string(), "stop;pause;//quit");
+
+        assert_eq!(
+            console_command.insert(0, Command::parse("echo a//b").unwrap()),
+            Err(CommandError::AfterComment)
+        );
+        assert_eq!(
+            console_command.insert(0, Command::parse("echo \"a//b\"").unwrap()),
+            Ok(())
+        );
+    }
+}
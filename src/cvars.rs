@@ -0,0 +1,408 @@
+//! Tracking of cvar values through a script.
+//!
+//! Console commands of frame bulks can set cvars, like `sv_maxspeed 300`. This module applies
+//! such commands in order, starting from an initial [`Environment`], and answers which value a
+//! cvar has at a given frame or line.
+//!
+//! The console command of a frame bulk runs on every one of its frames, so the cvars it sets are
+//! in effect from the first frame of the frame bulk. A command sets a cvar if its name is in the
+//! initial environment or is a cvar in the given [`Catalogue`]. Like in the game, only the first
+//! argument is used as the value, and a command without arguments only prints the value. Cvars set
+//! indirectly, through `alias` or `exec`, aren't tracked.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{
+//!     console::catalogue::Catalogue,
+//!     cvars::{self, Environment},
+//!     HLTAS,
+//! };
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|10|sv_maxspeed 300
+//! ----------|------|------|0.001|-|-|10
+//! ----------|------|------|0.001|-|-|10|sv_maxspeed 320;sv_gravity 600";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let analysis = cvars::analyze(&hltas, &Environment::default(), &Catalogue::new());
+//!
+//! assert_eq!(analysis.value_at_frame("sv_maxspeed", 15), Some("300"));
+//! assert_eq!(analysis.value_at_frame("sv_maxspeed", 20), Some("320"));
+//! assert_eq!(analysis.value_at_line("sv_gravity", 1), Some("800"));
+//!
+//! assert_eq!(analysis.changes.len(), 3);
+//! assert_eq!(analysis.changes[2].frame, 20);
+//! assert_eq!(analysis.changes[2].old_value.as_deref(), Some("800"));
+//! ```
+
+use std::collections::{btree_map, BTreeMap};
+
+use crate::{
+    console::catalogue::{Catalogue, Entry},
+    types::Line,
+    HLTAS,
+};
+
+/// Default values of the movement-relevant cvars.
+const MOVEMENT_DEFAULTS: [(&str, &str); 16] = [
+    ("cl_backspeed", "400"),
+    ("cl_forwardspeed", "400"),
+    ("cl_sidespeed", "400"),
+    ("cl_upspeed", "320"),
+    ("edgefriction", "2"),
+    ("fps_max", "100"),
+    ("host_framerate", "0"),
+    ("sv_accelerate", "10"),
+    ("sv_airaccelerate", "10"),
+    ("sv_bounce", "1"),
+    ("sv_friction", "4"),
+    ("sv_gravity", "800"),
+    ("sv_maxspeed", "320"),
+    ("sv_maxvelocity", "2000"),
+    ("sv_stepsize", "18"),
+    ("sv_stopspeed", "100"),
+];
+
+/// Values of cvars.
+///
+/// Names are case-insensitive, like in the game. The default environment has the default values
+/// of the movement-relevant cvars, like `sv_maxspeed` and `sv_airaccelerate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// Values by lowercase name.
+    values: BTreeMap<String, String>,
+}
+
+/// A command that changed the value of a cvar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Index of the frame bulk with the command in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Index of the first frame with the new value.
+    pub frame: u64,
+    /// Name of the cvar, in lowercase.
+    pub name: String,
+    /// The value before the change, or `None` if the cvar wasn't set before.
+    pub old_value: Option<String>,
+    /// The value after the change.
+    pub new_value: String,
+}
+
+/// Cvar values of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The environment at the start of the script.
+    pub initial: Environment,
+    /// Every change of a cvar value, in order.
+    pub changes: Vec<Change>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        let mut environment = Self::empty();
+        for (name, value) in MOVEMENT_DEFAULTS {
+            environment.set(name, value);
+        }
+        environment
+    }
+}
+
+impl Environment {
+    /// Creates an environment without any cvars.
+    pub fn empty() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the value of a cvar.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Returns the value of a cvar as a number, or `None` if it's not set or not a number.
+    pub fn number(&self, name: &str) -> Option<f32> {
+        self.get(name)?.trim().parse().ok()
+    }
+
+    /// Sets the value of a cvar, returning the previous value.
+    pub fn set(&mut self, name: &str, value: &str) -> Option<String> {
+        self.values
+            .insert(name.to_ascii_lowercase(), value.to_owned())
+    }
+
+    /// Returns the names and values of all cvars, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl Analysis {
+    /// Returns the value of a cvar on the given frame.
+    ///
+    /// Frames past the end of the script have the values at the end of the script.
+    pub fn value_at_frame(&self, name: &str, frame: u64) -> Option<&str> {
+        self.value_where(name, |change| change.frame <= frame)
+    }
+
+    /// Returns the value of a cvar when the line at the given index in [`HLTAS::lines`] takes
+    /// effect.
+    ///
+    /// For a frame bulk this includes the cvars set by its own console command.
+    pub fn value_at_line(&self, name: &str, line_index: usize) -> Option<&str> {
+        self.value_where(name, |change| change.line_index <= line_index)
+    }
+
+    /// Returns the values of all cvars on the given frame.
+    pub fn environment_at_frame(&self, frame: u64) -> Environment {
+        self.environment_where(|change| change.frame <= frame)
+    }
+
+    /// Returns the values of all cvars when the line at the given index in [`HLTAS::lines`] takes
+    /// effect.
+    pub fn environment_at_line(&self, line_index: usize) -> Environment {
+        self.environment_where(|change| change.line_index <= line_index)
+    }
+
+    fn value_where(&self, name: &str, applied: impl Fn(&Change) -> bool) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.changes
+            .iter()
+            .take_while(|change| applied(change))
+            .filter(|change| change.name == name)
+            .last()
+            .map(|change| change.new_value.as_str())
+            .or_else(|| self.initial.get(&name))
+    }
+
+    fn environment_where(&self, applied: impl Fn(&Change) -> bool) -> Environment {
+        let mut environment = self.initial.clone();
+        for change in self.changes.iter().take_while(|change| applied(change)) {
+            environment.set(&change.name, &change.new_value);
+        }
+        environment
+    }
+}
+
+/// Applies the cvar-setting commands of a script in order, starting from the initial environment.
+///
+/// Commands set cvars whose names are in the initial environment or are cvars in the catalogue.
+pub fn analyze(hltas: &HLTAS, initial: &Environment, catalogue: &Catalogue) -> Analysis {
+    let mut environment = initial.clone();
+    let mut changes = Vec::new();
+    let mut frame = 0;
+
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        let frame_bulk = match line {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => continue,
+        };
+
+        for command in frame_bulk.console_commands().commands() {
+            let tokens = command.tokens();
+            let (name, value) = match &tokens[..] {
+                [name, value, ..] => (name.value.to_ascii_lowercase(), value.value),
+                _ => continue,
+            };
+
+            let is_cvar = environment.values.contains_key(&name)
+                || matches!(catalogue.get(&name), Some(Entry::Cvar(_)));
+            if !is_cvar {
+                continue;
+            }
+
+            let old_value = match environment.values.entry(name.clone()) {
+                btree_map::Entry::Occupied(entry) if entry.get() == value => continue,
+                btree_map::Entry::Occupied(mut entry) => Some(entry.insert(value.to_owned())),
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(value.to_owned());
+                    None
+                }
+            };
+
+            changes.push(Change {
+                line_index,
+                frame,
+                name,
+                old_value,
+                new_value: value.to_owned(),
+            });
+        }
+
+        frame += u64::from(frame_bulk.frame_count.get());
+    }
+
+    Analysis {
+        initial: initial.clone(),
+        changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::test_util::parse_frames;
+
+    fn analyze_str(contents: &str, initial: &Environment) -> Analysis {
+        analyze(&parse_frames(contents), initial, &Catalogue::new())
+    }
+
+    #[test]
+    fn changes() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|5|sv_maxspeed 300;SV_Gravity \"600\" x\n\
+             strafing vectorial\n\
+             ----------|------|------|0.001|-|-|5|sv_maxspeed 300;sv_friction;//sv_friction 1\n\
+             ----------|------|------|0.001|-|-|5|sv_maxspeed 200;sv_maxspeed 250;volume 0.5",
+            &Environment::default(),
+        );
+
+        let changes: Vec<_> = analysis
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.line_index,
+                    change.frame,
+                    change.name.as_str(),
+                    change.old_value.as_deref(),
+                    change.new_value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (0, 0, "sv_maxspeed", Some("320"), "300"),
+                (0, 0, "sv_gravity", Some("800"), "600"),
+                (3, 10, "sv_maxspeed", Some("300"), "200"),
+                (3, 10, "sv_maxspeed", Some("200"), "250"),
+                (3, 10, "volume", None, "0.5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lookups() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|5\n\
+             ----------|------|------|0.001|-|-|5|sv_airaccelerate 100\n\
+             buttons\n\
+             ----------|------|------|0.001|-|-|5|sv_airaccelerate 10;sv_airaccelerate 1000",
+            &Environment::default(),
+        );
+
+        assert_eq!(analysis.value_at_frame("sv_airaccelerate", 4), Some("10"));
+        assert_eq!(analysis.value_at_frame("sv_airaccelerate", 5), Some("100"));
+        assert_eq!(analysis.value_at_frame("sv_airaccelerate", 9), Some("100"));
+        assert_eq!(
+            analysis.value_at_frame("SV_AIRACCELERATE", 10),
+            Some("1000")
+        );
+        assert_eq!(
+            analysis.value_at_frame("sv_airaccelerate", 100),
+            Some("1000")
+        );
+
+        assert_eq!(analysis.value_at_line("sv_airaccelerate", 0), Some("10"));
+        assert_eq!(analysis.value_at_line("sv_airaccelerate", 1), Some("100"));
+        assert_eq!(analysis.value_at_line("sv_airaccelerate", 2), Some("100"));
+        assert_eq!(analysis.value_at_line("sv_airaccelerate", 3), Some("1000"));
+
+        assert_eq!(analysis.value_at_frame("unknown", 0), None);
+
+        let environment = analysis.environment_at_frame(7);
+        assert_eq!(environment.number("sv_airaccelerate"), Some(100.));
+        assert_eq!(environment.number("sv_maxspeed"), Some(320.));
+        assert_eq!(analysis.environment_at_line(0), Environment::default());
+    }
+
+    #[test]
+    fn initial_environment() {
+        let mut initial = Environment::empty();
+        initial.set("my_cvar", "1");
+        initial.set("sv_maxspeed", "300");
+
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|5|my_cvar 2;sv_gravity 100;my_command 1",
+            &initial,
+        );
+        assert_eq!(analysis.value_at_frame("my_cvar", 0), Some("2"));
+        assert_eq!(analysis.value_at_frame("sv_maxspeed", 0), Some("300"));
+        assert_eq!(analysis.value_at_frame("sv_gravity", 0), Some("100"));
+        assert_eq!(analysis.changes[1].old_value, None);
+        assert_eq!(analysis.changes.len(), 2);
+
+        let analysis = analyze_str("----------|------|------|0.001|-|-|5", &initial);
+        assert_eq!(analysis.value_at_frame("sv_gravity", 0), None);
+    }
+
+    #[test]
+    fn environment() {
+        let mut environment = Environment::default();
+        assert_eq!(environment.get("SV_MAXSPEED"), Some("320"));
+        assert_eq!(
+            environment.set("Sv_MaxSpeed", "abc"),
+            Some("320".to_owned())
+        );
+        assert_eq!(environment.number("sv_maxspeed"), None);
+        assert!(environment
+            .iter()
+            .any(|(name, value)| name == "sv_maxspeed" && value == "abc"));
+    }
+
+    fn analyze_file(path: &str) -> Analysis {
+        let contents = read_to_string(path).unwrap();
+        let hltas = HLTAS::from_str(&contents).unwrap();
+        analyze(&hltas, &Environment::default(), &Catalogue::new())
+    }
+
+    #[test]
+    fn test_data() {
+        let analysis = analyze_file("test-data/parse/goldbhop.hltas");
+        let changes: Vec<_> = analysis
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.line_index,
+                    change.frame,
+                    change.name.as_str(),
+                    change.old_value.as_deref(),
+                    change.new_value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (0, 0, "bxt_bhopcap", None, "0"),
+                (0, 0, "sensitivity", None, "0"),
+                (0, 0, "_bxt_min_frametime", None, "0"),
+                (50, 2478, "_bxt_min_frametime", Some("0"), "0.03"),
+                (55, 2774, "sensitivity", Some("0"), "1"),
+                (55, 2774, "_bxt_min_frametime", Some("0.03"), "0"),
+            ]
+        );
+
+        let analysis = analyze_file("test-data/parse/triggertest.hltas");
+        let start = analysis.environment_at_frame(0);
+        assert_eq!(start.get("sv_airaccelerate"), Some("100"));
+        assert_eq!(start.get("_bxt_min_frametime"), Some("0.01"));
+        assert_eq!(start.get("sensitivity"), Some("0"));
+        assert_eq!(analysis.value_at_frame("sensitivity", 1231), Some("0"));
+        assert_eq!(analysis.value_at_frame("sensitivity", 1232), Some("1"));
+        assert_eq!(analysis.value_at_line("_bxt_min_frametime", 5), Some("0"));
+        assert_eq!(analysis.value_at_frame("sv_maxspeed", 1232), Some("320"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Owned diagnostics.
+//!
+//! [`read::Error`](crate::read::Error) borrows the input it was produced from, which makes it
+//! awkward to store, send across threads or hand over to an editor. [`Diagnostic`] is its owned
+//! counterpart: it has a stable error code, the byte span and the line and column of the problem,
+//! along with optional notes and a suggested fix.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{diagnostic::Severity, HLTAS};
+//!
+//! let contents = "version 1\nframes\n----------|------|------|0.001|-|-|5\ns03-------|------|------|0.001|-|-|1";
+//! let diagnostic = HLTAS::from_str(contents).unwrap_err().to_diagnostic();
+//!
+//! assert_eq!(diagnostic.code, "E0008");
+//! assert_eq!(diagnostic.severity, Severity::Error);
+//! assert_eq!(diagnostic.line, 3);
+//! assert_eq!(&contents[diagnostic.span], "-");
+//! ```
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+/// Severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub enum Severity {
+    /// Informational message.
+    Note,
+    /// The script works, but likely not as intended.
+    Warning,
+    /// The script is invalid.
+    Error,
+}
+
+/// A suggested fix for a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Suggestion {
+    /// Description of the fix.
+    pub message: String,
+    /// Byte range of the input to replace.
+    pub span: Range<usize>,
+    /// Text to replace the span with.
+    pub replacement: String,
+}
+
+/// An owned diagnostic with a location in the input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Diagnostic {
+    /// Stable code of the diagnostic, like `"E0006"`.
+    ///
+    /// Parse errors without a more specific description have the code `"E0000"`.
+    pub code: String,
+    /// Severity of the diagnostic.
+    pub severity: Severity,
+    /// Human-readable message.
+    pub message: String,
+    /// Byte range of the input the diagnostic refers to.
+    pub span: Range<usize>,
+    /// Line number of the start of the span, starting from 0.
+    pub line: usize,
+    /// Column of the start of the span in bytes, starting from 0.
+    pub column: usize,
+    /// Additional notes.
+    pub notes: Vec<String>,
+    /// Suggested fix.
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    /// Applies the suggested fix, if any, to `input`.
+    ///
+    /// Returns `None` if there's no suggestion or its span is out of bounds of `input`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "version 2\nframes\n";
+    /// let diagnostic = HLTAS::from_str(contents).unwrap_err().to_diagnostic();
+    /// assert_eq!(diagnostic.apply_suggestion(contents).unwrap(), "version 1\nframes\n");
+    /// ```
+    pub fn apply_suggestion(&self, input: &str) -> Option<String> {
+        let suggestion = self.suggestion.as_ref()?;
+        input.get(suggestion.span.clone())?;
+
+        let mut output = String::with_capacity(input.len() + suggestion.replacement.len());
+        output.push_str(&input[..suggestion.span.start]);
+        output.push_str(&suggestion.replacement);
+        output.push_str(&input[suggestion.span.end..]);
+        Some(output)
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {} (line {}, column {})",
+            self.severity,
+            self.code,
+            self.message,
+            self.line + 1,
+            self.column + 1
+        )?;
+
+        for note in &self.notes {
+            write!(f, "\n  note: {}", note)?;
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {}", suggestion.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read::Context, HLTAS};
+
+    fn diagnostic(input: &str) -> Diagnostic {
+        HLTAS::from_str(input).unwrap_err().to_diagnostic()
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        use Context::*;
+
+        let contexts = [
+            ErrorReadingVersion,
+            VersionTooHigh,
+            BothAutoJumpAndDuckTap,
+            NoLeaveGroundAction,
+            TimesOnLeaveGroundAction,
+            NoSaveName,
+            NoSeed,
+            NoYaw,
+            NoButtons,
+            NoLGAGSTMinSpeed,
+            NoResetSeed,
+            ErrorParsingLine,
+            InvalidStrafingAlgorithm,
+            NoConstraints,
+            NoPlusMinusBeforeTolerance,
+            NoFromToParameters,
+            NoTo,
+            NoYawspeed,
+            UnsupportedConstantYawspeedDir,
+            NegativeYawspeed,
+            NoYawOffset,
+            NoYawOffsetAcceleration,
+            TooFewAutoActionsCharacters,
+            InvalidAutoActions,
+            InvalidMovementKeys,
+            InvalidActionKeys,
+            InvalidFrameTime,
+            InvalidYawField,
+            InvalidPitch,
+            InvalidFrameCount,
+            InvalidChangeTarget,
+            NoChangeFinalValue,
+            NoChangeDuration,
+            InvalidOverrideValues,
+            InvalidLookAtEntity,
+            InvalidLookAtCoordinates,
+            InvalidSeeds,
+            InvalidHLStrafeVersion,
+            NoFrames,
+        ];
+
+        let mut codes: Vec<_> = contexts.iter().map(|c| c.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), contexts.len());
+    }
+
+    #[test]
+    fn span_and_position() {
+        let input = "version 1\nframes\n----------|------|------|0.001|-|-|5\nchange yaw to 5";
+        let diagnostic = diagnostic(input);
+
+        assert_eq!(diagnostic.code, Context::NoChangeDuration.code());
+        assert_eq!(diagnostic.line, 3);
+        assert_eq!(diagnostic.column, 15);
+        assert_eq!(diagnostic.span, input.len()..input.len());
+    }
+
+    #[test]
+    fn expected_char_note() {
+        let d = diagnostic("version 1\nframes\n----------|x-----|------|0.001|-|-|5");
+        assert_eq!(d.code, Context::InvalidMovementKeys.code());
+        assert_eq!(d.notes, ["expected '-'"]);
+        assert_eq!(d.column, 11);
+        assert_eq!(d.span.len(), 6);
+    }
+
+    #[test]
+    fn plus_minus_suggestion() {
+        let input = "version 1\nframes\ntarget_yaw velocity 5";
+        let d = diagnostic(input);
+        assert_eq!(d.code, Context::NoPlusMinusBeforeTolerance.code());
+        assert_eq!(
+            d.apply_suggestion(input).unwrap(),
+            "version 1\nframes\ntarget_yaw velocity +-5"
+        );
+    }
+
+    #[test]
+    fn display() {
+        let d = diagnostic("version 1\nframes\nsave ");
+        assert_eq!(
+            d.to_string(),
+            "error[E0006]: missing save name (line 3, column 6)"
+        );
+    }
+}
@@ -0,0 +1,563 @@
+//! Comparing scripts frame by frame.
+//!
+//! A line-based diff of two scripts shows changes whenever a frame bulk is split or merged, even
+//! though the frames stay the same. [`diff()`] instead compares the [`frames`](crate::frames) of
+//! two scripts, with the state carried over from the preceding lines resolved, and reports which
+//! frames differ and in which [`Field`]s. The header properties are compared too.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{diff::{diff, Field}, HLTAS};
+//!
+//! let a = HLTAS::from_str("\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|10").unwrap();
+//!
+//! let b = HLTAS::from_str("\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|4
+//! ----------|------|------|0.001|-|-|2|+attack
+//! ----------|------|------|0.001|-|-|4").unwrap();
+//!
+//! let diff = diff(&a, &b);
+//! assert!(!diff.is_equivalent());
+//! assert_eq!(diff.first_difference(), Some(4));
+//! assert_eq!(diff.ranges[0].frames, 4..6);
+//! assert_eq!(diff.ranges[0].fields, [Field::ConsoleCommand]);
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+use crate::{
+    frames::Frame,
+    types::{AutoMovement, Line, Properties, StrafeDir, StrafeSettings},
+    HLTAS,
+};
+
+/// A part of a frame that can differ between scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Field {
+    /// Manually pressed movement keys.
+    MovementKeys,
+    /// Manually pressed action keys.
+    ActionKeys,
+    /// Frame time.
+    FrameTime,
+    /// Strafing type and direction, the strafing mode and constraints and the strafe buttons.
+    Strafe,
+    /// Yaw angle set by the frame bulk or by a target yaw override.
+    Yaw,
+    /// Pitch angle set by the frame bulk or by a pitch override.
+    Pitch,
+    /// Render yaw and pitch overrides.
+    RenderAngles,
+    /// Automatic actions other than movement, their remaining times and the LGAGST min speed.
+    AutoActions,
+    /// Console command.
+    ConsoleCommand,
+    /// Active `change` lines.
+    Changes,
+    /// Shared and non-shared RNG seeds.
+    Seeds,
+    /// Lines that run before the frame without changing the state carried over, like `save`, and
+    /// unrecognized lines.
+    Lines,
+}
+
+/// A range of consecutive differing frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRange {
+    /// The differing frames.
+    pub frames: Range<u64>,
+    /// Fields that differ on any of the frames, sorted.
+    pub fields: Vec<Field>,
+}
+
+/// Result of comparing two scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    /// Ranges of frames present in both scripts that differ, in order.
+    pub ranges: Vec<DiffRange>,
+    /// Number of frames in the first script.
+    pub frames_a: u64,
+    /// Number of frames in the second script.
+    pub frames_b: u64,
+    /// Names of the header properties that differ, in the order they are written.
+    pub properties: Vec<String>,
+}
+
+impl Diff {
+    /// Returns `true` if both scripts have the same properties and every frame behaves the same.
+    #[inline]
+    pub fn is_equivalent(&self) -> bool {
+        self.ranges.is_empty() && self.frames_a == self.frames_b && self.properties.is_empty()
+    }
+
+    /// Returns the index of the first frame that differs or is present in only one script.
+    pub fn first_difference(&self) -> Option<u64> {
+        let common = self.frames_a.min(self.frames_b);
+        match self.ranges.first() {
+            Some(range) => Some(range.frames.start),
+            None if self.frames_a != self.frames_b => Some(common),
+            None => None,
+        }
+    }
+}
+
+/// Returns the strafe settings with the yaw angles left out.
+fn strafe_without_yaw(settings: StrafeSettings) -> StrafeSettings {
+    let dir = match settings.dir {
+        StrafeDir::Yaw(_) => StrafeDir::Yaw(0.),
+        StrafeDir::Point { .. } => StrafeDir::Point { x: 0., y: 0. },
+        StrafeDir::Line { .. } => StrafeDir::Line { yaw: 0. },
+        dir => dir,
+    };
+    StrafeSettings { dir, ..settings }
+}
+
+fn yaw(frame: &Frame) -> (Option<f32>, Option<(f32, f32)>, Option<f32>) {
+    let yaw = match (frame.auto_actions.movement, frame.strafe_dir) {
+        (Some(AutoMovement::SetYaw(yaw)), _) => Some(yaw),
+        (_, Some(StrafeDir::Yaw(yaw) | StrafeDir::Line { yaw })) => Some(yaw),
+        _ => None,
+    };
+    let point = match frame.strafe_dir {
+        Some(StrafeDir::Point { x, y }) => Some((x, y)),
+        _ => None,
+    };
+    (yaw, point, frame.state.target_yaw_override)
+}
+
+fn same_frame_time(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Returns the lines before the frame that aren't part of [`State`](crate::frames::State).
+fn lines<'a>(frame: &Frame<'a>) -> Vec<&'a Line> {
+    frame
+        .preceding_lines
+        .iter()
+        .filter(|line| matches!(line, Line::Save(_) | Line::Unknown(_)))
+        .collect()
+}
+
+/// Returns the names of the properties that differ between two scripts.
+fn compare_properties(a: &Properties, b: &Properties) -> Vec<String> {
+    let same_frame_time_0ms = match (&a.frametime_0ms, &b.frametime_0ms) {
+        (Some(a), Some(b)) => same_frame_time(a, b),
+        (a, b) => a == b,
+    };
+
+    let mut properties = Vec::new();
+    let mut check = |name: &str, same: bool| {
+        if !same {
+            properties.push(name.to_owned());
+        }
+    };
+    check("demo", a.demo == b.demo);
+    check("save", a.save == b.save);
+    check("frametime0ms", same_frame_time_0ms);
+    check("seed", a.seeds == b.seeds);
+    check("hlstrafe_version", a.hlstrafe_version == b.hlstrafe_version);
+    check("load_command", a.load_command == b.load_command);
+
+    // Custom properties can repeat, so all values of a name are compared in order.
+    let values = |properties: &Properties, name: &str| -> Vec<String> {
+        properties
+            .custom
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    };
+    for (name, _) in a.custom.iter().chain(&b.custom) {
+        if !properties.contains(name) && values(a, name) != values(b, name) {
+            properties.push(name.clone());
+        }
+    }
+    properties
+}
+
+/// Returns the fields that differ between two frames.
+pub fn compare_frames(a: &Frame, b: &Frame) -> Vec<Field> {
+    let (fa, fb) = (a.frame_bulk, b.frame_bulk);
+    let (sa, sb) = (&a.state, &b.state);
+
+    let strafe = |frame: &Frame| {
+        let settings = match frame.auto_actions.movement {
+            Some(AutoMovement::Strafe(settings)) => Some(strafe_without_yaw(StrafeSettings {
+                dir: frame.strafe_dir.unwrap_or(settings.dir),
+                ..settings
+            })),
+            _ => None,
+        };
+        (
+            settings,
+            frame.state.vectorial_strafing,
+            frame.state.constraints,
+            frame.state.buttons,
+        )
+    };
+    let auto_actions = |frame: &Frame| {
+        let aa = &frame.auto_actions;
+        (
+            aa.leave_ground_action,
+            aa.jump_bug,
+            aa.duck_before_collision,
+            aa.duck_before_ground,
+            aa.duck_when_jump,
+            frame.state.limited_times,
+            frame.state.lgagst_min_speed,
+        )
+    };
+    let changes = |frame: &Frame| {
+        frame
+            .state
+            .changes
+            .iter()
+            .map(|active| (active.change, active.elapsed))
+            .collect::<Vec<_>>()
+    };
+
+    let mut fields = Vec::new();
+    let mut check = |field, same: bool| {
+        if !same {
+            fields.push(field);
+        }
+    };
+    check(Field::MovementKeys, fa.movement_keys == fb.movement_keys);
+    check(Field::ActionKeys, fa.action_keys == fb.action_keys);
+    check(
+        Field::FrameTime,
+        same_frame_time(&fa.frame_time, &fb.frame_time),
+    );
+    check(Field::Strafe, strafe(a) == strafe(b));
+    check(Field::Yaw, yaw(a) == yaw(b));
+    check(
+        Field::Pitch,
+        (fa.pitch, sa.pitch_override) == (fb.pitch, sb.pitch_override),
+    );
+    check(
+        Field::RenderAngles,
+        (sa.render_yaw_override, sa.render_pitch_override)
+            == (sb.render_yaw_override, sb.render_pitch_override),
+    );
+    check(Field::AutoActions, auto_actions(a) == auto_actions(b));
+    check(
+        Field::ConsoleCommand,
+        fa.console_command == fb.console_command,
+    );
+    check(Field::Changes, changes(a) == changes(b));
+    check(
+        Field::Seeds,
+        (sa.shared_seed, sa.non_shared_seed) == (sb.shared_seed, sb.non_shared_seed),
+    );
+    check(Field::Lines, lines(a) == lines(b));
+    fields
+}
+
+/// Compares the properties of two scripts and the scripts frame by frame.
+///
+/// Consecutive differing frames are grouped into one [`DiffRange`].
+pub fn diff(a: &HLTAS, b: &HLTAS) -> Diff {
+    let mut ranges: Vec<DiffRange> = Vec::new();
+    let mut frames_a = a.frames();
+    let mut frames_b = b.frames();
+    let mut index = 0;
+
+    let (frames_a, frames_b) = loop {
+        let (frame_a, frame_b) = match (frames_a.next(), frames_b.next()) {
+            (Some(frame_a), Some(frame_b)) => (frame_a, frame_b),
+            (Some(_), None) => break (index + 1 + frames_a.count() as u64, index),
+            (None, Some(_)) => break (index, index + 1 + frames_b.count() as u64),
+            (None, None) => break (index, index),
+        };
+
+        let fields = compare_frames(&frame_a, &frame_b);
+        if !fields.is_empty() {
+            match ranges.last_mut() {
+                Some(range) if range.frames.end == index => {
+                    range.frames.end += 1;
+                    range.fields.extend(fields);
+                    range.fields.sort_unstable();
+                    range.fields.dedup();
+                }
+                _ => ranges.push(DiffRange {
+                    frames: index..index + 1,
+                    fields,
+                }),
+            }
+        }
+        index += 1;
+    };
+
+    Diff {
+        ranges,
+        frames_a,
+        frames_b,
+        properties: compare_properties(&a.properties, &b.properties),
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Field::MovementKeys => "movement keys",
+            Field::ActionKeys => "action keys",
+            Field::FrameTime => "frame time",
+            Field::Strafe => "strafing",
+            Field::Yaw => "yaw",
+            Field::Pitch => "pitch",
+            Field::RenderAngles => "render angles",
+            Field::AutoActions => "auto-actions",
+            Field::ConsoleCommand => "console command",
+            Field::Changes => "changes",
+            Field::Seeds => "seeds",
+            Field::Lines => "lines",
+        })
+    }
+}
+
+impl Display for DiffRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.frames.end - self.frames.start == 1 {
+            write!(f, "frame {}: ", self.frames.start)?;
+        } else {
+            write!(f, "frames {}..{}: ", self.frames.start, self.frames.end)?;
+        }
+
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", field)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_equivalent() {
+            return write!(f, "The scripts are equivalent.");
+        }
+
+        if !self.properties.is_empty() {
+            write!(
+                f,
+                "The scripts differ in properties: {}.",
+                self.properties.join(", ")
+            )?;
+        }
+
+        let first = match self.first_difference() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+
+        if !self.properties.is_empty() {
+            f.write_str("\n")?;
+        }
+        write!(f, "The scripts differ starting at frame {}.", first)?;
+        for range in &self.ranges {
+            write!(f, "\n{}", range)?;
+        }
+        if self.frames_a != self.frames_b {
+            write!(
+                f,
+                "\nThe first script has {} frames and the second script has {}.",
+                self.frames_a, self.frames_b
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::parse_frames;
+
+    #[test]
+    fn equivalent() {
+        let a = parse_frames(
+            "\
+            s06-------|------|------|0.001|2|-|4\n\
+            buttons 0 1 2 3\n\
+            ----------|------|------|0.001|-|-|2",
+        );
+        let b = parse_frames(
+            "\
+            s06-------|------|------|0.0010|2|-|2\n\
+            // Comment\n\
+            s07-------|------|------|0.001|2|-|2\n\
+            buttons 0 1 2 3\n\
+            ----------|------|------|0.001|-|-|1\n\
+            ----------|------|------|0.001|-|-|1",
+        );
+
+        let diff = diff(&a, &b);
+        assert!(diff.is_equivalent());
+        assert_eq!(diff.first_difference(), None);
+        assert_eq!(diff.to_string(), "The scripts are equivalent.");
+    }
+
+    #[test]
+    fn fields() {
+        let a = parse_frames(
+            "\
+            s03-------|------|------|0.001|10|-|2\n\
+            ----------|f-----|------|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|2",
+        );
+        let b = parse_frames(
+            "\
+            s03-------|------|------|0.001|20|-|1\n\
+            s13-------|------|------|0.001|20|-|1\n\
+            ----------|------|j-----|0.001|-|10|2\n\
+            ----------|------|------|0.001|-|-|2",
+        );
+
+        let diff = diff(&a, &b);
+        assert_eq!(
+            diff.ranges,
+            [DiffRange {
+                frames: 0..4,
+                fields: vec![
+                    Field::MovementKeys,
+                    Field::ActionKeys,
+                    Field::Strafe,
+                    Field::Yaw,
+                    Field::Pitch
+                ],
+            }]
+        );
+        assert_eq!(
+            diff.ranges[0].to_string(),
+            "frames 0..4: movement keys, action keys, strafing, yaw, pitch"
+        );
+    }
+
+    #[test]
+    fn state() {
+        let a = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|2\n\
+            target_yaw_override 1\n\
+            ----------|------|------|0.001|-|-|2\n\
+            seed 5\n\
+            ----------|------|------|0.001|-|-|1",
+        );
+        let b = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|2\n\
+            target_yaw_override 2\n\
+            ----------|------|------|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|1",
+        );
+
+        let diff = diff(&a, &b);
+        assert_eq!(
+            diff.ranges,
+            [
+                DiffRange {
+                    frames: 2..3,
+                    fields: vec![Field::Yaw],
+                },
+                DiffRange {
+                    frames: 4..5,
+                    fields: vec![Field::Seeds],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let a = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|2\n\
+            save foo\n\
+            ----------|------|------|0.001|-|-|2",
+        );
+        let b = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|2\n\
+            // save foo\n\
+            ----------|------|------|0.001|-|-|2",
+        );
+
+        let diff = diff(&a, &b);
+        assert!(!diff.is_equivalent());
+        assert_eq!(
+            diff.ranges,
+            [DiffRange {
+                frames: 2..3,
+                fields: vec![Field::Lines],
+            }]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "The scripts differ starting at frame 2.\nframe 2: lines"
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let contents = "frames\n----------|------|------|0.001|-|-|2";
+        let a = HLTAS::from_str(&format!(
+            "version 1\nload_command map c1a0\nframetime0ms 0.0000000001\nrunner a\n{}",
+            contents
+        ))
+        .unwrap();
+        let b = HLTAS::from_str(&format!(
+            "version 1\nframetime0ms 0.00000000010\nrunner a\nrunner b\n{}",
+            contents
+        ))
+        .unwrap();
+
+        let diff = diff(&a, &b);
+        assert!(!diff.is_equivalent());
+        assert!(diff.ranges.is_empty());
+        assert_eq!(diff.first_difference(), None);
+        assert_eq!(diff.properties, ["load_command", "runner"]);
+        assert_eq!(
+            diff.to_string(),
+            "The scripts differ in properties: load_command, runner."
+        );
+
+        let diff = super::diff(&a, &a);
+        assert!(diff.is_equivalent());
+        assert!(diff.properties.is_empty());
+    }
+
+    #[test]
+    fn different_lengths() {
+        let a = parse_frames("----------|------|------|0.001|-|-|10");
+        let b = parse_frames("----------|------|------|0.001|-|-|12");
+
+        let diff = diff(&a, &b);
+        assert!(!diff.is_equivalent());
+        assert!(diff.ranges.is_empty());
+        assert_eq!((diff.frames_a, diff.frames_b), (10, 12));
+        assert_eq!(diff.first_difference(), Some(10));
+        assert_eq!(
+            diff.to_string(),
+            "The scripts differ starting at frame 10.\n\
+             The first script has 10 frames and the second script has 12."
+        );
+
+        let diff = super::diff(&b, &a);
+        assert_eq!((diff.frames_a, diff.frames_b), (12, 10));
+    }
+}
@@ -0,0 +1,648 @@
+//! Editing scripts by absolute frame index.
+//!
+//! These functions back [`HLTAS::split_at_frame()`], [`HLTAS::insert_lines_at_frame()`],
+//! [`HLTAS::delete_frames()`] and [`HLTAS::set_yaw()`]. Frame indices count the frames of all
+//! frame bulks from the start of the script, like [`Frame::index`](crate::frames::Frame::index).
+//!
+//! Edits keep the untouched frames behaving the same:
+//! - When a frame bulk is split, auto-actions with limited times stay only on the first part,
+//!   since they start counting on the first frame of the frame bulk, and left-right strafing in
+//!   the second part continues on the same side.
+//! - Override lines apply their values to frames rather than to frame bulks, so inserting frames
+//!   moves the values meant for the following frames past the inserted lines, and deleting frames
+//!   deletes the values applied to them.
+//!
+//! [`HLTAS::split_at_frame()`]: crate::HLTAS::split_at_frame
+//! [`HLTAS::insert_lines_at_frame()`]: crate::HLTAS::insert_lines_at_frame
+//! [`HLTAS::delete_frames()`]: crate::HLTAS::delete_frames
+//! [`HLTAS::set_yaw()`]: crate::HLTAS::set_yaw
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    num::NonZeroU32,
+    ops::Range,
+};
+
+use crate::{
+    overrides::{self, OverrideKind},
+    types::{AutoMovement, FrameBulk, Line, StrafeDir, StrafeType, Times},
+    HLTAS,
+};
+
+/// Error returned by the frame-level editing methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditError {
+    /// The frame index is past the end of the script.
+    FrameOutOfBounds {
+        /// The frame index.
+        frame: u64,
+        /// Number of frames in the script.
+        total_frames: u64,
+    },
+    /// The start of the frame range is after its end.
+    InvalidRange {
+        /// Start of the range.
+        start: u64,
+        /// End of the range.
+        end: u64,
+    },
+    /// A frame bulk uses constant yawspeed strafing, which can't have a yaw angle.
+    ConstantYawspeed {
+        /// Index of the frame bulk in [`HLTAS::lines`].
+        line_index: usize,
+    },
+}
+
+/// Returns the line index and the first frame of every frame bulk, and the total frame count.
+fn frame_bulk_starts(lines: &[Line]) -> (Vec<(usize, u64)>, u64) {
+    let mut starts = Vec::new();
+    let mut frame = 0;
+    for (line_index, line) in lines.iter().enumerate() {
+        if let Line::FrameBulk(frame_bulk) = line {
+            starts.push((line_index, frame));
+            frame += u64::from(frame_bulk.frame_count.get());
+        }
+    }
+    (starts, frame)
+}
+
+fn check_frame(lines: &[Line], frame: u64) -> Result<(), EditError> {
+    let (_, total_frames) = frame_bulk_starts(lines);
+    if frame > total_frames {
+        Err(EditError::FrameOutOfBounds {
+            frame,
+            total_frames,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_range(lines: &[Line], range: &Range<u64>) -> Result<(), EditError> {
+    if range.start > range.end {
+        return Err(EditError::InvalidRange {
+            start: range.start,
+            end: range.end,
+        });
+    }
+    check_frame(lines, range.end)
+}
+
+/// Removes the auto-actions with limited times.
+fn strip_limited_times(frame_bulk: &mut FrameBulk) {
+    fn strip<T>(action: &mut Option<T>, times: impl Fn(&T) -> Times) {
+        if matches!(action.as_ref().map(times), Some(Times::Limited(_))) {
+            *action = None;
+        }
+    }
+
+    let aa = &mut frame_bulk.auto_actions;
+    strip(&mut aa.leave_ground_action, |x| x.times);
+    strip(&mut aa.jump_bug, |x| x.times);
+    strip(&mut aa.duck_before_collision, |x| x.times);
+    strip(&mut aa.duck_before_ground, |x| x.times);
+    strip(&mut aa.duck_when_jump, |x| x.times);
+}
+
+fn set_strafe_dir(frame_bulk: &mut FrameBulk, dir: StrafeDir) {
+    if let Some(AutoMovement::Strafe(settings)) = &mut frame_bulk.auto_actions.movement {
+        settings.dir = dir;
+    }
+}
+
+fn with_frame_count(frame_bulk: &FrameBulk, frame_count: u32) -> FrameBulk {
+    FrameBulk {
+        frame_count: NonZeroU32::new(frame_count).unwrap(),
+        ..frame_bulk.clone()
+    }
+}
+
+/// Returns frame bulks behaving like the frames of `frame_bulk` from `offset` onwards.
+///
+/// Left-right strafing that is in the middle of a side at `offset` needs two frame bulks: one
+/// finishing the side and one alternating from the next side. Limited times are kept on the first
+/// returned frame bulk if `keep_limited_times` is `true`, and removed otherwise.
+pub(crate) fn tail(
+    frame_bulk: &FrameBulk,
+    offset: u32,
+    keep_limited_times: bool,
+) -> Vec<FrameBulk> {
+    let frame_count = frame_bulk.frame_count.get() - offset;
+    let mut first = with_frame_count(frame_bulk, frame_count);
+    if !keep_limited_times {
+        strip_limited_times(&mut first);
+    }
+
+    let (count, starts_left) = match frame_bulk.auto_actions.movement {
+        Some(AutoMovement::Strafe(settings)) => match settings.dir {
+            StrafeDir::LeftRight(count) => (count, true),
+            StrafeDir::RightLeft(count) => (count, false),
+            _ => return vec![first],
+        },
+        _ => return vec![first],
+    };
+
+    let alternating = |left: bool| {
+        if left {
+            StrafeDir::LeftRight(count)
+        } else {
+            StrafeDir::RightLeft(count)
+        }
+    };
+
+    let side_index = offset / count.get();
+    let left = starts_left == side_index.is_multiple_of(2);
+    let phase = offset % count.get();
+    if phase == 0 {
+        set_strafe_dir(&mut first, alternating(left));
+        return vec![first];
+    }
+
+    let remaining_on_side = count.get() - phase;
+    if frame_count <= remaining_on_side {
+        set_strafe_dir(
+            &mut first,
+            if left {
+                StrafeDir::Left
+            } else {
+                StrafeDir::Right
+            },
+        );
+        return vec![first];
+    }
+
+    first.frame_count = NonZeroU32::new(remaining_on_side).unwrap();
+    set_strafe_dir(
+        &mut first,
+        if left {
+            StrafeDir::Left
+        } else {
+            StrafeDir::Right
+        },
+    );
+
+    let mut second = with_frame_count(frame_bulk, frame_count - remaining_on_side);
+    strip_limited_times(&mut second);
+    set_strafe_dir(&mut second, alternating(!left));
+
+    vec![first, second]
+}
+
+/// Returns the frame bulk behaving like `first` followed by `second`, if there is one.
+pub(crate) fn append(first: &FrameBulk, second: &FrameBulk) -> Option<FrameBulk> {
+    let frame_count = first
+        .frame_count
+        .get()
+        .checked_add(second.frame_count.get())?;
+    let merged = with_frame_count(first, frame_count);
+
+    // The merged frame bulk behaves like `first` on its first frames, so check that it behaves
+    // like `second` on the rest.
+    if tail(&merged, first.frame_count.get(), false) == [second.clone()] {
+        Some(merged)
+    } else {
+        None
+    }
+}
+
+/// Splits the frame bulk containing `frame` so that a frame bulk starts at `frame`.
+///
+/// Returns the index in `lines` right after the last frame bulk ending at or before `frame`,
+/// which is where lines are inserted at `frame`.
+///
+/// If `keep_limited_times` is `true`, auto-actions with limited times are moved to the second part
+/// instead of staying on the first part.
+fn split(lines: &mut Vec<Line>, frame: u64, keep_limited_times: bool) -> usize {
+    let (starts, _) = frame_bulk_starts(lines);
+
+    for (line_index, start) in starts {
+        let frame_bulk = match &lines[line_index] {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => unreachable!(),
+        };
+        let end = start + u64::from(frame_bulk.frame_count.get());
+
+        if frame == start {
+            break;
+        }
+        if frame >= end {
+            continue;
+        }
+
+        let offset = (frame - start) as u32;
+        let mut head = with_frame_count(frame_bulk, offset);
+        if keep_limited_times {
+            strip_limited_times(&mut head);
+        }
+        let tail = tail(frame_bulk, offset, keep_limited_times);
+
+        lines[line_index] = Line::FrameBulk(head);
+        lines.splice(
+            line_index + 1..line_index + 1,
+            tail.into_iter().map(Line::FrameBulk),
+        );
+        return line_index + 1;
+    }
+
+    // The frame is at a frame bulk boundary.
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line, Line::FrameBulk(_)))
+        .scan(0, |frames, (line_index, line)| {
+            if let Line::FrameBulk(frame_bulk) = line {
+                *frames += u64::from(frame_bulk.frame_count.get());
+            }
+            Some((line_index, *frames))
+        })
+        .take_while(|&(_, end)| end <= frame)
+        .last()
+        .map_or(0, |(line_index, _)| line_index + 1)
+}
+
+pub(crate) fn split_at_frame(hltas: &mut HLTAS, frame: u64) -> Result<usize, EditError> {
+    check_frame(&hltas.lines, frame)?;
+    let point = split(&mut hltas.lines, frame, false);
+
+    Ok(hltas.lines[point..]
+        .iter()
+        .position(|line| matches!(line, Line::FrameBulk(_)))
+        .map_or(hltas.lines.len(), |offset| point + offset))
+}
+
+fn override_line(kind: OverrideKind, values: Vec<f32>) -> Line {
+    match kind {
+        OverrideKind::TargetYaw => Line::TargetYawOverride(values),
+        OverrideKind::RenderYaw => Line::RenderYawOverride(values),
+        OverrideKind::Pitch => Line::PitchOverride(values),
+        OverrideKind::RenderPitch => Line::RenderPitchOverride(values),
+    }
+}
+
+fn override_values_mut(line: &mut Line) -> Option<&mut Vec<f32>> {
+    match line {
+        Line::TargetYawOverride(values)
+        | Line::RenderYawOverride(values)
+        | Line::PitchOverride(values)
+        | Line::RenderPitchOverride(values) => Some(values),
+        _ => None,
+    }
+}
+
+pub(crate) fn insert_lines_at_frame(
+    hltas: &mut HLTAS,
+    frame: u64,
+    new_lines: impl IntoIterator<Item = Line>,
+) -> Result<(), EditError> {
+    check_frame(&hltas.lines, frame)?;
+    let point = split(&mut hltas.lines, frame, false);
+
+    // Override lines before the insertion point with values left for the following frames.
+    let mut moved = Vec::new();
+    {
+        let analysis = overrides::analyze(hltas);
+        for kind in OverrideKind::ALL {
+            let application = analysis
+                .applications
+                .iter()
+                .rfind(|application| application.kind == kind && application.line_index < point);
+
+            if let Some(application) = application {
+                let consumed = (frame - application.start_frame) as usize;
+                if consumed < application.values.len() {
+                    moved.push((application.line_index, kind, consumed));
+                }
+            }
+        }
+    }
+
+    let mut moved_lines = Vec::new();
+    let mut removed = 0;
+    for (line_index, kind, consumed) in moved {
+        let values = override_values_mut(&mut hltas.lines[line_index]).unwrap();
+        moved_lines.push(override_line(kind, values.split_off(consumed)));
+        if values.is_empty() {
+            removed += 1;
+        }
+    }
+    hltas.lines.retain(|line| match line {
+        Line::TargetYawOverride(values)
+        | Line::RenderYawOverride(values)
+        | Line::PitchOverride(values)
+        | Line::RenderPitchOverride(values) => !values.is_empty(),
+        _ => true,
+    });
+    let point = point - removed;
+
+    let mut inserted: Vec<Line> = new_lines.into_iter().collect();
+    inserted.extend(moved_lines);
+    hltas.lines.splice(point..point, inserted);
+    Ok(())
+}
+
+pub(crate) fn delete_frames(hltas: &mut HLTAS, range: Range<u64>) -> Result<(), EditError> {
+    check_range(&hltas.lines, &range)?;
+    if range.is_empty() {
+        return Ok(());
+    }
+
+    // If the frame bulk containing the end starts inside the range, its first frame is deleted, so
+    // its limited times move to the remaining part.
+    let (starts, _) = frame_bulk_starts(&hltas.lines);
+    let keep_limited_times = starts
+        .iter()
+        .rev()
+        .find(|&&(_, start)| start < range.end)
+        .is_some_and(|&(_, start)| start >= range.start);
+    split(&mut hltas.lines, range.end, keep_limited_times);
+    split(&mut hltas.lines, range.start, false);
+
+    // Delete the override values applied to the deleted frames.
+    let mut deleted_values = Vec::new();
+    for application in overrides::analyze(hltas).applications {
+        let start = application.start_frame;
+        let end = start + application.applied as u64;
+        let from = range.start.clamp(start, end) - start;
+        let to = range.end.clamp(start, end) - start;
+        if from < to {
+            deleted_values.push((application.line_index, from as usize..to as usize));
+        }
+    }
+    for (line_index, values_range) in deleted_values {
+        override_values_mut(&mut hltas.lines[line_index])
+            .unwrap()
+            .drain(values_range);
+    }
+
+    let mut frame = 0;
+    hltas.lines.retain(|line| match line {
+        Line::FrameBulk(frame_bulk) => {
+            let start = frame;
+            frame += u64::from(frame_bulk.frame_count.get());
+            !range.contains(&start)
+        }
+        Line::TargetYawOverride(values)
+        | Line::RenderYawOverride(values)
+        | Line::PitchOverride(values)
+        | Line::RenderPitchOverride(values) => !values.is_empty(),
+        _ => true,
+    });
+
+    Ok(())
+}
+
+pub(crate) fn set_yaw(hltas: &mut HLTAS, range: Range<u64>, yaw: f32) -> Result<(), EditError> {
+    check_range(&hltas.lines, &range)?;
+    if range.is_empty() {
+        return Ok(());
+    }
+
+    let (starts, _) = frame_bulk_starts(&hltas.lines);
+    for (line_index, start) in starts {
+        let frame_bulk = match &hltas.lines[line_index] {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => unreachable!(),
+        };
+        let end = start + u64::from(frame_bulk.frame_count.get());
+        let overlaps = start < range.end && range.start < end;
+
+        if let Some(AutoMovement::Strafe(settings)) = frame_bulk.auto_actions.movement {
+            if overlaps && matches!(settings.type_, StrafeType::ConstYawspeed(_)) {
+                return Err(EditError::ConstantYawspeed { line_index });
+            }
+        }
+    }
+
+    split(&mut hltas.lines, range.end, false);
+    split(&mut hltas.lines, range.start, false);
+
+    let mut frame = 0;
+    for line in &mut hltas.lines {
+        if let Line::FrameBulk(frame_bulk) = line {
+            let start = frame;
+            frame += u64::from(frame_bulk.frame_count.get());
+            if !range.contains(&start) {
+                continue;
+            }
+
+            let movement = &mut frame_bulk.auto_actions.movement;
+            match movement {
+                Some(AutoMovement::Strafe(settings)) => match &mut settings.dir {
+                    StrafeDir::Yaw(x) | StrafeDir::Line { yaw: x } => *x = yaw,
+                    dir => *dir = StrafeDir::Yaw(yaw),
+                },
+                _ => *movement = Some(AutoMovement::SetYaw(yaw)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::FrameOutOfBounds {
+                frame,
+                total_frames,
+            } => write!(
+                f,
+                "frame {} is out of bounds of the script with {} frames",
+                frame, total_frames
+            ),
+            EditError::InvalidRange { start, end } => {
+                write!(f, "the frame range {}..{} is invalid", start, end)
+            }
+            EditError::ConstantYawspeed { line_index } => write!(
+                f,
+                "the frame bulk in line {} uses constant yawspeed strafing, which can't have a yaw",
+                line_index
+            ),
+        }
+    }
+}
+
+impl error::Error for EditError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        frames::behaviors,
+        test_util::{for_each_test_script, frame_counts, parse_frames},
+    };
+
+    #[test]
+    fn split_keeps_behavior() {
+        let contents = "\
+            strafing vectorial\n\
+            target_yaw_override 1 2 3 4 5 6 7\n\
+            s06l3j-bc2--|------|------|0.001|3|-|10\n\
+            s07-------|------|------|0.001|2|-|5\n\
+            -------c1--|------|------|0.001|-|-|4";
+        let original = parse_frames(contents);
+        let expected = behaviors(&original);
+
+        for frame in 0..=19 {
+            let mut edited = original.clone();
+            edited.split_at_frame(frame).unwrap();
+            assert_eq!(behaviors(&edited), expected, "split at {}", frame);
+        }
+    }
+
+    #[test]
+    fn split_test_data() {
+        for_each_test_script(|path, contents| {
+            let original = HLTAS::from_str(contents).unwrap();
+            let expected = behaviors(&original);
+            let total = expected.len() as u64;
+
+            for frame in [1, total / 3, total / 2, total.saturating_sub(1)] {
+                let mut edited = original.clone();
+                edited.split_at_frame(frame.min(total)).unwrap();
+                assert_eq!(behaviors(&edited), expected, "{}", path.display());
+            }
+        });
+    }
+
+    #[test]
+    fn split_returns_frame_bulk() {
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|5\n\
+             buttons\n\
+             ----------|------|------|0.001|-|-|5",
+        );
+
+        assert_eq!(hltas.split_at_frame(0), Ok(0));
+        assert_eq!(hltas.split_at_frame(5), Ok(2));
+        assert_eq!(hltas.split_at_frame(7), Ok(3));
+        assert_eq!(frame_counts(&hltas), [5, 2, 3]);
+        assert_eq!(hltas.split_at_frame(10), Ok(4));
+        assert_eq!(
+            hltas.split_at_frame(11),
+            Err(EditError::FrameOutOfBounds {
+                frame: 11,
+                total_frames: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let mut hltas = parse_frames(
+            "pitch_override 1 2 3 4\n\
+             ----------|------|------|0.001|-|-|3\n\
+             buttons\n\
+             ----------|------|------|0.001|-|-|1",
+        );
+        let inserted = Line::FrameBulk(FrameBulk::with_frame_time("0.004".to_owned()));
+
+        hltas.insert_lines_at_frame(2, [inserted.clone()]).unwrap();
+        assert_eq!(
+            hltas.lines,
+            [
+                Line::PitchOverride(vec![1., 2.]),
+                hltas.lines[1].clone(),
+                inserted.clone(),
+                Line::PitchOverride(vec![3., 4.]),
+                hltas.lines[4].clone(),
+                Line::Buttons(crate::types::Buttons::Reset),
+                hltas.lines[6].clone(),
+            ]
+        );
+        assert_eq!(frame_counts(&hltas), [2, 1, 1, 1]);
+
+        let pitches: Vec<_> = hltas
+            .frames()
+            .map(|frame| frame.state.pitch_override)
+            .collect();
+        assert_eq!(pitches, [Some(1.), Some(2.), None, Some(3.), Some(4.)]);
+
+        // At a boundary, the lines go before the lines preceding the next frame bulk.
+        hltas
+            .insert_lines_at_frame(4, [Line::Comment("x".to_owned())])
+            .unwrap();
+        assert_eq!(hltas.lines[5], Line::Comment("x".to_owned()));
+        hltas
+            .insert_lines_at_frame(0, [Line::Comment("y".to_owned())])
+            .unwrap();
+        assert_eq!(hltas.lines[0], Line::Comment("y".to_owned()));
+    }
+
+    #[test]
+    fn delete() {
+        let original = parse_frames(
+            "----------|------|------|0.001|-|-|3\n\
+             render_yaw_override 1 2 3 4\n\
+             ----------|------|------|0.004|-|-|2\n\
+             buttons\n\
+             ------b2---|------|------|0.001|-|-|3",
+        );
+
+        let mut hltas = original.clone();
+        hltas.delete_frames(2..6).unwrap();
+        assert_eq!(frame_counts(&hltas), [2, 2]);
+        assert_eq!(hltas.lines[1], Line::RenderYawOverride(vec![4.]));
+        assert!(matches!(hltas.lines[2], Line::Buttons(_)));
+        // The deleted frames included the first frame of the jumpbug frame bulk.
+        let frames: Vec<_> = hltas.frames().collect();
+        assert!(frames[2].auto_actions.jump_bug.is_some());
+
+        let mut hltas = original.clone();
+        hltas.delete_frames(3..5).unwrap();
+        assert_eq!(frame_counts(&hltas), [3, 3]);
+        assert_eq!(hltas.lines[1], Line::RenderYawOverride(vec![3., 4.]));
+
+        let mut hltas = original.clone();
+        hltas.delete_frames(0..8).unwrap();
+        assert_eq!(hltas.frame_bulks().count(), 0);
+        assert_eq!(hltas.lines.len(), 1);
+
+        let mut hltas = original.clone();
+        hltas.delete_frames(1..1).unwrap();
+        assert_eq!(hltas, original);
+        assert_eq!(
+            hltas.delete_frames(Range { start: 5, end: 4 }),
+            Err(EditError::InvalidRange { start: 5, end: 4 })
+        );
+    }
+
+    #[test]
+    fn set_yaw() {
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|4\n\
+             s03-------|------|------|0.001|10|-|4\n\
+             s00-------|------|------|0.001|-|-|4",
+        );
+        hltas.set_yaw(2..10, 90.).unwrap();
+        assert_eq!(frame_counts(&hltas), [2, 2, 4, 2, 2]);
+
+        let yaws: Vec<_> = hltas
+            .frame_bulks()
+            .map(|frame_bulk| frame_bulk.auto_actions.movement)
+            .collect();
+        let strafe = |dir| {
+            Some(AutoMovement::Strafe(crate::types::StrafeSettings {
+                type_: StrafeType::MaxAccel,
+                dir,
+            }))
+        };
+        assert_eq!(
+            yaws,
+            [
+                None,
+                Some(AutoMovement::SetYaw(90.)),
+                strafe(StrafeDir::Yaw(90.)),
+                strafe(StrafeDir::Yaw(90.)),
+                strafe(StrafeDir::Left),
+            ]
+        );
+
+        let mut hltas = parse_frames("s40-------|------|------|0.001|10|-|4");
+        assert_eq!(
+            hltas.set_yaw(0..1, 90.),
+            Err(EditError::ConstantYawspeed { line_index: 0 })
+        );
+    }
+}
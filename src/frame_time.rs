@@ -0,0 +1,320 @@
+//! Frame time quantisation analysis.
+//!
+//! GoldSrc doesn't move the player by the frame time directly. The frame time is stored as a
+//! 32-bit float, and the `msec` field of the usercmd is that value times 1000, truncated to a
+//! whole number of milliseconds and capped at 255. Player movement then uses `msec`, while the
+//! game time advances by the full frame time, so any leftover fraction of a millisecond is lost
+//! from movement on every frame.
+//!
+//! This is why scripts use frame times like `0.010000001`: `0.01` as a float is slightly below
+//! 0.01 and truncates to 9 msec, while `0.010000001` gives the intended 10 msec. This module
+//! computes the msec the engine will use for every frame bulk, the drift between game time and
+//! movement time that builds up over the bulk, and flags frame times that likely don't do what
+//! the author meant.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{frame_time, HLTAS};
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.01|-|-|100
+//! ----------|------|------|0.010000001|-|-|100";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let analysis = frame_time::analyze(&hltas);
+//!
+//! assert_eq!(analysis.frame_bulks[0].quantization.unwrap().msec, 9);
+//! assert!(!analysis.frame_bulks[0].issues.is_empty());
+//!
+//! assert_eq!(analysis.frame_bulks[1].quantization.unwrap().msec, 10);
+//! assert!(analysis.frame_bulks[1].issues.is_empty());
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    timeline::Time,
+    types::{LeaveGroundActionType, Line},
+    HLTAS,
+};
+
+/// The largest msec value the usercmd can hold.
+pub const MAX_MSEC: u32 = 255;
+
+/// Leftover below this many milliseconds per frame is considered intentional.
+///
+/// Frame times such as `0.010000001` are slightly above a whole millisecond on purpose, to
+/// counter the float rounding.
+const EPSILON_MSEC: f64 = 0.01;
+
+/// How the engine quantises a frame time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    /// The frame time as the engine stores it.
+    pub engine_frame_time: f32,
+    /// The exact decimal value of the frame time as written, if it's a valid non-negative
+    /// number.
+    pub exact: Option<Time>,
+    /// Milliseconds used for player movement.
+    pub msec: u32,
+    /// Game time minus movement time for one frame, in seconds.
+    pub drift_per_frame: f64,
+}
+
+/// Something about a frame time that the author probably didn't intend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+    /// The frame time couldn't be parsed as a number.
+    Invalid,
+    /// The frame time is a whole number of milliseconds, but float rounding makes it one
+    /// millisecond less.
+    RoundsDown {
+        /// The whole number of milliseconds that was written.
+        intended: u32,
+    },
+    /// A noticeable fraction of a millisecond is lost from movement on every frame.
+    Truncated,
+    /// The frame time is non-zero but gives zero msec, so the player won't move.
+    ZeroMsec,
+    /// The frame time is over 255 ms and gets capped.
+    Capped,
+    /// `frametime0ms` gives a non-zero msec, so 0 ms ducktaps won't be 0 ms.
+    NonZeroFrameTime0ms,
+}
+
+/// Quantisation of one frame bulk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBulkQuantization {
+    /// Index of the frame bulk in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Quantisation of the frame time, or `None` if it couldn't be parsed.
+    pub quantization: Option<Quantization>,
+    /// Number of frames in the frame bulk.
+    pub frame_count: u32,
+    /// Game time minus movement time over the whole frame bulk, in seconds.
+    pub drift: f64,
+    /// Whether the frame bulk does 0 ms ducktaps.
+    ///
+    /// Some of its frames then use [`Properties::frametime_0ms`](crate::types::Properties)
+    /// instead of its frame time, so the drift and the duration are not exact.
+    pub zero_ms_ducktap: bool,
+    /// Problems with the frame time.
+    pub issues: Vec<Issue>,
+}
+
+/// Quantisation of a whole script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// Quantisation of every frame bulk, in order.
+    pub frame_bulks: Vec<FrameBulkQuantization>,
+    /// Quantisation of [`Properties::frametime_0ms`](crate::types::Properties), if it's set.
+    pub frame_time_0ms: Option<Quantization>,
+    /// Problems with `frametime0ms`.
+    pub frame_time_0ms_issues: Vec<Issue>,
+    /// Game time minus movement time over the whole script, in seconds.
+    pub total_drift: f64,
+}
+
+/// Computes how the engine quantises a frame time.
+///
+/// Returns `None` if the frame time can't be parsed as a number.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate hltas;
+/// use hltas::frame_time::quantize;
+///
+/// assert_eq!(quantize("0.001").unwrap().msec, 1);
+/// assert_eq!(quantize("0.004").unwrap().msec, 4);
+/// assert_eq!(quantize("0.01").unwrap().msec, 9);
+/// assert_eq!(quantize("0.010000001").unwrap().msec, 10);
+/// ```
+pub fn quantize(frame_time: &str) -> Option<Quantization> {
+    let engine_frame_time = frame_time.parse::<f32>().ok()?;
+    if !engine_frame_time.is_finite() {
+        return None;
+    }
+
+    let msec = (f64::from(engine_frame_time) * 1000.).max(0.) as u32;
+    let msec = msec.min(MAX_MSEC);
+
+    let exact = frame_time.parse::<Time>().ok();
+    let seconds = exact.map_or(f64::from(engine_frame_time), Time::as_secs_f64);
+
+    Some(Quantization {
+        engine_frame_time,
+        exact,
+        msec,
+        drift_per_frame: seconds - f64::from(msec) / 1000.,
+    })
+}
+
+/// Returns the problems with a frame time used for regular frames.
+fn issues(quantization: Option<&Quantization>) -> Vec<Issue> {
+    let q = match quantization {
+        Some(q) => q,
+        None => return vec![Issue::Invalid],
+    };
+
+    let mut issues = Vec::new();
+    let written_msec = q
+        .exact
+        .map_or(f64::from(q.engine_frame_time), Time::as_secs_f64)
+        * 1000.;
+
+    if written_msec >= f64::from(MAX_MSEC + 1) {
+        issues.push(Issue::Capped);
+        return issues;
+    }
+
+    if let Some(exact) = q.exact {
+        let millis = Time::from_millis(exact.as_millis() as u64);
+        if millis == exact && exact != Time::ZERO && q.msec + 1 == exact.as_millis() as u32 {
+            issues.push(Issue::RoundsDown {
+                intended: exact.as_millis() as u32,
+            });
+            return issues;
+        }
+    }
+
+    if q.msec == 0 && written_msec > 0. {
+        issues.push(Issue::ZeroMsec);
+    } else if written_msec - f64::from(q.msec) >= EPSILON_MSEC {
+        issues.push(Issue::Truncated);
+    }
+
+    issues
+}
+
+/// Analyses the quantisation of every frame bulk in a script.
+pub fn analyze(hltas: &HLTAS) -> Analysis {
+    let mut frame_bulks = Vec::new();
+    let mut total_drift = 0.;
+
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        let frame_bulk = match line {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => continue,
+        };
+
+        let quantization = quantize(&frame_bulk.frame_time);
+        let frame_count = frame_bulk.frame_count.get();
+        let drift = quantization.map_or(0., |q| q.drift_per_frame * f64::from(frame_count));
+        total_drift += drift;
+
+        let zero_ms_ducktap = matches!(
+            frame_bulk.auto_actions.leave_ground_action,
+            Some(action) if action.type_ == LeaveGroundActionType::DuckTap { zero_ms: true }
+        );
+
+        frame_bulks.push(FrameBulkQuantization {
+            line_index,
+            issues: issues(quantization.as_ref()),
+            quantization,
+            frame_count,
+            drift,
+            zero_ms_ducktap,
+        });
+    }
+
+    let frame_time_0ms = hltas.properties.frametime_0ms.as_deref().map(quantize);
+    let frame_time_0ms_issues = match frame_time_0ms {
+        None => Vec::new(),
+        Some(None) => vec![Issue::Invalid],
+        Some(Some(q)) if q.msec != 0 => vec![Issue::NonZeroFrameTime0ms],
+        Some(Some(_)) => Vec::new(),
+    };
+
+    Analysis {
+        frame_bulks,
+        frame_time_0ms: frame_time_0ms.flatten(),
+        frame_time_0ms_issues,
+        total_drift,
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Invalid => write!(f, "the frame time is not a valid number"),
+            Issue::RoundsDown { intended } => write!(
+                f,
+                "the frame time gives {} msec rather than {} because of float rounding",
+                intended - 1,
+                intended
+            ),
+            Issue::Truncated => write!(
+                f,
+                "a fraction of a millisecond is lost from movement on every frame"
+            ),
+            Issue::ZeroMsec => write!(f, "the frame time gives 0 msec, so the player won't move"),
+            Issue::Capped => write!(f, "the frame time is over {} ms and gets capped", MAX_MSEC),
+            Issue::NonZeroFrameTime0ms => {
+                write!(
+                    f,
+                    "frametime0ms gives a non-zero msec, so 0 ms ducktaps won't work"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues_of(frame_time: &str) -> Vec<Issue> {
+        issues(quantize(frame_time).as_ref())
+    }
+
+    #[test]
+    fn msec() {
+        assert_eq!(quantize("0.001").unwrap().msec, 1);
+        assert_eq!(quantize("0.0019").unwrap().msec, 1);
+        assert_eq!(quantize("0.004").unwrap().msec, 4);
+        assert_eq!(quantize("0.01").unwrap().msec, 9);
+        assert_eq!(quantize("0.010000001").unwrap().msec, 10);
+        assert_eq!(quantize("0.0000001").unwrap().msec, 0);
+        assert_eq!(quantize("1").unwrap().msec, MAX_MSEC);
+        assert_eq!(quantize("x"), None);
+        assert_eq!(quantize("inf"), None);
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(issues_of("0.001"), []);
+        assert_eq!(issues_of("0.004"), []);
+        assert_eq!(issues_of("0.010000001"), []);
+        assert_eq!(issues_of("0"), []);
+        assert_eq!(issues_of("0.01"), [Issue::RoundsDown { intended: 10 }]);
+        assert_eq!(issues_of("0.0015"), [Issue::Truncated]);
+        assert_eq!(issues_of("0.0005"), [Issue::ZeroMsec]);
+        assert_eq!(issues_of("0.5"), [Issue::Capped]);
+        assert_eq!(issues_of("0.256"), [Issue::Capped]);
+        assert_eq!(issues_of("0.255"), [Issue::RoundsDown { intended: 255 }]);
+        assert_eq!(issues_of("a"), [Issue::Invalid]);
+    }
+
+    #[test]
+    fn drift() {
+        let hltas = HLTAS::from_str(
+            "version 1\nframetime0ms 0.001\nframes\n\
+             ----------|------|------|0.0015|-|-|10\n\
+             s03l-D----|------|------|0.001|90|-|5",
+        )
+        .unwrap();
+        let analysis = analyze(&hltas);
+
+        assert_eq!(analysis.frame_bulks.len(), 2);
+        assert!((analysis.frame_bulks[0].drift - 0.005).abs() < 1e-9);
+        assert!(!analysis.frame_bulks[0].zero_ms_ducktap);
+        assert!(analysis.frame_bulks[1].zero_ms_ducktap);
+        assert!((analysis.total_drift - 0.005).abs() < 1e-9);
+        assert_eq!(analysis.frame_time_0ms_issues, [Issue::NonZeroFrameTime0ms]);
+    }
+}
@@ -0,0 +1,612 @@
+//! Expansion of scripts into individual frames.
+//!
+//! A frame bulk alone doesn't describe what happens on its frames: preceding lines such as
+//! `buttons`, `lgagstminspeed`, `strafing`, `target_yaw`, `change` and the override lines set up
+//! state that carries over to the following frame bulks, and auto-actions with a limited number
+//! of times keep going past the frame bulk that enabled them. [`Frames`] walks the script and
+//! yields one [`Frame`] per frame with all of that state resolved.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{types::StrafeDir, HLTAS};
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! strafing vectorial
+//! target_yaw_override 10 20 30
+//! s06-------|------|------|0.001|1|-|4";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let frames: Vec<_> = hltas.frames().collect();
+//! assert_eq!(frames.len(), 4);
+//!
+//! assert!(frames[0].state.vectorial_strafing);
+//! assert_eq!(frames[0].state.target_yaw_override, Some(10.));
+//! assert_eq!(frames[3].state.target_yaw_override, None);
+//!
+//! // Left-right strafing is resolved to the direction of each frame.
+//! assert_eq!(frames[0].strafe_dir, Some(StrafeDir::Left));
+//! assert_eq!(frames[1].strafe_dir, Some(StrafeDir::Right));
+//! ```
+
+use std::num::NonZeroU32;
+
+use crate::{
+    types::{
+        AutoActions, AutoMovement, Buttons, Change, ChangeTarget, FrameBulk, Line, StrafeDir,
+        Times, VectorialStrafingConstraints,
+    },
+    HLTAS,
+};
+
+/// A single frame of a script with the state resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<'a> {
+    /// Index of this frame in the script, starting from 0.
+    pub index: u64,
+    /// Index of the frame bulk of this frame in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Index of this frame within its frame bulk, starting from 0.
+    pub index_in_frame_bulk: u32,
+    /// The frame bulk of this frame.
+    pub frame_bulk: &'a FrameBulk,
+    /// Non-frame-bulk lines between the previous frame bulk and this one.
+    ///
+    /// Only set on the first frame of a frame bulk, empty otherwise.
+    pub preceding_lines: &'a [Line],
+    /// Auto-actions executed on this frame.
+    ///
+    /// Like the C++ library's `Frame::ResetAutofuncs()`, auto-actions with a limited number of
+    /// times are only present on the first frame of their frame bulk, where they set the number
+    /// of times. The number of times still remaining is tracked in [`State::limited_times`].
+    pub auto_actions: AutoActions,
+    /// Strafing direction on this frame.
+    ///
+    /// [`StrafeDir::LeftRight`] and [`StrafeDir::RightLeft`] are resolved into
+    /// [`StrafeDir::Left`] or [`StrafeDir::Right`], counting from the start of the frame bulk.
+    /// `None` if the frame bulk doesn't strafe.
+    pub strafe_dir: Option<StrafeDir>,
+    /// State carried over from the preceding lines.
+    pub state: State,
+}
+
+/// State set up by non-frame-bulk lines, as of a given frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    /// Strafing buttons.
+    pub buttons: Buttons,
+    /// Minimum speed for the optimal leave-ground-action speed, if it was set.
+    pub lgagst_min_speed: Option<f32>,
+    /// Whether vectorial strafing is enabled.
+    pub vectorial_strafing: bool,
+    /// Constraints for vectorial strafing, if they were set.
+    pub constraints: Option<VectorialStrafingConstraints>,
+    /// Changes in progress, at most one per [`ChangeTarget`], in the order they were started.
+    pub changes: Vec<ActiveChange>,
+    /// Target yaw override value for this frame.
+    pub target_yaw_override: Option<f32>,
+    /// Render yaw override value for this frame.
+    pub render_yaw_override: Option<f32>,
+    /// Pitch override value for this frame.
+    pub pitch_override: Option<f32>,
+    /// Render pitch override value for this frame.
+    pub render_pitch_override: Option<f32>,
+    /// The shared seed to use on the next load, if it was set with a `seed` line.
+    pub shared_seed: Option<u32>,
+    /// The non-shared seed of the last engine reset, if there was one.
+    pub non_shared_seed: Option<i64>,
+    /// Auto-actions with a limited number of times which are still in effect.
+    pub limited_times: LimitedTimes,
+}
+
+/// A change in progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveChange {
+    /// The change line.
+    pub change: Change,
+    /// Index of the change line in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Time in seconds elapsed since the start of the change, at the start of this frame.
+    pub elapsed: f32,
+}
+
+/// Number of times set for auto-actions with a limited number of times.
+///
+/// An auto-action with a limited number of times remains in effect past its frame bulk until it
+/// has been executed that many times or until a later frame bulk specifies the same auto-action.
+/// How many times an action has actually been executed depends on the game, so these are the
+/// numbers of times that were set, not the ones remaining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LimitedTimes {
+    /// Automatic jumping and ducktapping.
+    pub leave_ground_action: Option<NonZeroU32>,
+    /// Automatic jumpbug.
+    pub jump_bug: Option<NonZeroU32>,
+    /// Duck before collision.
+    pub duck_before_collision: Option<NonZeroU32>,
+    /// Duck before ground.
+    pub duck_before_ground: Option<NonZeroU32>,
+    /// Duck when jump.
+    pub duck_when_jump: Option<NonZeroU32>,
+}
+
+/// Values of an override line, applied one per frame.
+#[derive(Debug, Clone, Copy, Default)]
+struct Override<'a> {
+    values: &'a [f32],
+    position: usize,
+}
+
+impl Override<'_> {
+    fn current(&self) -> Option<f32> {
+        self.values.get(self.position).copied()
+    }
+}
+
+/// Iterator over the frames of a script.
+///
+/// Created with [`HLTAS::frames()`].
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    lines: &'a [Line],
+    /// Index of the next line to process.
+    next_line: usize,
+    /// The current frame bulk, its line index and the index of the next frame within it.
+    current: Option<(&'a FrameBulk, usize, u32)>,
+    index: u64,
+    state: State,
+    target_yaw_override: Override<'a>,
+    render_yaw_override: Override<'a>,
+    pitch_override: Override<'a>,
+    render_pitch_override: Override<'a>,
+}
+
+impl<'a> Frames<'a> {
+    pub(crate) fn new(hltas: &'a HLTAS) -> Self {
+        Self {
+            lines: &hltas.lines,
+            next_line: 0,
+            current: None,
+            index: 0,
+            state: State {
+                buttons: Buttons::Reset,
+                lgagst_min_speed: None,
+                vectorial_strafing: false,
+                constraints: None,
+                changes: Vec::new(),
+                target_yaw_override: None,
+                render_yaw_override: None,
+                pitch_override: None,
+                render_pitch_override: None,
+                shared_seed: None,
+                non_shared_seed: hltas.properties.seeds.map(|seeds| seeds.non_shared),
+                limited_times: LimitedTimes::default(),
+            },
+            target_yaw_override: Override::default(),
+            render_yaw_override: Override::default(),
+            pitch_override: Override::default(),
+            render_pitch_override: Override::default(),
+        }
+    }
+
+    /// Applies the non-frame-bulk lines up to the next frame bulk.
+    ///
+    /// Returns the applied lines and the next frame bulk with its line index.
+    fn advance_to_frame_bulk(&mut self) -> Option<(&'a [Line], &'a FrameBulk, usize)> {
+        let start = self.next_line;
+
+        while let Some(line) = self.lines.get(self.next_line) {
+            let line_index = self.next_line;
+            self.next_line += 1;
+
+            match line {
+                Line::FrameBulk(frame_bulk) => {
+                    return Some((&self.lines[start..line_index], frame_bulk, line_index));
+                }
+                Line::SharedSeed(seed) => self.state.shared_seed = Some(*seed),
+                Line::Buttons(buttons) => self.state.buttons = *buttons,
+                Line::LGAGSTMinSpeed(speed) => self.state.lgagst_min_speed = Some(*speed),
+                Line::Reset { non_shared_seed } => {
+                    self.state.non_shared_seed = Some(*non_shared_seed)
+                }
+                Line::VectorialStrafing(enabled) => self.state.vectorial_strafing = *enabled,
+                Line::VectorialStrafingConstraints(constraints) => {
+                    self.state.constraints = Some(*constraints)
+                }
+                Line::Change(change) => {
+                    self.state
+                        .changes
+                        .retain(|active| active.change.target != change.target);
+                    self.state.changes.push(ActiveChange {
+                        change: *change,
+                        line_index,
+                        elapsed: 0.,
+                    });
+                }
+                Line::TargetYawOverride(values) => {
+                    self.target_yaw_override = Override {
+                        values,
+                        position: 0,
+                    }
+                }
+                Line::RenderYawOverride(values) => {
+                    self.render_yaw_override = Override {
+                        values,
+                        position: 0,
+                    }
+                }
+                Line::PitchOverride(values) => {
+                    self.pitch_override = Override {
+                        values,
+                        position: 0,
+                    }
+                }
+                Line::RenderPitchOverride(values) => {
+                    self.render_pitch_override = Override {
+                        values,
+                        position: 0,
+                    }
+                }
+                Line::Save(_) | Line::Comment(_) | Line::Unknown(_) => {}
+            }
+        }
+
+        None
+    }
+
+    /// Resolves the auto-actions of a frame, updating the limited times.
+    fn resolve_auto_actions(&mut self, frame_bulk: &FrameBulk, first_frame: bool) -> AutoActions {
+        fn resolve<T: Copy>(
+            action: Option<T>,
+            times: impl Fn(&T) -> Times,
+            limited: &mut Option<NonZeroU32>,
+            first_frame: bool,
+        ) -> Option<T> {
+            let action = action?;
+            match times(&action) {
+                Times::UnlimitedWithinFrameBulk => {
+                    *limited = None;
+                    Some(action)
+                }
+                Times::Limited(times) => {
+                    if first_frame {
+                        *limited = Some(times);
+                        Some(action)
+                    } else {
+                        None
+                    }
+                }
+            }
+        }
+
+        let aa = &frame_bulk.auto_actions;
+        let limited = &mut self.state.limited_times;
+        AutoActions {
+            movement: aa.movement,
+            leave_ground_action: resolve(
+                aa.leave_ground_action,
+                |x| x.times,
+                &mut limited.leave_ground_action,
+                first_frame,
+            ),
+            jump_bug: resolve(aa.jump_bug, |x| x.times, &mut limited.jump_bug, first_frame),
+            duck_before_collision: resolve(
+                aa.duck_before_collision,
+                |x| x.times,
+                &mut limited.duck_before_collision,
+                first_frame,
+            ),
+            duck_before_ground: resolve(
+                aa.duck_before_ground,
+                |x| x.times,
+                &mut limited.duck_before_ground,
+                first_frame,
+            ),
+            duck_when_jump: resolve(
+                aa.duck_when_jump,
+                |x| x.times,
+                &mut limited.duck_when_jump,
+                first_frame,
+            ),
+        }
+    }
+}
+
+/// Resolves left-right and right-left strafing into the direction of the given frame.
+fn strafe_dir(movement: Option<AutoMovement>, index_in_frame_bulk: u32) -> Option<StrafeDir> {
+    let dir = match movement? {
+        AutoMovement::Strafe(settings) => settings.dir,
+        AutoMovement::SetYaw(_) => return None,
+    };
+
+    let first_side = |count: NonZeroU32| (index_in_frame_bulk / count.get()).is_multiple_of(2);
+    Some(match dir {
+        StrafeDir::LeftRight(count) if first_side(count) => StrafeDir::Left,
+        StrafeDir::LeftRight(_) => StrafeDir::Right,
+        StrafeDir::RightLeft(count) if first_side(count) => StrafeDir::Right,
+        StrafeDir::RightLeft(_) => StrafeDir::Left,
+        dir => dir,
+    })
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (frame_bulk, line_index, index_in_frame_bulk, preceding_lines) = match self.current {
+            Some((frame_bulk, line_index, index)) if index < frame_bulk.frame_count.get() => {
+                (frame_bulk, line_index, index, &self.lines[..0])
+            }
+            _ => {
+                let (preceding_lines, frame_bulk, line_index) = self.advance_to_frame_bulk()?;
+                (frame_bulk, line_index, 0, preceding_lines)
+            }
+        };
+        self.current = Some((frame_bulk, line_index, index_in_frame_bulk + 1));
+
+        let auto_actions = self.resolve_auto_actions(frame_bulk, index_in_frame_bulk == 0);
+
+        self.state.target_yaw_override = self.target_yaw_override.current();
+        self.state.render_yaw_override = self.render_yaw_override.current();
+        self.state.pitch_override = self.pitch_override.current();
+        self.state.render_pitch_override = self.render_pitch_override.current();
+
+        let frame = Frame {
+            index: self.index,
+            line_index,
+            index_in_frame_bulk,
+            frame_bulk,
+            preceding_lines,
+            auto_actions,
+            strafe_dir: strafe_dir(frame_bulk.auto_actions.movement, index_in_frame_bulk),
+            state: self.state.clone(),
+        };
+
+        // Advance the per-frame state.
+        self.index += 1;
+        self.target_yaw_override.position += 1;
+        self.render_yaw_override.position += 1;
+        self.pitch_override.position += 1;
+        self.render_pitch_override.position += 1;
+
+        let frame_time = frame_bulk.frame_time.parse::<f32>().unwrap_or(0.);
+        for active in &mut self.state.changes {
+            active.elapsed += frame_time;
+        }
+        self.state
+            .changes
+            .retain(|active| active.elapsed < active.change.over);
+
+        Some(frame)
+    }
+}
+
+impl ActiveChange {
+    /// Returns the fraction of the change that is complete at the start of this frame, from 0 to
+    /// 1.
+    pub fn progress(&self) -> f32 {
+        let over = self.change.over;
+        if over <= 0. {
+            1.
+        } else {
+            (self.elapsed / over).min(1.)
+        }
+    }
+}
+
+impl State {
+    /// Returns the change in progress for the given target, if any.
+    pub fn change(&self, target: ChangeTarget) -> Option<&ActiveChange> {
+        self.changes
+            .iter()
+            .find(|active| active.change.target == target)
+    }
+}
+
+/// Returns what every frame of the script does, leaving out where in the script it comes from.
+///
+/// Used to check that transformations keep the frames of a script the same.
+#[cfg(test)]
+pub(crate) fn behaviors(hltas: &HLTAS) -> Vec<impl PartialEq + std::fmt::Debug> {
+    hltas
+        .frames()
+        .map(|frame| {
+            let mut auto_actions = frame.auto_actions;
+            if let (Some(AutoMovement::Strafe(settings)), Some(dir)) =
+                (&mut auto_actions.movement, frame.strafe_dir)
+            {
+                settings.dir = dir;
+            }
+
+            let state = frame.state;
+            let changes: Vec<_> = state
+                .changes
+                .iter()
+                .map(|active| (active.change, active.elapsed))
+                .collect();
+            let fb = frame.frame_bulk;
+
+            (
+                auto_actions,
+                (
+                    state.buttons,
+                    state.lgagst_min_speed,
+                    state.vectorial_strafing,
+                    state.constraints,
+                    changes,
+                    state.shared_seed,
+                    state.non_shared_seed,
+                    state.limited_times,
+                ),
+                (
+                    state.target_yaw_override,
+                    state.render_yaw_override,
+                    state.pitch_override,
+                    state.render_pitch_override,
+                ),
+                (
+                    fb.movement_keys,
+                    fb.action_keys,
+                    fb.frame_time.clone(),
+                    fb.pitch,
+                    fb.console_command.clone(),
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::for_each_test_script,
+        types::{Button, JumpBug, LeaveGroundAction},
+    };
+
+    fn frames(contents: &str) -> Vec<Frame<'static>> {
+        let hltas = Box::leak(Box::new(HLTAS::from_str(contents).unwrap()));
+        hltas.frames().collect()
+    }
+
+    #[test]
+    fn frame_count_matches() {
+        for_each_test_script(|path, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+
+            let expected: u64 = hltas
+                .frame_bulks()
+                .map(|frame_bulk| u64::from(frame_bulk.frame_count.get()))
+                .sum();
+            let count = hltas
+                .frames()
+                .enumerate()
+                .inspect(|(i, frame)| assert_eq!(*i as u64, frame.index))
+                .count();
+            assert_eq!(count as u64, expected, "{}", path.display());
+        });
+    }
+
+    #[test]
+    fn state_carries_over() {
+        let frames = frames(
+            "version 1\nframes\n\
+             buttons 0 6 2 4\n\
+             lgagstminspeed 30\n\
+             reset 5\n\
+             ----------|------|------|0.001|-|-|2\n\
+             buttons\n\
+             ----------|------|------|0.001|-|-|1",
+        );
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].preceding_lines.len(), 3);
+        assert!(frames[1].preceding_lines.is_empty());
+        assert_eq!(frames[2].line_index, 5);
+        assert_eq!(
+            frames[1].state.buttons,
+            Buttons::Set {
+                air_left: Button::Forward,
+                air_right: Button::Right,
+                ground_left: Button::Left,
+                ground_right: Button::Back,
+            }
+        );
+        assert_eq!(frames[1].state.lgagst_min_speed, Some(30.));
+        assert_eq!(frames[1].state.non_shared_seed, Some(5));
+        assert_eq!(frames[2].state.buttons, Buttons::Reset);
+        assert_eq!(frames[2].state.lgagst_min_speed, Some(30.));
+    }
+
+    #[test]
+    fn limited_times() {
+        let frames = frames(
+            "version 1\nframes\n\
+             ----j2-b---|------|------|0.001|-|-|2\n\
+             ----------|------|------|0.001|-|-|1\n\
+             ----j-----|------|------|0.001|-|-|1",
+        );
+
+        let jump = frames[0].auto_actions.leave_ground_action;
+        assert!(matches!(
+            jump,
+            Some(LeaveGroundAction {
+                times: Times::Limited(_),
+                ..
+            })
+        ));
+        assert_eq!(
+            frames[0].auto_actions.jump_bug,
+            Some(JumpBug {
+                times: Times::UnlimitedWithinFrameBulk
+            })
+        );
+
+        // Limited actions are only set on the first frame.
+        assert_eq!(frames[1].auto_actions.leave_ground_action, None);
+        assert!(frames[1].auto_actions.jump_bug.is_some());
+
+        let two = NonZeroU32::new(2);
+        assert_eq!(frames[1].state.limited_times.leave_ground_action, two);
+        assert_eq!(frames[2].state.limited_times.leave_ground_action, two);
+        assert_eq!(frames[2].auto_actions.jump_bug, None);
+
+        // Overridden by the next frame bulk with the same action.
+        assert_eq!(frames[3].state.limited_times.leave_ground_action, None);
+        assert!(frames[3].auto_actions.leave_ground_action.is_some());
+    }
+
+    #[test]
+    fn overrides_span_frame_bulks() {
+        let frames = frames(
+            "version 1\nframes\n\
+             pitch_override 1 2 3\n\
+             ----------|------|------|0.001|-|-|2\n\
+             render_yaw_override 5\n\
+             ----------|------|------|0.001|-|-|2",
+        );
+
+        let pitches: Vec<_> = frames.iter().map(|f| f.state.pitch_override).collect();
+        assert_eq!(pitches, [Some(1.), Some(2.), Some(3.), None]);
+        let yaws: Vec<_> = frames.iter().map(|f| f.state.render_yaw_override).collect();
+        assert_eq!(yaws, [None, None, Some(5.), None]);
+    }
+
+    #[test]
+    fn changes_progress() {
+        let frames = frames(
+            "version 1\nframes\n\
+             change yaw to 90 over 0.002 s\n\
+             ----------|------|------|0.001|-|-|3",
+        );
+
+        let progress: Vec<_> = frames
+            .iter()
+            .map(|f| {
+                f.state
+                    .change(ChangeTarget::Yaw)
+                    .map(ActiveChange::progress)
+            })
+            .collect();
+        assert_eq!(progress, [Some(0.), Some(0.5), None]);
+    }
+
+    #[test]
+    fn left_right() {
+        let frames = frames("version 1\nframes\ns07-------|------|------|0.001|2|-|5");
+        let dirs: Vec<_> = frames.iter().map(|f| f.strafe_dir.unwrap()).collect();
+        assert_eq!(
+            dirs,
+            [
+                StrafeDir::Right,
+                StrafeDir::Right,
+                StrafeDir::Left,
+                StrafeDir::Left,
+                StrafeDir::Right
+            ]
+        );
+    }
+}
@@ -58,3 +58,59 @@ pub use types::HLTAS;
 
 pub mod read;
 pub mod write;
+
+pub mod diagnostic;
+pub mod source_map;
+
+pub mod frames;
+pub mod timeline;
+
+pub mod cvars;
+pub mod diff;
+pub mod frame_time;
+pub mod lint;
+pub mod merge;
+pub mod overrides;
+pub mod timing;
+
+pub mod builder;
+pub mod edit;
+pub mod normalize;
+pub mod retime;
+pub mod transform;
+pub mod validate;
+
+pub mod console;
+pub mod syntax;
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::{
+        fs::{read_dir, read_to_string},
+        path::Path,
+    };
+
+    use crate::HLTAS;
+
+    /// Parses a script with an empty header and the given lines.
+    pub(crate) fn parse_frames(lines: &str) -> HLTAS {
+        HLTAS::from_str(&format!("version 1\nframes\n{}", lines)).unwrap()
+    }
+
+    /// Returns the frame count of every frame bulk.
+    pub(crate) fn frame_counts(hltas: &HLTAS) -> Vec<u32> {
+        hltas
+            .frame_bulks()
+            .map(|frame_bulk| frame_bulk.frame_count.get())
+            .collect()
+    }
+
+    /// Calls `f` with the path and the contents of every script in `test-data/parse`.
+    pub(crate) fn for_each_test_script(mut f: impl FnMut(&Path, &str)) {
+        for entry in read_dir("test-data/parse").unwrap() {
+            let path = entry.unwrap().path();
+            let contents = read_to_string(&path).unwrap();
+            f(&path, &contents);
+        }
+    }
+}
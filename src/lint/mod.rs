@@ -0,0 +1,410 @@
+//! Linting of scripts.
+//!
+//! A [`Linter`] runs a set of [`Rule`]s over an [`HLTAS`] and collects the [`Lint`]s they report.
+//! The built-in rules are in the [`rules`] module, and custom rules can be added by implementing
+//! the [`Rule`] trait.
+//!
+//! Lints can be suppressed with special comment lines. `// hltas-lint: allow(rule-name)` suppresses
+//! the listed rules on the next non-comment line, and `// hltas-lint: allow-file(rule-name)`
+//! suppresses them in the whole script. Several rule names can be separated with commas.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::lint::Linter;
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|5
+//! ----------|------|------|0.001|-|-|10
+//! // hltas-lint: allow(mergeable-frame-bulks)
+//! ----------|------|------|0.001|-|-|10";
+//!
+//! let diagnostics = Linter::new().lint_str(contents).unwrap();
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(diagnostics[0].line, 3);
+//! println!("{}", diagnostics[0]);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    console::catalogue::Catalogue,
+    diagnostic::{Diagnostic, Severity},
+    read,
+    source_map::SourceMap,
+    types::Line,
+    HLTAS,
+};
+
+pub mod rules;
+
+/// Prefix of comment lines with lint suppressions.
+const SUPPRESSION_PREFIX: &str = "hltas-lint:";
+
+/// A check that can be run on a script.
+pub trait Rule {
+    /// Returns the name of the rule, like `"mergeable-frame-bulks"`, used for configuration and
+    /// suppression.
+    fn name(&self) -> &'static str;
+
+    /// Returns the stable code of the rule, like `"L0001"`.
+    fn code(&self) -> &'static str;
+
+    /// Returns the severity the lints of this rule have unless configured otherwise.
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Checks the script, calling `report` with the index of the offending line in
+    /// [`HLTAS::lines`] and a message for every problem found.
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String));
+}
+
+/// A problem reported by a [`Rule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Name of the rule.
+    pub rule: &'static str,
+    /// Code of the rule.
+    pub code: &'static str,
+    /// Severity of the lint.
+    pub severity: Severity,
+    /// Index of the offending line in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Human-readable message.
+    pub message: String,
+}
+
+impl Lint {
+    /// Converts the lint into a [`Diagnostic`] pointing at the offending line.
+    ///
+    /// Returns `None` if the line index is out of bounds of the source map.
+    pub fn to_diagnostic(&self, source_map: &SourceMap) -> Option<Diagnostic> {
+        let location = source_map.get(self.line_index)?;
+        Some(Diagnostic {
+            code: self.code.to_owned(),
+            severity: self.severity,
+            message: self.message.clone(),
+            span: location.span.clone(),
+            line: location.line,
+            column: location.column,
+            notes: vec![format!(
+                "to allow, add `// {} allow({})` before the line",
+                SUPPRESSION_PREFIX, self.rule
+            )],
+            suggestion: None,
+        })
+    }
+}
+
+/// A rule along with its configuration.
+struct ConfiguredRule {
+    rule: Box<dyn Rule>,
+    severity: Severity,
+    enabled: bool,
+}
+
+/// Runs [`Rule`]s over scripts.
+pub struct Linter {
+    rules: Vec<ConfiguredRule>,
+}
+
+impl Default for Linter {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter with all built-in rules enabled with their default settings.
+    pub fn new() -> Self {
+        Self::with_catalogue(Catalogue::new())
+    }
+
+    /// Creates a linter with all built-in rules enabled with their default settings, checking
+    /// console commands against `catalogue`.
+    ///
+    /// The rules checking console commands share the catalogue, so it only needs to be extended
+    /// once.
+    pub fn with_catalogue(catalogue: Catalogue) -> Self {
+        let mut linter = Self::empty();
+        for rule in rules::all_with_catalogue(catalogue) {
+            linter.add_rule(rule);
+        }
+        linter
+    }
+
+    /// Creates a linter without any rules.
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds a rule, replacing a rule with the same name if there is one.
+    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+        let configured = ConfiguredRule {
+            severity: rule.default_severity(),
+            enabled: true,
+            rule,
+        };
+
+        if let Some(existing) = self.find_mut(configured.rule.name()) {
+            *existing = configured;
+        } else {
+            self.rules.push(configured);
+        }
+    }
+
+    /// Enables or disables the rule with the given name.
+    ///
+    /// Returns `false` if there's no such rule.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.find_mut(name)
+            .map(|rule| rule.enabled = enabled)
+            .is_some()
+    }
+
+    /// Sets the severity of the lints of the rule with the given name.
+    ///
+    /// Returns `false` if there's no such rule.
+    pub fn set_severity(&mut self, name: &str, severity: Severity) -> bool {
+        self.find_mut(name)
+            .map(|rule| rule.severity = severity)
+            .is_some()
+    }
+
+    /// Returns the names of all rules, in the order they run.
+    pub fn rule_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|rule| rule.rule.name())
+    }
+
+    /// Lints a script, returning the lints ordered by line.
+    pub fn lint(&self, hltas: &HLTAS) -> Vec<Lint> {
+        let suppressions = Suppressions::new(hltas);
+        let mut lints = Vec::new();
+
+        for configured in self.rules.iter().filter(|rule| rule.enabled) {
+            let rule = &*configured.rule;
+            rule.check(hltas, &mut |line_index, message| {
+                if suppressions.is_suppressed(rule.name(), line_index) {
+                    return;
+                }
+
+                lints.push(Lint {
+                    rule: rule.name(),
+                    code: rule.code(),
+                    severity: configured.severity,
+                    line_index,
+                    message,
+                });
+            });
+        }
+
+        // Stable sort keeps the rule order within a line.
+        lints.sort_by_key(|lint| lint.line_index);
+        lints
+    }
+
+    /// Parses and lints a script, returning the lints as [`Diagnostic`]s.
+    pub fn lint_str<'a>(&self, input: &'a str) -> Result<Vec<Diagnostic>, read::Error<'a>> {
+        let (hltas, source_map) = HLTAS::from_str_with_source_map(input)?;
+        Ok(self
+            .lint(&hltas)
+            .iter()
+            .filter_map(|lint| lint.to_diagnostic(&source_map))
+            .collect())
+    }
+
+    fn find_mut(&mut self, name: &str) -> Option<&mut ConfiguredRule> {
+        self.rules.iter_mut().find(|rule| rule.rule.name() == name)
+    }
+}
+
+/// Lint suppressions from comment lines.
+#[derive(Debug, Default)]
+struct Suppressions<'a> {
+    file: Vec<&'a str>,
+    lines: HashMap<usize, Vec<&'a str>>,
+}
+
+impl<'a> Suppressions<'a> {
+    fn new(hltas: &'a HLTAS) -> Self {
+        let mut suppressions = Self::default();
+        let mut pending = Vec::new();
+
+        for (line_index, line) in hltas.lines.iter().enumerate() {
+            let comment = match line {
+                Line::Comment(comment) => comment,
+                _ => {
+                    if !pending.is_empty() {
+                        suppressions
+                            .lines
+                            .insert(line_index, std::mem::take(&mut pending));
+                    }
+                    continue;
+                }
+            };
+
+            let directive = match comment.trim().strip_prefix(SUPPRESSION_PREFIX) {
+                Some(directive) => directive.trim(),
+                None => continue,
+            };
+
+            if let Some(names) = parse_names(directive, "allow-file") {
+                suppressions.file.extend(names);
+            } else if let Some(names) = parse_names(directive, "allow") {
+                pending.extend(names);
+            }
+        }
+
+        suppressions
+    }
+
+    fn is_suppressed(&self, rule: &str, line_index: usize) -> bool {
+        self.file.contains(&rule)
+            || self
+                .lines
+                .get(&line_index)
+                .is_some_and(|names| names.contains(&rule))
+    }
+}
+
+/// Parses `directive(name, name)` into the names.
+fn parse_names<'a>(text: &'a str, directive: &str) -> Option<impl Iterator<Item = &'a str>> {
+    let names = text
+        .strip_prefix(directive)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    Some(
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EveryLine;
+
+    impl Rule for EveryLine {
+        fn name(&self) -> &'static str {
+            "every-line"
+        }
+
+        fn code(&self) -> &'static str {
+            "X0001"
+        }
+
+        fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+            for (index, line) in hltas.lines.iter().enumerate() {
+                if !matches!(line, Line::Comment(_)) {
+                    report(index, "line".to_owned());
+                }
+            }
+        }
+    }
+
+    fn lint_lines(linter: &Linter, contents: &str) -> Vec<usize> {
+        let hltas = HLTAS::from_str(contents).unwrap();
+        linter
+            .lint(&hltas)
+            .into_iter()
+            .map(|lint| lint.line_index)
+            .collect()
+    }
+
+    #[test]
+    fn suppressions() {
+        let mut linter = Linter::empty();
+        linter.add_rule(Box::new(EveryLine));
+
+        let contents = "version 1\nframes\n\
+                        reset 1\n\
+                        // hltas-lint: allow(other, every-line)\n\
+                        // another comment\n\
+                        reset 2\n\
+                        reset 3\n\
+                        //hltas-lint:allow( every-line )\n\
+                        reset 4\n\
+                        // hltas-lint: allow(other)\n\
+                        reset 5";
+        assert_eq!(lint_lines(&linter, contents), [0, 4, 8]);
+
+        let contents = "version 1\nframes\n\
+                        reset 1\n\
+                        // hltas-lint: allow-file(every-line)\n\
+                        reset 2";
+        assert_eq!(lint_lines(&linter, contents), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn configuration() {
+        let mut linter = Linter::empty();
+        linter.add_rule(Box::new(EveryLine));
+        assert!(linter.set_severity("every-line", Severity::Error));
+        assert!(!linter.set_severity("nonexistent", Severity::Error));
+
+        let hltas = HLTAS::from_str("version 1\nframes\nreset 1").unwrap();
+        let lints = linter.lint(&hltas);
+        assert_eq!(
+            lints,
+            [Lint {
+                rule: "every-line",
+                code: "X0001",
+                severity: Severity::Error,
+                line_index: 0,
+                message: "line".to_owned(),
+            }]
+        );
+
+        assert!(linter.set_enabled("every-line", false));
+        assert!(linter.lint(&hltas).is_empty());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut linter = Linter::empty();
+        linter.add_rule(Box::new(EveryLine));
+
+        let contents = "version 1\nframes\n\n  reset 1";
+        let diagnostics = linter.lint_str(contents).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "X0001");
+        assert_eq!(diagnostics[0].line, 3);
+        assert_eq!(diagnostics[0].column, 2);
+        assert_eq!(&contents[diagnostics[0].span.clone()], "reset 1");
+    }
+
+    #[test]
+    fn with_catalogue() {
+        let contents = "version 1\nframes\n\
+                        ----------|------|------|0.001|-|-|1|my_alias x\n\
+                        ----------|------|------|0.001|-|-|1|my_alais 1";
+        let codes = |linter: &Linter| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+            linter
+                .lint(&hltas)
+                .into_iter()
+                .map(|lint| (lint.rule, lint.line_index))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(&Linter::new()), []);
+
+        let mut catalogue = Catalogue::new();
+        catalogue.extend_from_str("command my_alias int").unwrap();
+        assert_eq!(
+            codes(&Linter::with_catalogue(catalogue)),
+            [
+                ("console-command-arguments", 0),
+                ("misspelled-console-command", 1)
+            ]
+        );
+    }
+}
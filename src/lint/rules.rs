@@ -0,0 +1,686 @@
+//! Built-in lint rules.
+
+use std::sync::Arc;
+
+use crate::{
+    console::{
+        catalogue::{Catalogue, Issue as CommandIssue},
+        split_at_semicolons, Command, ConsoleCommand,
+    },
+    lint::Rule,
+    overrides::{self, Issue},
+    types::{
+        AutoMovement, ChangeTarget, FrameBulk, LeaveGroundActionSpeed, Line, StrafeDir, Times,
+    },
+    HLTAS,
+};
+
+/// Returns all built-in rules with their default settings.
+pub fn all() -> Vec<Box<dyn Rule>> {
+    all_with_catalogue(Catalogue::new())
+}
+
+/// Returns all built-in rules with their default settings, with the rules checking console
+/// commands sharing the given catalogue.
+pub fn all_with_catalogue(catalogue: Catalogue) -> Vec<Box<dyn Rule>> {
+    let catalogue = Arc::new(catalogue);
+    vec![
+        Box::new(MergeableFrameBulks),
+        Box::new(ChangeTooShort),
+        Box::new(LgagstWithoutMinSpeed),
+        Box::new(CommentInConsoleCommand),
+        Box::new(YawJump::default()),
+        Box::new(FramesAfterStop),
+        Box::new(OverrideLength),
+        Box::new(OverrideWithoutVectorialStrafing),
+        Box::new(MisspelledConsoleCommand {
+            catalogue: catalogue.clone(),
+        }),
+        Box::new(ConsoleCommandArguments { catalogue }),
+    ]
+}
+
+/// Adjacent frame bulks which differ only in the frame count and can be merged into one.
+///
+/// Frame bulks with limited times or left-right strafing are never reported since merging them
+/// changes their behavior.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeableFrameBulks;
+
+impl Rule for MergeableFrameBulks {
+    fn name(&self) -> &'static str {
+        "mergeable-frame-bulks"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0001"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for (index, pair) in hltas.lines.windows(2).enumerate() {
+            if let [Line::FrameBulk(a), Line::FrameBulk(b)] = pair {
+                if can_merge(a, b) {
+                    report(
+                        index + 1,
+                        "this frame bulk can be merged with the previous one".to_owned(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Returns `true` if the two frame bulks can be merged into one by adding up the frame counts.
+fn can_merge(a: &FrameBulk, b: &FrameBulk) -> bool {
+    let mut a_ = a.clone();
+    a_.frame_count = b.frame_count;
+    a_ == *b && !has_limited_times(a) && !has_alternating_dir(a)
+}
+
+/// Returns `true` if any of the frame bulk's auto-actions has limited times.
+fn has_limited_times(frame_bulk: &FrameBulk) -> bool {
+    let aa = &frame_bulk.auto_actions;
+    [
+        aa.leave_ground_action.map(|x| x.times),
+        aa.jump_bug.map(|x| x.times),
+        aa.duck_before_collision.map(|x| x.times),
+        aa.duck_before_ground.map(|x| x.times),
+        aa.duck_when_jump.map(|x| x.times),
+    ]
+    .iter()
+    .any(|times| matches!(times, Some(Times::Limited(_))))
+}
+
+/// Returns `true` if the frame bulk strafes left-right or right-left.
+fn has_alternating_dir(frame_bulk: &FrameBulk) -> bool {
+    matches!(
+        frame_bulk.auto_actions.movement,
+        Some(AutoMovement::Strafe(settings))
+            if matches!(settings.dir, StrafeDir::LeftRight(_) | StrafeDir::RightLeft(_))
+    )
+}
+
+/// Shortfall of a change's duration in seconds that is attributed to rounding.
+const CHANGE_EPSILON: f64 = 1e-6;
+
+/// A `change` that is cut short by the next `change` of the same value or by the end of the
+/// script.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChangeTooShort;
+
+impl Rule for ChangeTooShort {
+    fn name(&self) -> &'static str {
+        "change-too-short"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0002"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        // Changes in progress: target, line index, duration and elapsed time.
+        let mut active: Vec<(ChangeTarget, usize, f64, f64)> = Vec::new();
+        let mut report_short = |(_, line_index, over, elapsed): (ChangeTarget, usize, f64, f64)| {
+            report(
+                line_index,
+                format!(
+                    "the change takes {} s, but only {:.3} s of frames follow it",
+                    over, elapsed
+                ),
+            )
+        };
+
+        for (line_index, line) in hltas.lines.iter().enumerate() {
+            match line {
+                Line::Change(change) => {
+                    if let Some(position) = active.iter().position(|x| x.0 == change.target) {
+                        report_short(active.remove(position));
+                    }
+                    active.push((change.target, line_index, f64::from(change.over), 0.));
+                }
+                Line::FrameBulk(frame_bulk) => {
+                    let frame_time = frame_bulk.frame_time.parse::<f64>().unwrap_or(0.);
+                    let duration = frame_time * f64::from(frame_bulk.frame_count.get());
+
+                    for (_, _, _, elapsed) in &mut active {
+                        *elapsed += duration;
+                    }
+                    active.retain(|&(_, _, over, elapsed)| elapsed + CHANGE_EPSILON < over);
+                }
+                _ => {}
+            }
+        }
+
+        for change in active {
+            report_short(change);
+        }
+    }
+}
+
+/// Optimal LGAGST without a preceding `lgagstminspeed`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LgagstWithoutMinSpeed;
+
+impl Rule for LgagstWithoutMinSpeed {
+    fn name(&self) -> &'static str {
+        "lgagst-without-min-speed"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0003"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for (line_index, line) in hltas.lines.iter().enumerate() {
+            match line {
+                Line::LGAGSTMinSpeed(_) => return,
+                Line::FrameBulk(frame_bulk) => {
+                    let lgagst = frame_bulk
+                        .auto_actions
+                        .leave_ground_action
+                        .is_some_and(|action| action.speed != LeaveGroundActionSpeed::Any);
+
+                    if lgagst {
+                        report(
+                            line_index,
+                            "LGAGST is used without setting lgagstminspeed first".to_owned(),
+                        );
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// `//` in a console command, which comments out all commands after it.
+///
+/// A `//` at the start of the last command is fine, as it only comments out that command.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommentInConsoleCommand;
+
+impl Rule for CommentInConsoleCommand {
+    fn name(&self) -> &'static str {
+        "comment-in-console-command"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0004"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for (line_index, command) in console_commands(hltas) {
+            // Only the last command can have a comment, which runs until the end of the text.
+            let console_command = ConsoleCommand::parse(command);
+            let comment = match console_command.commands().last().and_then(Command::comment) {
+                Some(comment) => comment,
+                None => continue,
+            };
+
+            let swallowed = split_at_semicolons(comment)
+                .into_iter()
+                .skip(1)
+                .filter(|range| !comment[range.clone()].trim().is_empty())
+                .count();
+
+            if swallowed > 0 {
+                report(
+                    line_index,
+                    format!(
+                        "`//` comments out the rest of the console command, including {} \
+                         command(s) after the next `;`",
+                        swallowed
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Returns the names of the commands in a console command, ignoring the commented-out part.
+fn command_names(command: &str) -> Vec<String> {
+    ConsoleCommand::parse(command)
+        .names()
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Returns the console commands of all frame bulks with their line indices.
+fn console_commands(hltas: &HLTAS) -> impl Iterator<Item = (usize, &str)> {
+    hltas
+        .lines
+        .iter()
+        .enumerate()
+        .filter_map(|(line_index, line)| match line {
+            Line::FrameBulk(FrameBulk {
+                console_command: Some(command),
+                ..
+            }) => Some((line_index, command.as_str())),
+            _ => None,
+        })
+}
+
+/// A jump in the yaw angle between consecutive frame bulks that set it explicitly.
+///
+/// Angles that differ by more than half a turn usually mean that an angle was written in a
+/// different range than its neighbours, like 350 after 10, or that its sign is wrong.
+#[derive(Debug, Clone, Copy)]
+pub struct YawJump {
+    /// The smallest difference in degrees that is reported.
+    pub threshold: f32,
+}
+
+impl Default for YawJump {
+    fn default() -> Self {
+        Self { threshold: 180. }
+    }
+}
+
+impl Rule for YawJump {
+    fn name(&self) -> &'static str {
+        "yaw-jump"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0005"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        let mut previous = None;
+
+        for (line_index, line) in hltas.lines.iter().enumerate() {
+            match line {
+                Line::FrameBulk(frame_bulk) => {
+                    let yaw = explicit_yaw(frame_bulk);
+                    if let (Some(previous), Some(yaw)) = (previous, yaw) {
+                        let difference: f32 = yaw - previous;
+                        if difference.abs() > self.threshold {
+                            report(
+                                line_index,
+                                format!(
+                                    "the yaw jumps by {} degrees from {} to {}",
+                                    difference.abs(),
+                                    previous,
+                                    yaw
+                                ),
+                            );
+                        }
+                    }
+                    previous = yaw;
+                }
+                Line::Change(change) if change.target == ChangeTarget::Yaw => previous = None,
+                Line::TargetYawOverride(_) => previous = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the yaw angle explicitly set by the frame bulk, if any.
+fn explicit_yaw(frame_bulk: &FrameBulk) -> Option<f32> {
+    match frame_bulk.auto_actions.movement? {
+        AutoMovement::SetYaw(yaw) => Some(yaw),
+        AutoMovement::Strafe(settings) => match settings.dir {
+            StrafeDir::Yaw(yaw) | StrafeDir::Line { yaw } => Some(yaw),
+            _ => None,
+        },
+    }
+}
+
+/// Frame bulks after the final `stop` or `quit` command.
+///
+/// A `stop` followed by a `record` is not final, so scripts recording several demos are fine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FramesAfterStop;
+
+impl Rule for FramesAfterStop {
+    fn name(&self) -> &'static str {
+        "frames-after-stop"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0006"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        let mut final_stop = None;
+
+        for (line_index, command) in console_commands(hltas) {
+            for name in command_names(command) {
+                match name.as_str() {
+                    "stop" | "quit" => final_stop = Some((line_index, name)),
+                    "record" | "bxt_record" => final_stop = None,
+                    _ => {}
+                }
+            }
+        }
+
+        let (stop_index, name) = match final_stop {
+            Some(stop) => stop,
+            None => return,
+        };
+
+        let mut frame_bulks = hltas.lines[stop_index + 1..].iter().enumerate().filter_map(
+            |(offset, line)| match line {
+                Line::FrameBulk(frame_bulk) => Some((stop_index + 1 + offset, frame_bulk)),
+                _ => None,
+            },
+        );
+
+        if let Some((line_index, frame_bulk)) = frame_bulks.next() {
+            let frames = frame_bulks.fold(u64::from(frame_bulk.frame_count.get()), |sum, x| {
+                sum + u64::from(x.1.frame_count.get())
+            });
+            report(
+                line_index,
+                format!(
+                    "{} frame(s) come after the final `{}` command",
+                    frames, name
+                ),
+            );
+        }
+    }
+}
+
+/// Override lines with a different number of values than the frames they apply to.
+///
+/// See [`overrides::Issue::Overrun`] and [`overrides::Issue::Underrun`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverrideLength;
+
+impl Rule for OverrideLength {
+    fn name(&self) -> &'static str {
+        "override-length"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0007"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for issue in overrides::analyze(hltas).issues {
+            if matches!(issue, Issue::Overrun { .. } | Issue::Underrun { .. }) {
+                report(issue.line_index(), issue.to_string());
+            }
+        }
+    }
+}
+
+/// `target_yaw_override` applied to frames while vectorial strafing is off.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverrideWithoutVectorialStrafing;
+
+impl Rule for OverrideWithoutVectorialStrafing {
+    fn name(&self) -> &'static str {
+        "override-without-vectorial-strafing"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0008"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for issue in overrides::analyze(hltas).issues {
+            if let Issue::NoVectorialStrafing { .. } = issue {
+                report(issue.line_index(), issue.to_string());
+            }
+        }
+    }
+}
+
+/// Console commands that are likely misspellings of known commands or cvars.
+///
+/// See [`Catalogue::check()`] for what counts as a misspelling.
+#[derive(Debug, Clone, Default)]
+pub struct MisspelledConsoleCommand {
+    /// The known commands and cvars, which can be shared with other rules.
+    pub catalogue: Arc<Catalogue>,
+}
+
+impl Rule for MisspelledConsoleCommand {
+    fn name(&self) -> &'static str {
+        "misspelled-console-command"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0009"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for (line_index, issue) in self.catalogue.check_hltas(hltas) {
+            if let CommandIssue::Misspelled { .. } = issue {
+                report(line_index, issue.to_string());
+            }
+        }
+    }
+}
+
+/// Known console commands and cvars with the wrong number or types of arguments.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleCommandArguments {
+    /// The known commands and cvars, which can be shared with other rules.
+    pub catalogue: Arc<Catalogue>,
+}
+
+impl Rule for ConsoleCommandArguments {
+    fn name(&self) -> &'static str {
+        "console-command-arguments"
+    }
+
+    fn code(&self) -> &'static str {
+        "L0010"
+    }
+
+    fn check(&self, hltas: &HLTAS, report: &mut dyn FnMut(usize, String)) {
+        for (line_index, issue) in self.catalogue.check_hltas(hltas) {
+            if !matches!(issue, CommandIssue::Misspelled { .. }) {
+                report(line_index, issue.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::{lint::Linter, test_util::parse_frames};
+
+    fn check(rule: &dyn Rule, contents: &str) -> Vec<usize> {
+        let hltas = parse_frames(contents);
+        let mut lines = Vec::new();
+        rule.check(&hltas, &mut |line_index, _| lines.push(line_index));
+        lines
+    }
+
+    #[test]
+    fn all_rules_have_unique_names_and_codes() {
+        let rules = all();
+        for (i, a) in rules.iter().enumerate() {
+            for b in &rules[i + 1..] {
+                assert_ne!(a.name(), b.name());
+                assert_ne!(a.code(), b.code());
+            }
+        }
+    }
+
+    #[test]
+    fn test_data() {
+        let lint = |name: &str| -> Vec<(String, usize)> {
+            let contents = read_to_string(format!("test-data/parse/{}", name)).unwrap();
+            Linter::new()
+                .lint_str(&contents)
+                .unwrap()
+                .into_iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.line))
+                .collect()
+        };
+        let lints = |expected: &[(&str, usize)]| -> Vec<(String, usize)> {
+            expected
+                .iter()
+                .map(|&(code, line)| (code.to_owned(), line))
+                .collect()
+        };
+
+        assert_eq!(lint("strafe.hltas"), []);
+        assert_eq!(lint("vectorial.hltas"), []);
+        assert_eq!(lint("bhop.hltas"), lints(&[("L0003", 14)]));
+        assert_eq!(lint("pitch_override.hltas"), lints(&[("L0007", 3)]));
+        assert_eq!(
+            lint("target_yaw_override.hltas"),
+            lints(&[("L0008", 2), ("L0007", 3)])
+        );
+        assert_eq!(
+            lint("goldbhop.hltas"),
+            lints(&[("L0003", 4), ("L0005", 5), ("L0005", 40)])
+        );
+        assert_eq!(
+            lint("nuker.hltas"),
+            lints(&[("L0005", 18), ("L0005", 21), ("L0005", 33), ("L0004", 38)])
+        );
+    }
+
+    #[test]
+    fn mergeable_frame_bulks() {
+        let contents = "\
+            ----------|------|------|0.001|-|-|5\n\
+            ----------|------|------|0.001|-|-|10\n\
+            ----------|------|------|0.004|-|-|10\n\
+            // comment\n\
+            ----------|------|------|0.004|-|-|10\n\
+            -------c1--|------|------|0.004|-|-|10\n\
+            -------c1--|------|------|0.004|-|-|10\n\
+            s06-------|------|------|0.004|2|-|10\n\
+            s06-------|------|------|0.004|2|-|10\n\
+            s00-------|------|------|0.004|-|-|10\n\
+            s00-------|------|------|0.004|-|-|10";
+        assert_eq!(check(&MergeableFrameBulks, contents), [1, 10]);
+    }
+
+    #[test]
+    fn change_too_short() {
+        let contents = "\
+            change yaw to 90 over 0.1 s\n\
+            ----------|------|------|0.01|-|-|5\n\
+            change yaw to 180 over 0.1 s\n\
+            change pitch to 10 over 0.05 s\n\
+            ----------|------|------|0.01|-|-|10\n\
+            change yaw to 0 over 1 s\n\
+            ----------|------|------|0.01|-|-|10";
+        assert_eq!(check(&ChangeTooShort, contents), [0, 5]);
+    }
+
+    #[test]
+    fn lgagst_without_min_speed() {
+        let rule = LgagstWithoutMinSpeed;
+        assert_eq!(
+            check(
+                &rule,
+                "-------c--|------|------|0.001|-|-|5\n\
+                 s03lj-----|------|------|0.001|90|-|5\n\
+                 s03lj-----|------|------|0.001|90|-|5"
+            ),
+            [1]
+        );
+        assert_eq!(
+            check(
+                &rule,
+                "lgagstminspeed 30\n\
+                 s03lj-----|------|------|0.001|90|-|5"
+            ),
+            [] as [usize; 0]
+        );
+    }
+
+    #[test]
+    fn comment_in_console_command() {
+        let contents = "\
+            ----------|------|------|0.001|-|-|1|stop;//condebug\n\
+            ----------|------|------|0.001|-|-|1|//stop;pause\n\
+            ----------|------|------|0.001|-|-|1|echo \"//\";pause\n\
+            ----------|------|------|0.001|-|-|1|stop;//pause;\n\
+            ----------|------|------|0.001|-|-|1|stop;//echo \"a;b\"\n\
+            ----------|------|------|0.001|-|-|1|stop;//a;//b";
+        assert_eq!(check(&CommentInConsoleCommand, contents), [1, 5]);
+    }
+
+    #[test]
+    fn command_names_() {
+        let names = command_names("echo \"a;b\" ; stop;;  pause //x;quit");
+        assert_eq!(names, ["echo", "stop", "pause"]);
+    }
+
+    #[test]
+    fn console_commands_() {
+        let contents = "\
+            ----------|------|------|0.001|-|-|1|bxt_timer_start;sensitivty 0\n\
+            ----------|------|------|0.001|-|-|1|bxt_setpos 1 2\n\
+            ----------|------|------|0.001|-|-|1|my_alias 1 2;//bxt_setpos";
+        assert_eq!(check(&MisspelledConsoleCommand::default(), contents), [0]);
+        assert_eq!(check(&ConsoleCommandArguments::default(), contents), [1]);
+
+        let mut catalogue = Catalogue::new();
+        catalogue.extend_from_str("command my_alias int").unwrap();
+        let catalogue = Arc::new(catalogue);
+        let rule = ConsoleCommandArguments {
+            catalogue: catalogue.clone(),
+        };
+        assert_eq!(check(&rule, contents), [1, 2]);
+        let rule = MisspelledConsoleCommand { catalogue };
+        assert_eq!(check(&rule, contents), [0]);
+    }
+
+    #[test]
+    fn yaw_jump() {
+        let contents = "\
+            ----------|------|------|0.001|10|-|1\n\
+            ----------|------|------|0.001|350|-|1\n\
+            s03-------|------|------|0.001|170|-|1\n\
+            change yaw to 0 over 0.1 s\n\
+            s03-------|------|------|0.001|-100|-|1\n\
+            s00-------|------|------|0.001|-|-|1\n\
+            ----------|------|------|0.001|100|-|1";
+        assert_eq!(check(&YawJump::default(), contents), [1]);
+        assert_eq!(check(&YawJump { threshold: 90. }, contents), [1, 2]);
+    }
+
+    #[test]
+    fn frames_after_stop() {
+        let rule = FramesAfterStop;
+        assert_eq!(
+            check(
+                &rule,
+                "----------|------|------|0.001|-|-|1|record a\n\
+                 ----------|------|------|0.001|-|-|1|stop;record b\n\
+                 ----------|------|------|0.001|-|-|1|stop\n\
+                 // comment\n\
+                 ----------|------|------|0.001|-|-|5\n\
+                 ----------|------|------|0.001|-|-|5"
+            ),
+            [4]
+        );
+        assert_eq!(
+            check(
+                &rule,
+                "----------|------|------|0.001|-|-|1|stop\n\
+                 ----------|------|------|0.001|-|-|1|record a\n\
+                 ----------|------|------|0.001|-|-|1|bxt_timer_stop;//stop"
+            ),
+            [] as [usize; 0]
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let contents = "\
+            target_yaw_override 1 2 3\n\
+            ----------|------|------|0.001|-|-|2\n\
+            strafing vectorial\n\
+            pitch_override 4 5\n\
+            ----------|------|------|0.001|-|-|1";
+        assert_eq!(check(&OverrideLength, contents), [3]);
+        assert_eq!(check(&OverrideWithoutVectorialStrafing, contents), [0]);
+    }
+}
@@ -0,0 +1,797 @@
+//! Three-way merging of scripts.
+//!
+//! [`merge()`] combines the changes that two scripts ("ours" and "theirs") made to a common base
+//! script. Frame bulks are compared frame by frame rather than line by line, so splitting a frame
+//! bulk or changing its frame count only affects the frames that actually changed, and edits to
+//! different frames of the same frame bulk merge cleanly. Other lines are compared as a whole.
+//!
+//! Changes that don't overlap are resolved automatically. Overlapping changes that differ become
+//! [`Conflict`]s, which can be resolved in code or written out with conflict markers by
+//! [`Merge::to_writer()`].
+//!
+//! Properties are merged one by one. If both sides changed a property differently, the merged
+//! properties keep our value and [`Merge::properties_conflict`] is set.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{merge::merge, HLTAS};
+//!
+//! let base = HLTAS::from_str("\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|10").unwrap();
+//!
+//! let ours = HLTAS::from_str("\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|2
+//! ----------|------|j-----|0.001|-|-|1
+//! ----------|------|------|0.001|-|-|7").unwrap();
+//!
+//! let theirs = HLTAS::from_str("\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|8
+//! ----------|------|------|0.001|-|-|2|+attack").unwrap();
+//!
+//! let merged = merge(&base, &ours, &theirs).into_hltas().unwrap();
+//!
+//! let frame_counts: Vec<_> = merged.frame_bulks().map(|x| x.frame_count.get()).collect();
+//! assert_eq!(frame_counts, [2, 1, 5, 2]);
+//! ```
+
+use std::{io::Write, ops::Range};
+
+use cookie_factory::GenError;
+
+use crate::{
+    edit,
+    types::{FrameBulk, Line, Properties},
+    write::gen_lines,
+    HLTAS,
+};
+
+/// A part of the merged script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chunk {
+    /// Lines that were merged automatically.
+    Resolved(Vec<Line>),
+    /// Lines that both sides changed differently.
+    Conflict(Conflict),
+}
+
+/// Conflicting changes to the same part of the script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Frames of the base script that the conflicting lines replace.
+    ///
+    /// Empty if both sides inserted lines at the same position.
+    pub frames: Range<u64>,
+    /// Lines of the base script.
+    pub base: Vec<Line>,
+    /// Our lines.
+    pub ours: Vec<Line>,
+    /// Their lines.
+    pub theirs: Vec<Line>,
+}
+
+/// Properties that both sides changed differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertiesConflict {
+    /// Names of the conflicting properties.
+    pub names: Vec<&'static str>,
+    /// Properties of the base script.
+    pub base: Properties,
+    /// Our properties.
+    pub ours: Properties,
+    /// Their properties.
+    pub theirs: Properties,
+}
+
+/// Result of a three-way merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    /// Merged properties, with our values for conflicting properties.
+    pub properties: Properties,
+    /// Conflicting properties, if any.
+    pub properties_conflict: Option<PropertiesConflict>,
+    /// Parts of the merged frames section, in order.
+    pub chunks: Vec<Chunk>,
+}
+
+/// A piece of a script compared during merging: a single frame or a non-frame-bulk line.
+#[derive(Debug, Clone)]
+enum Unit {
+    /// One frame of a frame bulk, as a frame bulk with a frame count of 1.
+    Frame {
+        frame_bulk: FrameBulk,
+        /// Whether this is the first frame of its frame bulk.
+        first: bool,
+    },
+    Line(Line),
+}
+
+impl PartialEq for Unit {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // Where frame bulks start doesn't change what the frames do, so splitting a frame bulk
+            // isn't a change. Limited times are only kept on the first frame of a frame bulk, so
+            // frames that differ in them still compare different.
+            (Unit::Frame { frame_bulk: a, .. }, Unit::Frame { frame_bulk: b, .. }) => a == b,
+            (Unit::Line(a), Unit::Line(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Merge {
+    /// Returns `true` if there are no conflicts.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.properties_conflict.is_none() && self.conflicts().next().is_none()
+    }
+
+    /// Returns an iterator over the conflicts in the frames section.
+    pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> {
+        self.chunks.iter().filter_map(|chunk| match chunk {
+            Chunk::Conflict(conflict) => Some(conflict),
+            Chunk::Resolved(_) => None,
+        })
+    }
+
+    /// Returns the merged script if there are no conflicts.
+    pub fn into_hltas(self) -> Option<HLTAS> {
+        if !self.is_clean() {
+            return None;
+        }
+
+        let mut lines = Vec::new();
+        for chunk in self.chunks {
+            if let Chunk::Resolved(chunk) = chunk {
+                lines.extend(chunk);
+            }
+        }
+
+        Some(HLTAS {
+            properties: self.properties,
+            lines,
+        })
+    }
+
+    /// Writes the merged script, with conflicts surrounded by conflict markers.
+    ///
+    /// Conflicting properties are written as three complete headers between the markers. The
+    /// output can only be parsed back once there are no conflicts.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), GenError> {
+        let header = |properties: &Properties| {
+            let mut buffer = Vec::new();
+            HLTAS {
+                properties: properties.clone(),
+                lines: Vec::new(),
+            }
+            .to_writer(&mut buffer)
+            .map(|()| buffer)
+        };
+
+        match &self.properties_conflict {
+            None => writer.write_all(&header(&self.properties)?),
+            Some(conflict) => write_conflict(
+                &mut writer,
+                &header(&conflict.ours)?,
+                &header(&conflict.base)?,
+                &header(&conflict.theirs)?,
+            ),
+        }
+        .map_err(GenError::IoError)?;
+
+        for chunk in &self.chunks {
+            match chunk {
+                Chunk::Resolved(lines) => gen_lines(&mut writer, lines)?,
+                Chunk::Conflict(conflict) => {
+                    let lines = |lines: &[Line]| {
+                        let mut buffer = Vec::new();
+                        gen_lines(&mut buffer, lines).map(|()| buffer)
+                    };
+
+                    write_conflict(
+                        &mut writer,
+                        &lines(&conflict.ours)?,
+                        &lines(&conflict.base)?,
+                        &lines(&conflict.theirs)?,
+                    )
+                    .map_err(GenError::IoError)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_conflict<W: Write>(
+    mut w: W,
+    ours: &[u8],
+    base: &[u8],
+    theirs: &[u8],
+) -> std::io::Result<()> {
+    w.write_all(b"<<<<<<< ours\n")?;
+    w.write_all(ours)?;
+    w.write_all(b"||||||| base\n")?;
+    w.write_all(base)?;
+    w.write_all(b"=======\n")?;
+    w.write_all(theirs)?;
+    w.write_all(b">>>>>>> theirs\n")
+}
+
+/// Splits the lines into units, expanding frame bulks into single frames.
+fn units(lines: &[Line]) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for line in lines {
+        let frame_bulk = match line {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            line => {
+                units.push(Unit::Line(line.clone()));
+                continue;
+            }
+        };
+
+        let mut first = frame_bulk.clone();
+        first.frame_count = 1.try_into().unwrap();
+        units.push(Unit::Frame {
+            frame_bulk: first,
+            first: true,
+        });
+
+        for offset in 1..frame_bulk.frame_count.get() {
+            let mut frame = edit::tail(frame_bulk, offset, false).swap_remove(0);
+            frame.frame_count = 1.try_into().unwrap();
+            units.push(Unit::Frame {
+                frame_bulk: frame,
+                first: false,
+            });
+        }
+    }
+    units
+}
+
+/// Joins units back into lines, merging frames that came from the same frame bulk.
+fn lines(units: &[Unit]) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    for unit in units {
+        match unit {
+            Unit::Frame { frame_bulk, first } => {
+                if !first {
+                    if let Some(Line::FrameBulk(previous)) = lines.last_mut() {
+                        if let Some(merged) = edit::append(previous, frame_bulk) {
+                            *previous = merged;
+                            continue;
+                        }
+                    }
+                }
+                lines.push(Line::FrameBulk(frame_bulk.clone()));
+            }
+            Unit::Line(line) => lines.push(line.clone()),
+        }
+    }
+    lines
+}
+
+/// Finds the middle snake of the shortest edit script between `a` and `b`.
+///
+/// Returns the start and the end of the snake as `(x, y, u, v)`. See "An O(ND) Difference
+/// Algorithm and Its Variations" by Eugene W. Myers.
+fn middle_snake<T: PartialEq>(a: &[T], b: &[T]) -> (usize, usize, usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // Furthest reaching x on every diagonal, going forward and going backward from the end.
+    let mut forward = vec![0; 2 * max as usize + 3];
+    let mut backward = vec![0; 2 * max as usize + 3];
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+
+            let c = delta - k;
+            if odd && -d < c && c < d && x + backward[index(c)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+
+            let c = delta - k;
+            if !odd && -d <= c && c <= d && x + forward[index(c)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x0) as usize,
+                    (m - y0) as usize,
+                );
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// Records the elements of `b` matched to the elements of `a` by a shortest edit script.
+fn match_elements<T: PartialEq>(
+    mut a: &[T],
+    mut b: &[T],
+    mut a_offset: usize,
+    mut b_offset: usize,
+    matches: &mut [Option<usize>],
+) {
+    while let (Some(x), Some(y)) = (a.first(), b.first()) {
+        if x != y {
+            break;
+        }
+        matches[a_offset] = Some(b_offset);
+        a = &a[1..];
+        b = &b[1..];
+        a_offset += 1;
+        b_offset += 1;
+    }
+    while let (Some(x), Some(y)) = (a.last(), b.last()) {
+        if x != y {
+            break;
+        }
+        matches[a_offset + a.len() - 1] = Some(b_offset + b.len() - 1);
+        a = &a[..a.len() - 1];
+        b = &b[..b.len() - 1];
+    }
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+
+    let (x, y, u, v) = middle_snake(a, b);
+    for i in 0..u - x {
+        matches[a_offset + x + i] = Some(b_offset + y + i);
+    }
+    match_elements(&a[..x], &b[..y], a_offset, b_offset, matches);
+    match_elements(&a[u..], &b[v..], a_offset + u, b_offset + v, matches);
+}
+
+/// Returns, for every element of `a`, the index of the element of `b` it is matched to.
+fn matches<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; a.len()];
+    match_elements(a, b, 0, 0, &mut matches);
+    matches
+}
+
+/// Returns the index of the frame at or after every unit.
+fn frame_indices(units: &[Unit]) -> Vec<u64> {
+    units
+        .iter()
+        .scan(0, |frame, unit| {
+            let index = *frame;
+            *frame += u64::from(matches!(unit, Unit::Frame { .. }));
+            Some(index)
+        })
+        .collect()
+}
+
+/// Rearranges the matches within runs of identical frames of `a`.
+///
+/// Most frames of a frame bulk are identical, so a shortest edit script can match them in many
+/// ways. Those matches are moved so that the frames keep their distance in frames from the
+/// preceding match, which makes edits of different frames of the same frame bulk not overlap.
+fn realign(a: &[Unit], b: &[Unit], matches: &mut [Option<usize>]) {
+    let frames_a = frame_indices(a);
+    let frames_b = frame_indices(b);
+    // Distance in frames between the matched units before the current run.
+    let mut shift = 0;
+
+    let mut start = 0;
+    while start < a.len() {
+        let end = start + a[start..].iter().take_while(|x| **x == a[start]).count();
+
+        let matched: Vec<usize> = matches[start..end].iter().flatten().copied().collect();
+        if matches!(a[start], Unit::Frame { .. }) && matched.len() < end - start {
+            matches[start..end].fill(None);
+
+            let mut next = start;
+            for (i, &j) in matched.iter().enumerate() {
+                let wanted = start as i64 + frames_b[j] as i64 - shift - frames_a[start] as i64;
+                let last = end - (matched.len() - i);
+                let position = wanted.clamp(next as i64, last as i64) as usize;
+                matches[position] = Some(j);
+                next = position + 1;
+            }
+        }
+
+        if let Some((i, j)) = (start..end).rev().find_map(|i| Some((i, matches[i]?))) {
+            shift = frames_b[j] as i64 - frames_a[i] as i64;
+        }
+        start = end;
+    }
+}
+
+fn frame_count(units: &[Unit]) -> u64 {
+    units
+        .iter()
+        .filter(|unit| matches!(unit, Unit::Frame { .. }))
+        .count() as u64
+}
+
+/// Merges the frames sections.
+fn merge_lines(base: &[Line], ours: &[Line], theirs: &[Line]) -> Vec<Chunk> {
+    let base = units(base);
+    let ours = units(ours);
+    let theirs = units(theirs);
+    let mut ours_matches = matches(&base, &ours);
+    let mut theirs_matches = matches(&base, &theirs);
+    realign(&base, &ours, &mut ours_matches);
+    realign(&base, &theirs, &mut theirs_matches);
+
+    let mut chunks = Vec::new();
+    let mut resolved = Vec::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    let mut frame = 0;
+
+    loop {
+        // Copy the units that are unchanged on both sides, keeping where our frame bulks start.
+        while b < base.len() && ours_matches[b] == Some(o) && theirs_matches[b] == Some(t) {
+            resolved.push(ours[o].clone());
+            frame += u64::from(matches!(base[b], Unit::Frame { .. }));
+            b += 1;
+            o += 1;
+            t += 1;
+        }
+
+        // Find the next unit that is unchanged on both sides.
+        let (next_b, next_o, next_t) = (b..base.len())
+            .find_map(|i| Some((i, ours_matches[i]?, theirs_matches[i]?)))
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        if (next_b, next_o, next_t) == (b, o, t) {
+            break;
+        }
+
+        let base_chunk = &base[b..next_b];
+        let ours_chunk = &ours[o..next_o];
+        let theirs_chunk = &theirs[t..next_t];
+        if ours_chunk == base_chunk || ours_chunk == theirs_chunk {
+            resolved.extend_from_slice(theirs_chunk);
+        } else if theirs_chunk == base_chunk {
+            resolved.extend_from_slice(ours_chunk);
+        } else {
+            if !resolved.is_empty() {
+                chunks.push(Chunk::Resolved(lines(&resolved)));
+                resolved.clear();
+            }
+
+            let frames = frame_count(base_chunk);
+            chunks.push(Chunk::Conflict(Conflict {
+                frames: frame..frame + frames,
+                base: lines(base_chunk),
+                ours: lines(ours_chunk),
+                theirs: lines(theirs_chunk),
+            }));
+        }
+
+        frame += frame_count(base_chunk);
+        b = next_b;
+        o = next_o;
+        t = next_t;
+    }
+
+    if !resolved.is_empty() {
+        chunks.push(Chunk::Resolved(lines(&resolved)));
+    }
+    chunks
+}
+
+/// Merges one value, returning `None` if both sides changed it differently.
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == base || ours == theirs {
+        Some(theirs.clone())
+    } else if theirs == base {
+        Some(ours.clone())
+    } else {
+        None
+    }
+}
+
+fn merge_properties(
+    base: &Properties,
+    ours: &Properties,
+    theirs: &Properties,
+) -> (Properties, Option<PropertiesConflict>) {
+    let mut properties = ours.clone();
+    let mut names = Vec::new();
+
+    macro_rules! merge {
+        ($($field:ident: $name:expr),* $(,)?) => {
+            $(
+                match merge_value(&base.$field, &ours.$field, &theirs.$field) {
+                    Some(value) => properties.$field = value,
+                    None => names.push($name),
+                }
+            )*
+        };
+    }
+
+    merge! {
+        demo: "demo",
+        save: "save",
+        frametime_0ms: "frametime0ms",
+        seeds: "seed",
+        hlstrafe_version: "hlstrafe_version",
+        load_command: "load_command",
+        custom: "custom properties",
+    }
+
+    let conflict = if names.is_empty() {
+        None
+    } else {
+        Some(PropertiesConflict {
+            names,
+            base: base.clone(),
+            ours: ours.clone(),
+            theirs: theirs.clone(),
+        })
+    };
+    (properties, conflict)
+}
+
+/// Merges the changes that `ours` and `theirs` made to `base`.
+pub fn merge(base: &HLTAS, ours: &HLTAS, theirs: &HLTAS) -> Merge {
+    let (properties, properties_conflict) =
+        merge_properties(&base.properties, &ours.properties, &theirs.properties);
+
+    Merge {
+        properties,
+        properties_conflict,
+        chunks: merge_lines(&base.lines, &ours.lines, &theirs.lines),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_utf8;
+
+    use super::*;
+    use crate::{
+        frames::behaviors,
+        test_util::{for_each_test_script, parse_frames},
+    };
+
+    #[test]
+    fn matches_shortest_edit_script() {
+        let a: Vec<_> = "abcabba".chars().collect();
+        let b: Vec<_> = "cbabac".chars().collect();
+        let matches = matches(&a, &b);
+
+        // The longest common subsequence has 4 elements.
+        let matched: Vec<_> = matches.iter().flatten().copied().collect();
+        assert_eq!(matched.len(), 4);
+        assert!(matched.windows(2).all(|w| w[0] < w[1]));
+        for (i, j) in matches.iter().enumerate() {
+            if let Some(j) = j {
+                assert_eq!(a[i], b[*j]);
+            }
+        }
+    }
+
+    #[test]
+    fn units_round_trip() {
+        let hltas = parse_frames(
+            "\
+            s06l2j-----|------|------|0.001|3|-|10\n\
+            buttons 0 1 2 3\n\
+            ----------|------|------|0.001|-|-|5|+attack\n\
+            ----------|------|------|0.001|-|-|5",
+        );
+        assert_eq!(lines(&units(&hltas.lines)), hltas.lines);
+    }
+
+    #[test]
+    fn clean_merge() {
+        let base = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|10\n\
+            buttons 0 1 2 3\n\
+            s06-------|------|------|0.001|3|-|10",
+        );
+        let ours = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|7\n\
+            ----------|------|------|0.001|-|-|3\n\
+            buttons 0 1 2 3\n\
+            s06-------|------|------|0.001|3|-|12",
+        );
+        let theirs = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|3\n\
+            ----------|------|------|0.004|-|-|2\n\
+            ----------|------|------|0.001|-|-|5\n\
+            // Comment\n\
+            buttons 0 1 2 3\n\
+            s06-------|------|------|0.001|3|-|10",
+        );
+
+        let merge = merge(&base, &ours, &theirs);
+        assert!(merge.is_clean());
+        let merged = merge.into_hltas().unwrap();
+
+        let expected = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|3\n\
+            ----------|------|------|0.004|-|-|2\n\
+            ----------|------|------|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|3\n\
+            // Comment\n\
+            buttons 0 1 2 3\n\
+            s06-------|------|------|0.001|3|-|12",
+        );
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn merges_edits_of_test_data() {
+        for_each_test_script(|path, contents| {
+            let base = HLTAS::from_str(contents).unwrap();
+            let total = base.frames().count() as u64;
+            if total < 20 {
+                return;
+            }
+
+            let mut ours = base.clone();
+            ours.set_yaw(total / 4..total / 4 + 3, 45.).ok();
+            let mut theirs = base.clone();
+            theirs.delete_frames(total / 2..total / 2 + 5).unwrap();
+
+            let mut expected = ours.clone();
+            expected.delete_frames(total / 2..total / 2 + 5).unwrap();
+
+            let merged = merge(&base, &ours, &theirs).into_hltas().unwrap();
+            assert_eq!(
+                behaviors(&merged),
+                behaviors(&expected),
+                "{}",
+                path.display()
+            );
+        });
+    }
+
+    #[test]
+    fn split_and_edit() {
+        let base = parse_frames("----------|------|------|0.001|-|-|10");
+        let ours = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|5\n\
+            ----------|------|------|0.001|-|-|5",
+        );
+        let theirs = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|5\n\
+            ----------|------|j-----|0.001|-|-|1\n\
+            ----------|------|------|0.001|-|-|4",
+        );
+
+        let merge = merge(&base, &ours, &theirs);
+        assert!(merge.is_clean());
+        assert_eq!(merge.into_hltas().unwrap(), theirs);
+
+        // Splitting right after the edited frame doesn't conflict either.
+        let ours = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|6\n\
+            ----------|------|------|0.001|-|-|4",
+        );
+        let merged = super::merge(&base, &ours, &theirs).into_hltas().unwrap();
+        assert_eq!(behaviors(&merged), behaviors(&theirs));
+    }
+
+    #[test]
+    fn same_change() {
+        let base = parse_frames("----------|------|------|0.001|-|-|10");
+        let ours = parse_frames("----------|------|------|0.001|-|-|10|+attack");
+
+        let merged = merge(&base, &ours, &ours).into_hltas().unwrap();
+        assert_eq!(behaviors(&merged), behaviors(&ours));
+    }
+
+    #[test]
+    fn conflict() {
+        let base = parse_frames("----------|------|------|0.001|-|-|10");
+        let ours = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|4\n\
+            ----------|------|j-----|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|4",
+        );
+        let theirs = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|5\n\
+            ----------|------|-d----|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|3",
+        );
+
+        let merge = merge(&base, &ours, &theirs);
+        assert!(!merge.is_clean());
+
+        let conflicts: Vec<_> = merge.conflicts().collect();
+        assert_eq!(conflicts.len(), 1);
+        let conflict = conflicts[0];
+        assert_eq!(conflict.frames, 4..7);
+        assert_eq!(conflict.base.len(), 1);
+        assert_eq!(conflict.ours.len(), 2);
+        assert_eq!(conflict.theirs.len(), 2);
+
+        let mut buffer = Vec::new();
+        merge.to_writer(&mut buffer).unwrap();
+        assert_eq!(
+            from_utf8(&buffer).unwrap(),
+            "\
+version 1
+frames
+----------|------|------|0.001|-|-|4
+<<<<<<< ours
+----------|------|j-----|0.001|-|-|2
+----------|------|------|0.001|-|-|1
+||||||| base
+----------|------|------|0.001|-|-|3
+=======
+----------|------|------|0.001|-|-|1
+----------|------|-d----|0.001|-|-|2
+>>>>>>> theirs
+----------|------|------|0.001|-|-|3
+"
+        );
+        assert!(merge.into_hltas().is_none());
+    }
+
+    #[test]
+    fn properties() {
+        let base = HLTAS::from_str("version 1\ndemo a\nsave b\nframes\n").unwrap();
+        let ours = HLTAS::from_str("version 1\ndemo c\nsave b\nframes\n").unwrap();
+        let theirs = HLTAS::from_str("version 1\ndemo a\nsave d\nframes\n").unwrap();
+
+        let merged = merge(&base, &ours, &theirs).into_hltas().unwrap();
+        assert_eq!(merged.properties.demo.as_deref(), Some("c"));
+        assert_eq!(merged.properties.save.as_deref(), Some("d"));
+
+        let merge = merge(&base, &ours, &ours.clone());
+        assert!(merge.is_clean());
+
+        let theirs = HLTAS::from_str("version 1\ndemo e\nframes\n").unwrap();
+        let merge = super::merge(&base, &ours, &theirs);
+        let conflict = merge.properties_conflict.as_ref().unwrap();
+        assert_eq!(conflict.names, ["demo"]);
+        assert_eq!(merge.properties.demo.as_deref(), Some("c"));
+        assert_eq!(merge.properties.save, None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Normalizing scripts.
+//!
+//! [`HLTAS::normalize()`] cleans up a script without changing what any of its frames do:
+//! - Consecutive frame bulks that behave as one frame bulk are merged. Frame bulks with console
+//!   commands are never merged, and a frame bulk with auto-actions with limited times can only
+//!   be merged into the preceding frame bulk if those auto-actions are the ones it started.
+//! - `buttons` lines that don't change the buttons are removed.
+//! - `strafing` lines that don't change the strafing mode are removed.
+//! - Comment lines are removed if [`Options::remove_comments`] is set.
+//!
+//! The legacy `-` and `0` frame counts are read as 1, so they are written as 1 after parsing and
+//! normalization doesn't need to handle them.
+//!
+//! [`HLTAS::normalize()`]: crate::HLTAS::normalize
+
+use crate::{
+    edit,
+    types::{Buttons, FrameBulk, Line},
+    HLTAS,
+};
+
+/// Options for [`HLTAS::normalize()`](crate::HLTAS::normalize).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Whether to remove comment lines.
+    pub remove_comments: bool,
+}
+
+/// Returns the frame bulk behaving like `first` followed by `second`, if they can be merged.
+fn merge(first: &FrameBulk, second: &FrameBulk) -> Option<FrameBulk> {
+    if first.console_command.is_some() || second.console_command.is_some() {
+        return None;
+    }
+
+    edit::append(first, second)
+}
+
+pub(crate) fn normalize(hltas: &mut HLTAS, options: Options) {
+    let mut buttons = Buttons::Reset;
+    let mut vectorial_strafing = false;
+
+    let mut lines: Vec<Line> = Vec::with_capacity(hltas.lines.len());
+    for line in hltas.lines.drain(..) {
+        match &line {
+            Line::Comment(_) if options.remove_comments => continue,
+            Line::Buttons(new) => {
+                if *new == buttons {
+                    continue;
+                }
+                buttons = *new;
+            }
+            Line::VectorialStrafing(enabled) => {
+                if *enabled == vectorial_strafing {
+                    continue;
+                }
+                vectorial_strafing = *enabled;
+            }
+            Line::FrameBulk(frame_bulk) => {
+                if let Some(Line::FrameBulk(previous)) = lines.last_mut() {
+                    if let Some(merged) = merge(previous, frame_bulk) {
+                        *previous = merged;
+                        continue;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        lines.push(line);
+    }
+
+    hltas.lines = lines;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        frames::behaviors,
+        test_util::{for_each_test_script, parse_frames},
+    };
+
+    fn normalized(contents: &str, options: Options) -> HLTAS {
+        let mut hltas = parse_frames(contents);
+        hltas.normalize(options);
+        hltas
+    }
+
+    #[test]
+    fn keeps_behavior_of_test_data() {
+        for_each_test_script(|path, contents| {
+            let original = HLTAS::from_str(contents).unwrap();
+
+            let mut hltas = original.clone();
+            hltas.normalize(Options {
+                remove_comments: true,
+            });
+            assert_eq!(
+                behaviors(&hltas),
+                behaviors(&original),
+                "{}",
+                path.display()
+            );
+        });
+    }
+
+    #[test]
+    fn merges_frame_bulks() {
+        let contents = "\
+            ----------|------|------|0.001|-|-|5\n\
+            ----------|------|------|0.001|-|-|10\n\
+            ----------|------|------|0.004|-|-|2\n\
+            s06-------|------|------|0.001|3|-|3\n\
+            s07-------|------|------|0.001|3|-|3\n\
+            s06-------|------|------|0.001|3|-|3\n\
+            s06-------|------|------|0.001|3|-|3";
+        let original = parse_frames(contents);
+        let hltas = normalized(contents, Options::default());
+
+        let frame_counts: Vec<_> = hltas
+            .frame_bulks()
+            .map(|frame_bulk| frame_bulk.frame_count.get())
+            .collect();
+        assert_eq!(frame_counts, [15, 2, 9, 3]);
+        assert_eq!(behaviors(&hltas), behaviors(&original));
+    }
+
+    #[test]
+    fn merges_limited_times() {
+        let contents = "\
+            ------b2---|------|------|0.001|-|-|5\n\
+            ----------|------|------|0.001|-|-|5\n\
+            ------b2---|------|------|0.001|-|-|5\n\
+            ------b---|------|------|0.001|-|-|5\n\
+            ------b---|------|------|0.001|-|-|5";
+        let hltas = normalized(contents, Options::default());
+
+        let frame_counts: Vec<_> = hltas
+            .frame_bulks()
+            .map(|frame_bulk| frame_bulk.frame_count.get())
+            .collect();
+        assert_eq!(frame_counts, [10, 5, 10]);
+    }
+
+    #[test]
+    fn keeps_console_commands() {
+        let contents = "\
+            ----------|------|------|0.001|-|-|5|+attack\n\
+            ----------|------|------|0.001|-|-|5|+attack";
+        let hltas = normalized(contents, Options::default());
+        assert_eq!(hltas, parse_frames(contents));
+    }
+
+    #[test]
+    fn removes_no_op_lines() {
+        let contents = "\
+            buttons\n\
+            strafing yaw\n\
+            ----------|------|------|0.001|-|-|5\n\
+            // Comment\n\
+            strafing vectorial\n\
+            buttons 0 1 2 3\n\
+            buttons 0 1 2 3\n\
+            ----------|------|------|0.001|-|-|5\n\
+            strafing vectorial\n\
+            buttons\n\
+            ----------|------|------|0.001|-|-|5";
+
+        let hltas = normalized(contents, Options::default());
+        let expected = parse_frames(
+            "\
+            ----------|------|------|0.001|-|-|5\n\
+            // Comment\n\
+            strafing vectorial\n\
+            buttons 0 1 2 3\n\
+            ----------|------|------|0.001|-|-|5\n\
+            buttons\n\
+            ----------|------|------|0.001|-|-|5",
+        );
+        assert_eq!(hltas, expected);
+
+        let hltas = normalized(
+            contents,
+            Options {
+                remove_comments: true,
+            },
+        );
+        assert!(!hltas
+            .lines
+            .iter()
+            .any(|line| matches!(line, Line::Comment(_))));
+    }
+}
@@ -0,0 +1,512 @@
+//! Matching of override lines to the frames they apply to.
+//!
+//! `target_yaw_override`, `render_yaw_override`, `pitch_override` and `render_pitch_override`
+//! lines contain one value per frame, applied to the frames of the following frame bulks. Nothing
+//! in the format ties the number of values to those frame bulks, so it's easy to end up with
+//! values that are never used or with values running out in the middle of a frame bulk. This
+//! module matches every value to its frame and reports such mismatches, along with target yaw
+//! overrides applied while vectorial strafing is off, which has no effect.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{
+//!     overrides::{self, Issue, OverrideKind},
+//!     HLTAS,
+//! };
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|2
+//! pitch_override 10 20 30
+//! ----------|------|------|0.001|-|-|5";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let analysis = overrides::analyze(&hltas);
+//!
+//! let value = analysis.value_at(OverrideKind::Pitch, 3).unwrap();
+//! assert_eq!(value.line_index, 1);
+//! assert_eq!(value.value_index, 1);
+//! assert_eq!(value.value, 20.);
+//!
+//! assert_eq!(
+//!     analysis.issues,
+//!     [Issue::Underrun {
+//!         kind: OverrideKind::Pitch,
+//!         line_index: 1,
+//!         frame_bulk_line_index: 2,
+//!         frames_without_values: 2,
+//!     }]
+//! );
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{types::Line, HLTAS};
+
+/// Kind of an override line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverrideKind {
+    /// `target_yaw_override`.
+    TargetYaw,
+    /// `render_yaw_override`.
+    RenderYaw,
+    /// `pitch_override`.
+    Pitch,
+    /// `render_pitch_override`.
+    RenderPitch,
+}
+
+/// An override line with the frames its values apply to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Application<'a> {
+    /// Kind of the override line.
+    pub kind: OverrideKind,
+    /// Index of the override line in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Values of the override line.
+    pub values: &'a [f32],
+    /// Index of the frame the first value applies to.
+    pub start_frame: u64,
+    /// Number of values that are applied to frames.
+    ///
+    /// The value at index `i` applies to frame `start_frame + i` for `i` less than this.
+    pub applied: usize,
+}
+
+/// A value of an override line applied to a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverrideValue {
+    /// Index of the override line in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// Index of the value in the override line.
+    pub value_index: usize,
+    /// The value.
+    pub value: f32,
+}
+
+/// Override values applied to a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameOverrides {
+    /// Value of `target_yaw_override`.
+    pub target_yaw: Option<OverrideValue>,
+    /// Value of `render_yaw_override`.
+    pub render_yaw: Option<OverrideValue>,
+    /// Value of `pitch_override`.
+    pub pitch: Option<OverrideValue>,
+    /// Value of `render_pitch_override`.
+    pub render_pitch: Option<OverrideValue>,
+}
+
+/// A mismatch between an override line and the frames following it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+    /// Some values are never applied because another override line of the same kind or the end
+    /// of the script comes first.
+    Overrun {
+        /// Kind of the override line.
+        kind: OverrideKind,
+        /// Index of the override line in [`HLTAS::lines`].
+        line_index: usize,
+        /// Number of values that are never applied.
+        unused_values: usize,
+        /// Index of the override line that replaces this one in [`HLTAS::lines`], or `None` if
+        /// the script ends first.
+        replaced_by: Option<usize>,
+    },
+    /// The values run out in the middle of a frame bulk.
+    Underrun {
+        /// Kind of the override line.
+        kind: OverrideKind,
+        /// Index of the override line in [`HLTAS::lines`].
+        line_index: usize,
+        /// Index of the frame bulk where the values run out in [`HLTAS::lines`].
+        frame_bulk_line_index: usize,
+        /// Number of frames of that frame bulk left without a value.
+        frames_without_values: u32,
+    },
+    /// A target yaw override is applied to a frame bulk while vectorial strafing is off.
+    NoVectorialStrafing {
+        /// Index of the override line in [`HLTAS::lines`].
+        line_index: usize,
+        /// Index of the first such frame bulk in [`HLTAS::lines`].
+        frame_bulk_line_index: usize,
+    },
+}
+
+/// Override lines of a script matched to their frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis<'a> {
+    /// Every override line with the frames it applies to, in order.
+    pub applications: Vec<Application<'a>>,
+    /// Mismatches between override lines and frames, in the order they are found.
+    pub issues: Vec<Issue>,
+}
+
+impl OverrideKind {
+    /// All kinds of override lines.
+    pub const ALL: [OverrideKind; 4] = [
+        OverrideKind::TargetYaw,
+        OverrideKind::RenderYaw,
+        OverrideKind::Pitch,
+        OverrideKind::RenderPitch,
+    ];
+
+    /// Returns the kind and the values of an override line, or `None` if the line isn't one.
+    pub fn of(line: &Line) -> Option<(OverrideKind, &[f32])> {
+        match line {
+            Line::TargetYawOverride(values) => Some((OverrideKind::TargetYaw, values)),
+            Line::RenderYawOverride(values) => Some((OverrideKind::RenderYaw, values)),
+            Line::PitchOverride(values) => Some((OverrideKind::Pitch, values)),
+            Line::RenderPitchOverride(values) => Some((OverrideKind::RenderPitch, values)),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the override line, like `"target_yaw_override"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            OverrideKind::TargetYaw => "target_yaw_override",
+            OverrideKind::RenderYaw => "render_yaw_override",
+            OverrideKind::Pitch => "pitch_override",
+            OverrideKind::RenderPitch => "render_pitch_override",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Issue {
+    /// Returns the index of the line the issue is about in [`HLTAS::lines`].
+    ///
+    /// This is the frame bulk for underruns and the override line otherwise.
+    pub fn line_index(&self) -> usize {
+        match *self {
+            Issue::Overrun { line_index, .. } => line_index,
+            Issue::Underrun {
+                frame_bulk_line_index,
+                ..
+            } => frame_bulk_line_index,
+            Issue::NoVectorialStrafing { line_index, .. } => line_index,
+        }
+    }
+}
+
+impl Analysis<'_> {
+    /// Returns the value of the given kind applied to the frame with the given index.
+    pub fn value_at(&self, kind: OverrideKind, frame: u64) -> Option<OverrideValue> {
+        // Applications of the same kind are ordered by the start frame, and an earlier one
+        // starting at the same frame is replaced by a later one before applying any values.
+        let application = self
+            .applications
+            .iter()
+            .rev()
+            .filter(|application| application.kind == kind)
+            .find(|application| application.start_frame <= frame)?;
+
+        let value_index = usize::try_from(frame - application.start_frame).ok()?;
+        if value_index >= application.applied {
+            return None;
+        }
+
+        Some(OverrideValue {
+            line_index: application.line_index,
+            value_index,
+            value: application.values[value_index],
+        })
+    }
+
+    /// Returns the values of all kinds applied to the frame with the given index.
+    pub fn frame(&self, frame: u64) -> FrameOverrides {
+        FrameOverrides {
+            target_yaw: self.value_at(OverrideKind::TargetYaw, frame),
+            render_yaw: self.value_at(OverrideKind::RenderYaw, frame),
+            pitch: self.value_at(OverrideKind::Pitch, frame),
+            render_pitch: self.value_at(OverrideKind::RenderPitch, frame),
+        }
+    }
+}
+
+/// Matches every override line of a script to the frames it applies to.
+pub fn analyze(hltas: &HLTAS) -> Analysis<'_> {
+    let mut applications = Vec::new();
+    let mut issues = Vec::new();
+
+    // Index into `applications` of the current override of every kind.
+    let mut active: [Option<usize>; 4] = [None; 4];
+    // Whether the current target yaw override was already reported for vectorial strafing.
+    let mut reported_vectorial = false;
+    let mut vectorial_strafing = false;
+    let mut frame = 0;
+
+    let overrun = |application: &Application, replaced_by| {
+        let unused_values = application.values.len() - application.applied;
+        (unused_values > 0).then_some(Issue::Overrun {
+            kind: application.kind,
+            line_index: application.line_index,
+            unused_values,
+            replaced_by,
+        })
+    };
+
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        if let Some((kind, values)) = OverrideKind::of(line) {
+            if let Some(index) = active[kind.index()] {
+                issues.extend(overrun(&applications[index], Some(line_index)));
+            }
+
+            active[kind.index()] = Some(applications.len());
+            applications.push(Application {
+                kind,
+                line_index,
+                values,
+                start_frame: frame,
+                applied: 0,
+            });
+
+            if kind == OverrideKind::TargetYaw {
+                reported_vectorial = false;
+            }
+            continue;
+        }
+
+        match line {
+            Line::VectorialStrafing(enabled) => vectorial_strafing = *enabled,
+            Line::FrameBulk(frame_bulk) => {
+                let frame_count = frame_bulk.frame_count.get();
+
+                for &index in active.iter().flatten() {
+                    let application = &mut applications[index];
+                    let remaining = application.values.len() - application.applied;
+                    if remaining == 0 {
+                        continue;
+                    }
+
+                    if application.kind == OverrideKind::TargetYaw
+                        && !vectorial_strafing
+                        && !reported_vectorial
+                    {
+                        reported_vectorial = true;
+                        issues.push(Issue::NoVectorialStrafing {
+                            line_index: application.line_index,
+                            frame_bulk_line_index: line_index,
+                        });
+                    }
+
+                    match u32::try_from(remaining) {
+                        Ok(remaining) if remaining < frame_count => {
+                            application.applied = application.values.len();
+                            issues.push(Issue::Underrun {
+                                kind: application.kind,
+                                line_index: application.line_index,
+                                frame_bulk_line_index: line_index,
+                                frames_without_values: frame_count - remaining,
+                            });
+                        }
+                        _ => application.applied += frame_count as usize,
+                    }
+                }
+
+                frame += u64::from(frame_count);
+            }
+            _ => {}
+        }
+    }
+
+    for index in active.iter().flatten() {
+        issues.extend(overrun(&applications[*index], None));
+    }
+
+    Analysis {
+        applications,
+        issues,
+    }
+}
+
+impl Display for OverrideKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Issue::Overrun {
+                kind,
+                unused_values,
+                replaced_by: Some(_),
+                ..
+            } => write!(
+                f,
+                "{} values of {} are never used because another {} line comes first",
+                unused_values, kind, kind
+            ),
+            Issue::Overrun {
+                kind,
+                unused_values,
+                replaced_by: None,
+                ..
+            } => write!(
+                f,
+                "{} values of {} are never used because the script ends first",
+                unused_values, kind
+            ),
+            Issue::Underrun {
+                kind,
+                frames_without_values,
+                ..
+            } => write!(
+                f,
+                "{} runs out of values {} frames before the end of this frame bulk",
+                kind, frames_without_values
+            ),
+            Issue::NoVectorialStrafing { .. } => write!(
+                f,
+                "target_yaw_override has no effect because vectorial strafing is off"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{for_each_test_script, parse_frames};
+
+    fn analyze_str(contents: &str) -> Vec<Issue> {
+        let hltas = parse_frames(contents);
+        analyze(&hltas).issues
+    }
+
+    #[test]
+    fn matches_frames() {
+        for_each_test_script(|path, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+            let analysis = analyze(&hltas);
+
+            for frame in hltas.frames() {
+                let overrides = analysis.frame(frame.index);
+                let value = |x: Option<OverrideValue>| x.map(|x| x.value);
+                assert_eq!(
+                    value(overrides.target_yaw),
+                    frame.state.target_yaw_override,
+                    "{}",
+                    path.display()
+                );
+                assert_eq!(value(overrides.render_yaw), frame.state.render_yaw_override);
+                assert_eq!(value(overrides.pitch), frame.state.pitch_override);
+                assert_eq!(
+                    value(overrides.render_pitch),
+                    frame.state.render_pitch_override
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn exact() {
+        let contents = "\
+            strafing vectorial\n\
+            target_yaw_override 1 2 3\n\
+            ----------|------|------|0.001|-|-|1\n\
+            ----------|------|------|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|5";
+        assert_eq!(analyze_str(contents), []);
+    }
+
+    #[test]
+    fn overrun() {
+        let contents = "\
+            pitch_override 1 2 3\n\
+            ----------|------|------|0.001|-|-|1\n\
+            pitch_override 4 5\n\
+            render_pitch_override 6\n\
+            render_pitch_override 7";
+        assert_eq!(
+            analyze_str(contents),
+            [
+                Issue::Overrun {
+                    kind: OverrideKind::Pitch,
+                    line_index: 0,
+                    unused_values: 2,
+                    replaced_by: Some(2),
+                },
+                Issue::Overrun {
+                    kind: OverrideKind::RenderPitch,
+                    line_index: 3,
+                    unused_values: 1,
+                    replaced_by: Some(4),
+                },
+                Issue::Overrun {
+                    kind: OverrideKind::Pitch,
+                    line_index: 2,
+                    unused_values: 2,
+                    replaced_by: None,
+                },
+                Issue::Overrun {
+                    kind: OverrideKind::RenderPitch,
+                    line_index: 4,
+                    unused_values: 1,
+                    replaced_by: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn underrun_and_vectorial() {
+        let contents = "\
+            target_yaw_override 1 2 3\n\
+            render_yaw_override 4\n\
+            ----------|------|------|0.001|-|-|2\n\
+            ----------|------|------|0.001|-|-|2";
+        assert_eq!(
+            analyze_str(contents),
+            [
+                Issue::NoVectorialStrafing {
+                    line_index: 0,
+                    frame_bulk_line_index: 2,
+                },
+                Issue::Underrun {
+                    kind: OverrideKind::RenderYaw,
+                    line_index: 1,
+                    frame_bulk_line_index: 2,
+                    frames_without_values: 1,
+                },
+                Issue::Underrun {
+                    kind: OverrideKind::TargetYaw,
+                    line_index: 0,
+                    frame_bulk_line_index: 3,
+                    frames_without_values: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn value_at() {
+        let hltas = HLTAS::from_str(
+            "version 1\nframes\n\
+             render_yaw_override 1 2\n\
+             render_yaw_override 3 4\n\
+             ----------|------|------|0.001|-|-|3",
+        )
+        .unwrap();
+        let analysis = analyze(&hltas);
+
+        let value = |frame| {
+            analysis
+                .value_at(OverrideKind::RenderYaw, frame)
+                .map(|x| (x.line_index, x.value_index, x.value))
+        };
+        assert_eq!(value(0), Some((1, 0, 3.)));
+        assert_eq!(value(1), Some((1, 1, 4.)));
+        assert_eq!(value(2), None);
+        assert_eq!(analysis.value_at(OverrideKind::Pitch, 0), None);
+    }
+}
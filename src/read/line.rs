@@ -2,11 +2,11 @@ use std::{num::NonZeroU32, str::FromStr};
 
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag},
-    character::complete::{anychar, char, not_line_ending, space0, space1},
-    combinator::{all_consuming, cut, fail, map, map_res, not, opt, peek, recognize, verify},
+    bytes::complete::{is_not, tag, take_while},
+    character::complete::{anychar, char, line_ending, not_line_ending, space0, space1},
+    combinator::{all_consuming, cut, eof, fail, map, map_res, not, opt, peek, recognize, verify},
     multi::separated_list1,
-    sequence::{pair, preceded, separated_pair, tuple},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
 };
 
 use crate::{
@@ -25,12 +25,12 @@ fn uncut<'a, O, F: Fn(&'a str) -> IResult<O>>(f: F) -> impl Fn(&'a str) -> IResu
     }
 }
 
-fn recognize_float(i: &str) -> IResult<&'_ str> {
+fn recognize_float(i: &str) -> IResult<'_, &str> {
     // Nom's recognize_float contains a very annoying cut(). Get rid of it.
     uncut(nom::number::complete::recognize_float)(i)
 }
 
-fn strafe_type(i: &str) -> IResult<StrafeType> {
+fn strafe_type(i: &str) -> IResult<'_, StrafeType> {
     alt((
         map(char('0'), |_| StrafeType::MaxAccel),
         map(char('1'), |_| StrafeType::MaxAngle),
@@ -45,7 +45,7 @@ fn strafe_type(i: &str) -> IResult<StrafeType> {
     ))(i)
 }
 
-fn strafe_dir(i: &str) -> IResult<StrafeDir> {
+fn strafe_dir(i: &str) -> IResult<'_, StrafeDir> {
     // The actual values for Yaw, Point and Line are filled in later, while parsing the yaw field.
     alt((
         map(char('0'), |_| StrafeDir::Left),
@@ -63,20 +63,20 @@ fn strafe_dir(i: &str) -> IResult<StrafeDir> {
     ))(i)
 }
 
-fn strafe_settings(i: &str) -> IResult<StrafeSettings> {
+fn strafe_settings(i: &str) -> IResult<'_, StrafeSettings> {
     map(tuple((strafe_type, strafe_dir)), |(type_, dir)| {
         StrafeSettings { type_, dir }
     })(i)
 }
 
-fn strafe(i: &str) -> IResult<Option<StrafeSettings>> {
+fn strafe(i: &str) -> IResult<'_, Option<StrafeSettings>> {
     alt((
         map(tag("---"), |_| None),
         map(preceded(char('s'), strafe_settings), Some),
     ))(i)
 }
 
-fn parse_times(i: &str) -> IResult<Times> {
+fn parse_times(i: &str) -> IResult<'_, Times> {
     let (i, times) = opt(non_zero_u32)(i)?;
     Ok((
         i,
@@ -86,7 +86,7 @@ fn parse_times(i: &str) -> IResult<Times> {
     ))
 }
 
-fn lgagst_action_speed(i: &str) -> IResult<LeaveGroundActionSpeed> {
+fn lgagst_action_speed(i: &str) -> IResult<'_, LeaveGroundActionSpeed> {
     alt((
         map(char('l'), |_| LeaveGroundActionSpeed::Optimal),
         map(char('L'), |_| {
@@ -95,7 +95,7 @@ fn lgagst_action_speed(i: &str) -> IResult<LeaveGroundActionSpeed> {
     ))(i)
 }
 
-fn lgagst_action(i: &str) -> IResult<LeaveGroundAction> {
+fn lgagst_action(i: &str) -> IResult<'_, LeaveGroundAction> {
     let (i, speed) = lgagst_action_speed(i)?;
     let (i, times) = parse_times(i)?;
 
@@ -141,7 +141,7 @@ fn lgagst_action(i: &str) -> IResult<LeaveGroundAction> {
     )))(i)
 }
 
-fn non_lgagst_action(i: &str) -> IResult<LeaveGroundAction> {
+fn non_lgagst_action(i: &str) -> IResult<'_, LeaveGroundAction> {
     let (i, _) = char('-')(i)?;
 
     // Check for the both autojump and ducktap error.
@@ -180,7 +180,7 @@ fn non_lgagst_action(i: &str) -> IResult<LeaveGroundAction> {
     ))(i)
 }
 
-fn leave_ground_action(i: &str) -> IResult<Option<LeaveGroundAction>> {
+fn leave_ground_action(i: &str) -> IResult<'_, Option<LeaveGroundAction>> {
     alt((
         map(lgagst_action, Some),
         map(non_lgagst_action, Some),
@@ -188,7 +188,7 @@ fn leave_ground_action(i: &str) -> IResult<Option<LeaveGroundAction>> {
     ))(i)
 }
 
-fn jump_bug(i: &str) -> IResult<Option<JumpBug>> {
+fn jump_bug(i: &str) -> IResult<'_, Option<JumpBug>> {
     alt((
         map(char('-'), |_| None),
         map(preceded(char('b'), parse_times), |times| {
@@ -197,7 +197,7 @@ fn jump_bug(i: &str) -> IResult<Option<JumpBug>> {
     ))(i)
 }
 
-fn duck_before_collision(i: &str) -> IResult<Option<DuckBeforeCollision>> {
+fn duck_before_collision(i: &str) -> IResult<'_, Option<DuckBeforeCollision>> {
     alt((
         map(char('-'), |_| None),
         map(preceded(char('c'), parse_times), |times| {
@@ -215,7 +215,7 @@ fn duck_before_collision(i: &str) -> IResult<Option<DuckBeforeCollision>> {
     ))(i)
 }
 
-fn duck_before_ground(i: &str) -> IResult<Option<DuckBeforeGround>> {
+fn duck_before_ground(i: &str) -> IResult<'_, Option<DuckBeforeGround>> {
     alt((
         map(char('-'), |_| None),
         map(preceded(char('g'), parse_times), |times| {
@@ -224,7 +224,7 @@ fn duck_before_ground(i: &str) -> IResult<Option<DuckBeforeGround>> {
     ))(i)
 }
 
-fn duck_when_jump(i: &str) -> IResult<Option<DuckWhenJump>> {
+fn duck_when_jump(i: &str) -> IResult<'_, Option<DuckWhenJump>> {
     alt((
         map(char('-'), |_| None),
         map(preceded(char('w'), parse_times), |times| {
@@ -233,13 +233,42 @@ fn duck_when_jump(i: &str) -> IResult<Option<DuckWhenJump>> {
     ))(i)
 }
 
-fn auto_actions(i: &str) -> IResult<AutoActions> {
+/// Wraps a parser for a part of the auto actions field after the strafing settings.
+fn auto_action<'a, O>(
+    f: impl FnMut(&'a str) -> IResult<'a, O>,
+) -> impl FnMut(&'a str) -> IResult<'a, O> {
+    cut(context(
+        Context::InvalidAutoActions,
+        preceded(
+            context(Context::TooFewAutoActionsCharacters, not(char('|'))),
+            f,
+        ),
+    ))
+}
+
+/// Characters that can appear in the auto actions field.
+const AUTO_ACTIONS_CHARACTERS: &str = "-0123456789sljdDLbcCgw";
+
+/// Checks that a field of auto actions characters followed by `|` has at least the 10 characters
+/// of an auto actions field.
+fn auto_actions_length(i: &str) -> IResult<'_, ()> {
+    let field = terminated(
+        take_while(|c| AUTO_ACTIONS_CHARACTERS.contains(c)),
+        char('|'),
+    );
+    cut(context(
+        Context::TooFewAutoActionsCharacters,
+        not(verify(field, |field: &str| field.len() < 10)),
+    ))(i)
+}
+
+fn auto_actions(i: &str) -> IResult<'_, AutoActions> {
     let (i, strafe) = strafe(i)?;
-    let (i, leave_ground_action) = cut(leave_ground_action)(i)?;
-    let (i, jump_bug) = cut(jump_bug)(i)?;
-    let (i, duck_before_collision) = cut(duck_before_collision)(i)?;
-    let (i, duck_before_ground) = cut(duck_before_ground)(i)?;
-    let (i, duck_when_jump) = cut(duck_when_jump)(i)?;
+    let (i, leave_ground_action) = auto_action(leave_ground_action)(i)?;
+    let (i, jump_bug) = auto_action(jump_bug)(i)?;
+    let (i, duck_before_collision) = auto_action(duck_before_collision)(i)?;
+    let (i, duck_before_ground) = auto_action(duck_before_ground)(i)?;
+    let (i, duck_when_jump) = auto_action(duck_when_jump)(i)?;
     Ok((
         i,
         AutoActions {
@@ -257,7 +286,7 @@ fn key<'a>(symbol: char) -> impl FnMut(&'a str) -> IResult<bool> {
     alt((map(char(symbol), |_| true), map(char('-'), |_| false)))
 }
 
-fn movement_keys(i: &str) -> IResult<MovementKeys> {
+fn movement_keys(i: &str) -> IResult<'_, MovementKeys> {
     let (i, forward) = key('f')(i)?;
     let (i, left) = key('l')(i)?;
     let (i, right) = key('r')(i)?;
@@ -277,7 +306,7 @@ fn movement_keys(i: &str) -> IResult<MovementKeys> {
     ))
 }
 
-fn action_keys(i: &str) -> IResult<ActionKeys> {
+fn action_keys(i: &str) -> IResult<'_, ActionKeys> {
     let (i, jump) = key('j')(i)?;
     let (i, duck) = key('d')(i)?;
     let (i, use_) = key('u')(i)?;
@@ -297,7 +326,7 @@ fn action_keys(i: &str) -> IResult<ActionKeys> {
     ))
 }
 
-fn float(i: &str) -> IResult<f32> {
+fn float(i: &str) -> IResult<'_, f32> {
     verify(map_res(recognize_float, f32::from_str), |x| x.is_finite())(i)
 }
 
@@ -470,11 +499,11 @@ fn yaw_field<'a>(
     }
 }
 
-fn pitch(i: &str) -> IResult<Option<f32>> {
+fn pitch(i: &str) -> IResult<'_, Option<f32>> {
     alt((map(float, Some), map(char('-'), |_| None)))(i)
 }
 
-fn frame_count(i: &str) -> IResult<NonZeroU32> {
+fn frame_count(i: &str) -> IResult<'_, NonZeroU32> {
     alt((
         map(char('-'), |_| NonZeroU32::new(1).unwrap()), // Backwards compatibility.
         map(char('0'), |_| NonZeroU32::new(1).unwrap()), // Backwards compatibility.
@@ -494,23 +523,39 @@ fn frame_count(i: &str) -> IResult<NonZeroU32> {
 /// let frame_bulk = hltas::read::frame_bulk(contents).unwrap().1;
 /// assert_eq!(frame_bulk.frame_time, "0.001");
 /// ```
-pub fn frame_bulk(i: &str) -> IResult<FrameBulk> {
+pub fn frame_bulk(i: &str) -> IResult<'_, FrameBulk> {
+    auto_actions_length(i)?;
     // Mutable because the movement parameter will be filled in later.
     let (i, mut auto_actions) = auto_actions(i)?;
     // Backwards compatibility: HLTAS didn't check the first field length, so extra characters were
     // permitted.
     let (i, _) = opt(is_not("|"))(i)?;
 
-    let (i, movement_keys) = cut(preceded(char('|'), movement_keys))(i)?;
-    let (i, action_keys) = cut(preceded(char('|'), action_keys))(i)?;
-    let (i, frame_time) = cut(preceded(char('|'), recognize_float))(i)?;
+    let (i, movement_keys) = cut(context(
+        Context::InvalidMovementKeys,
+        preceded(char('|'), movement_keys),
+    ))(i)?;
+    let (i, action_keys) = cut(context(
+        Context::InvalidActionKeys,
+        preceded(char('|'), action_keys),
+    ))(i)?;
+    let (i, frame_time) = cut(context(
+        Context::InvalidFrameTime,
+        preceded(char('|'), recognize_float),
+    ))(i)?;
 
     // Parse the yaw field and get the updated movement.
-    let (i, new_movement) = cut(preceded(char('|'), yaw_field(auto_actions.movement)))(i)?;
+    let (i, new_movement) = cut(context(
+        Context::InvalidYawField,
+        preceded(char('|'), yaw_field(auto_actions.movement)),
+    ))(i)?;
     auto_actions.movement = new_movement;
 
-    let (i, pitch) = cut(preceded(char('|'), pitch))(i)?;
-    let (i, frame_count) = cut(preceded(char('|'), frame_count))(i)?;
+    let (i, pitch) = cut(context(Context::InvalidPitch, preceded(char('|'), pitch)))(i)?;
+    let (i, frame_count) = cut(context(
+        Context::InvalidFrameCount,
+        preceded(char('|'), frame_count),
+    ))(i)?;
 
     // The console command field is optional.
     let (i, console_command) = opt(preceded(char('|'), not_line_ending))(i)?;
@@ -529,22 +574,22 @@ pub fn frame_bulk(i: &str) -> IResult<FrameBulk> {
     ))
 }
 
-fn line_save(i: &str) -> IResult<&str> {
+fn line_save(i: &str) -> IResult<'_, &str> {
     let (i, (name, value)) = property(i)?;
-    tag("save")(name)?;
+    all_consuming(tag("save"))(name)?;
     cut(context(Context::NoSaveName, anychar))(value)?;
     Ok((i, value))
 }
 
-fn line_seed(i: &str) -> IResult<u32> {
+fn line_seed(i: &str) -> IResult<'_, u32> {
     let (i, (name, value)) = property(i)?;
-    tag("seed")(name)?;
+    all_consuming(tag("seed"))(name)?;
     cut(context(Context::NoSeed, anychar))(value)?;
     let (_, seed) = cut(shared_seed)(value)?;
     Ok((i, seed))
 }
 
-fn button(i: &str) -> IResult<Button> {
+fn button(i: &str) -> IResult<'_, Button> {
     alt((
         map(char('0'), |_| Button::Forward),
         map(char('1'), |_| Button::ForwardLeft),
@@ -557,7 +602,7 @@ fn button(i: &str) -> IResult<Button> {
     ))(i)
 }
 
-fn buttons(i: &str) -> IResult<Buttons> {
+fn buttons(i: &str) -> IResult<'_, Buttons> {
     cut(context(Context::NoButtons, preceded(space1, anychar)))(i)?;
     let (i, air_left) = preceded(space1, button)(i)?;
     cut(context(Context::NoButtons, preceded(space1, anychar)))(i)?;
@@ -577,8 +622,8 @@ fn buttons(i: &str) -> IResult<Buttons> {
     ))
 }
 
-fn line_buttons(i: &str) -> IResult<Buttons> {
-    let (i, _) = tag("buttons")(i)?;
+fn line_buttons(i: &str) -> IResult<'_, Buttons> {
+    let (i, _) = terminated(tag("buttons"), peek(alt((space1, line_ending, eof))))(i)?;
 
     if preceded(space1::<&str, ()>, not_line_ending)(i).is_ok() {
         cut(buttons)(i)
@@ -587,29 +632,29 @@ fn line_buttons(i: &str) -> IResult<Buttons> {
     }
 }
 
-fn line_lgagst_min_speed(i: &str) -> IResult<f32> {
+fn line_lgagst_min_speed(i: &str) -> IResult<'_, f32> {
     let (i, (name, value)) = property(i)?;
-    tag("lgagstminspeed")(name)?;
+    all_consuming(tag("lgagstminspeed"))(name)?;
     cut(context(Context::NoLGAGSTMinSpeed, anychar))(value)?;
     let (_, lgagst_min_speed) = cut(float)(value)?;
     Ok((i, lgagst_min_speed))
 }
 
-fn line_reset(i: &str) -> IResult<i64> {
+fn line_reset(i: &str) -> IResult<'_, i64> {
     let (i, (name, value)) = property(i)?;
-    tag("reset")(name)?;
+    all_consuming(tag("reset"))(name)?;
     cut(context(Context::NoResetSeed, anychar))(value)?;
     let (_, seed) = cut(non_shared_seed)(value)?;
     Ok((i, seed))
 }
 
-fn line_comment(i: &str) -> IResult<&str> {
+fn line_comment(i: &str) -> IResult<'_, &str> {
     preceded(tag("//"), not_line_ending)(i)
 }
 
-fn line_strafing(i: &str) -> IResult<bool> {
+fn line_strafing(i: &str) -> IResult<'_, bool> {
     let (i, (name, value)) = property(i)?;
-    tag("strafing")(name)?;
+    all_consuming(tag("strafing"))(name)?;
     let (_, enabled) = cut(context(
         Context::InvalidStrafingAlgorithm,
         alt((map(tag("yaw"), |_| false), map(tag("vectorial"), |_| true))),
@@ -617,22 +662,25 @@ fn line_strafing(i: &str) -> IResult<bool> {
     Ok((i, enabled))
 }
 
-fn parse_tolerance(i: &str) -> IResult<f32> {
+fn parse_tolerance(i: &str) -> IResult<'_, f32> {
     preceded(
         context(Context::NoPlusMinusBeforeTolerance, tag("+-")),
         float,
     )(i)
 }
 
-fn parse_look_at_entity_index(i: &str) -> IResult<NonZeroU32> {
-    preceded(tag("entity "), cut(non_zero_u32))(i)
+fn parse_look_at_entity_index(i: &str) -> IResult<'_, NonZeroU32> {
+    preceded(
+        tag("entity "),
+        cut(context(Context::InvalidLookAtEntity, non_zero_u32)),
+    )(i)
 }
 
-fn parse_xyz(i: &str) -> IResult<(f32, f32, f32)> {
+fn parse_xyz(i: &str) -> IResult<'_, (f32, f32, f32)> {
     tuple((float, preceded(tag(" "), float), preceded(tag(" "), float)))(i)
 }
 
-fn parse_look_at(i: &str) -> IResult<(Option<NonZeroU32>, (f32, f32, f32))> {
+fn parse_look_at(i: &str) -> IResult<'_, (Option<NonZeroU32>, (f32, f32, f32))> {
     preceded(
         tag(" "),
         alt((
@@ -648,7 +696,7 @@ fn parse_look_at(i: &str) -> IResult<(Option<NonZeroU32>, (f32, f32, f32))> {
     )(i)
 }
 
-fn line_target_yaw(i: &str) -> IResult<VectorialStrafingConstraints> {
+fn line_target_yaw(i: &str) -> IResult<'_, VectorialStrafingConstraints> {
     let (i, (name, value)) = property(i)?;
     all_consuming(tag("target_yaw"))(name)?;
 
@@ -693,7 +741,10 @@ fn line_target_yaw(i: &str) -> IResult<VectorialStrafingConstraints> {
             },
         ),
         map(
-            preceded(tag("look_at"), cut(parse_look_at)),
+            preceded(
+                tag("look_at"),
+                cut(context(Context::InvalidLookAtCoordinates, parse_look_at)),
+            ),
             |(entity, (x, y, z))| VectorialStrafingConstraints::LookAt { entity, x, y, z },
         ),
         map(
@@ -715,24 +766,30 @@ fn line_target_yaw(i: &str) -> IResult<VectorialStrafingConstraints> {
     Ok((i, constraints))
 }
 
-fn line_change(i: &str) -> IResult<Change> {
+fn line_change(i: &str) -> IResult<'_, Change> {
     let (i, (name, value)) = property(i)?;
-    tag("change")(name)?;
+    all_consuming(tag("change"))(name)?;
 
-    let (value, target) = cut(alt((
-        map(tag("yaw"), |_| ChangeTarget::Yaw),
-        map(tag("pitch"), |_| ChangeTarget::Pitch),
-        map(tag("target_yaw_offset"), |_| {
-            ChangeTarget::VectorialStrafingYawOffset
-        }),
-        map(tag("target_yaw"), |_| ChangeTarget::VectorialStrafingYaw),
-    )))(value)?;
+    let (value, target) = cut(context(
+        Context::InvalidChangeTarget,
+        alt((
+            map(tag("yaw"), |_| ChangeTarget::Yaw),
+            map(tag("pitch"), |_| ChangeTarget::Pitch),
+            map(tag("target_yaw_offset"), |_| {
+                ChangeTarget::VectorialStrafingYawOffset
+            }),
+            map(tag("target_yaw"), |_| ChangeTarget::VectorialStrafingYaw),
+        )),
+    ))(value)?;
 
-    let (value, _) = cut(tag(" to "))(value)?;
-    let (value, final_value) = float(value)?;
-    let (value, _) = cut(tag(" over "))(value)?;
-    let (value, over) = float(value)?;
-    cut(tag(" s"))(value)?;
+    let (value, final_value) = cut(context(
+        Context::NoChangeFinalValue,
+        preceded(tag(" to "), float),
+    ))(value)?;
+    let (_, over) = cut(context(
+        Context::NoChangeDuration,
+        terminated(preceded(tag(" over "), float), tag(" s")),
+    ))(value)?;
 
     Ok((
         i,
@@ -744,31 +801,43 @@ fn line_change(i: &str) -> IResult<Change> {
     ))
 }
 
-fn line_target_yaw_override(i: &str) -> IResult<Vec<f32>> {
+fn line_target_yaw_override(i: &str) -> IResult<'_, Vec<f32>> {
     let (i, (name, value)) = property(i)?;
-    tag("target_yaw_override")(name)?;
-    let (_, yaws) = cut(separated_list1(space1, float))(value)?;
+    all_consuming(tag("target_yaw_override"))(name)?;
+    let (_, yaws) = cut(context(
+        Context::InvalidOverrideValues,
+        separated_list1(space1, float),
+    ))(value)?;
     Ok((i, yaws))
 }
 
-fn line_render_yaw_override(i: &str) -> IResult<Vec<f32>> {
+fn line_render_yaw_override(i: &str) -> IResult<'_, Vec<f32>> {
     let (i, (name, value)) = property(i)?;
-    tag("render_yaw_override")(name)?;
-    let (_, yaws) = cut(separated_list1(space1, float))(value)?;
+    all_consuming(tag("render_yaw_override"))(name)?;
+    let (_, yaws) = cut(context(
+        Context::InvalidOverrideValues,
+        separated_list1(space1, float),
+    ))(value)?;
     Ok((i, yaws))
 }
 
-fn line_pitch_override(i: &str) -> IResult<Vec<f32>> {
+fn line_pitch_override(i: &str) -> IResult<'_, Vec<f32>> {
     let (i, (name, value)) = property(i)?;
-    tag("pitch_override")(name)?;
-    let (_, pitches) = cut(separated_list1(space1, float))(value)?;
+    all_consuming(tag("pitch_override"))(name)?;
+    let (_, pitches) = cut(context(
+        Context::InvalidOverrideValues,
+        separated_list1(space1, float),
+    ))(value)?;
     Ok((i, pitches))
 }
 
-fn line_render_pitch_override(i: &str) -> IResult<Vec<f32>> {
+fn line_render_pitch_override(i: &str) -> IResult<'_, Vec<f32>> {
     let (i, (name, value)) = property(i)?;
-    tag("render_pitch_override")(name)?;
-    let (_, pitches) = cut(separated_list1(space1, float))(value)?;
+    all_consuming(tag("render_pitch_override"))(name)?;
+    let (_, pitches) = cut(context(
+        Context::InvalidOverrideValues,
+        separated_list1(space1, float),
+    ))(value)?;
     Ok((i, pitches))
 }
 
@@ -783,7 +852,7 @@ fn line_render_pitch_override(i: &str) -> IResult<Vec<f32>> {
 /// let line = hltas::read::line("reset 1234").unwrap().1;
 /// assert_eq!(line, Line::Reset { non_shared_seed: 1234 });
 /// ```
-pub fn line(i: &str) -> IResult<Line> {
+pub fn line(i: &str) -> IResult<'_, Line> {
     alt((
         map(frame_bulk, Line::FrameBulk),
         map(line_save, |name| Line::Save(name.to_owned())),
@@ -858,6 +927,19 @@ mod tests {
         assert_eq!(line_buttons(input), Ok(("", Buttons::Reset)));
     }
 
+    #[test]
+    fn keywords_are_whole_words() {
+        for input in [
+            "seedling 5",
+            "strafing_mode yaw",
+            "reset_all 5",
+            "target_yaw_override2 1",
+            "buttonsx",
+        ] {
+            assert!(matches!(line(input), Err(nom::Err::Error(_))), "{}", input);
+        }
+    }
+
     #[test]
     fn no_lgagst_min_speed() {
         let input = "lgagstminspeed ";
@@ -880,6 +962,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn too_few_auto_actions_characters() {
+        for n in 0..=9 {
+            let input = format!("{}|------|------|0.001|-|-|5", "-".repeat(n));
+            let err = line(&input).unwrap_err();
+            if let nom::Err::Failure(err) = err {
+                assert_eq!(
+                    err.context,
+                    Some(Context::TooFewAutoActionsCharacters),
+                    "{}",
+                    n
+                );
+            } else {
+                unreachable!()
+            }
+        }
+
+        let input = "s00L-D---w2|------|------|0.001|-|-|5";
+        assert!(line(input).is_ok());
+    }
+
     #[test]
     fn both_autojump_ducktap() {
         let input = "----jd----";
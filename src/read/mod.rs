@@ -1,29 +1,36 @@
 //! Reading `.hltas` files.
 
 use std::{
-    fmt::{self, Display, Formatter},
+    fmt::{self, Display, Formatter, Write},
     num::NonZeroU32,
     str::FromStr,
 };
 
 use nom::{
     self,
+    branch::alt,
     bytes::complete::tag,
-    character::complete::{digit0, line_ending, multispace0, one_of, space1},
-    combinator::{all_consuming, map_res, opt, recognize, verify},
+    character::complete::{digit0, line_ending, multispace0, not_line_ending, one_of, space1},
+    combinator::{all_consuming, eof, map, map_res, opt, peek, recognize, verify},
     error::{FromExternalError, ParseError},
     multi::{many1, separated_list0},
-    sequence::{delimited, pair, preceded},
+    sequence::{delimited, pair, preceded, terminated},
     Offset,
 };
 
-use crate::types::{Line, HLTAS};
+use crate::{
+    diagnostic::{Diagnostic, Severity, Suggestion},
+    types::{Line, HLTAS},
+};
 
 mod line;
 pub use line::{frame_bulk, line};
 
 pub(crate) mod properties;
-use properties::properties;
+
+mod reader;
+use properties::{properties, property, set_property};
+pub use reader::{Reader, ReaderError};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum ErrorKind {
@@ -78,6 +85,49 @@ pub enum Context {
     NoYawOffset,
     /// Empty yaw offset acceleration.
     NoYawOffsetAcceleration,
+    /// The auto actions field of a frame bulk is too short.
+    TooFewAutoActionsCharacters,
+    /// Invalid auto actions field of a frame bulk.
+    InvalidAutoActions,
+    /// Invalid movement keys field of a frame bulk.
+    InvalidMovementKeys,
+    /// Invalid action keys field of a frame bulk.
+    InvalidActionKeys,
+    /// Invalid frame time field of a frame bulk.
+    InvalidFrameTime,
+    /// Invalid yaw field of a frame bulk.
+    InvalidYawField,
+    /// Invalid pitch field of a frame bulk.
+    InvalidPitch,
+    /// Invalid frame count field of a frame bulk.
+    InvalidFrameCount,
+    /// Invalid target of a change line.
+    InvalidChangeTarget,
+    /// The final value of a change line is missing or invalid.
+    NoChangeFinalValue,
+    /// The duration of a change line is missing or invalid.
+    NoChangeDuration,
+    /// The values of an override line are missing or invalid.
+    InvalidOverrideValues,
+    /// The entity index of the look-at vectorial strafing constraint is invalid.
+    InvalidLookAtEntity,
+    /// The coordinates of the look-at vectorial strafing constraint are missing or invalid.
+    InvalidLookAtCoordinates,
+    /// Invalid seeds property value.
+    InvalidSeeds,
+    /// Invalid hlstrafe_version property value.
+    InvalidHLStrafeVersion,
+    /// The frames section is missing.
+    NoFrames,
+}
+
+/// Location of a line that wasn't recognized by the lenient parser.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct UnknownLine {
+    /// Index of the [`Line::Unknown`] in [`HLTAS::lines`].
+    pub index: usize,
+    /// Line number in the input, counting from zero like [`Error::line()`].
+    pub line: usize,
 }
 
 /// `.hltas` parsing error.
@@ -149,50 +199,94 @@ impl Display for Context {
             NoYawOffsetAcceleration => {
                 write!(f, "missing yaw offset acceleration value")
             }
+            TooFewAutoActionsCharacters => write!(
+                f,
+                "too few characters in the auto actions field (expected 10, like \"----------\")"
+            ),
+            InvalidAutoActions => write!(f, "invalid auto actions field"),
+            InvalidMovementKeys => write!(f, "invalid movement keys field"),
+            InvalidActionKeys => write!(f, "invalid action keys field"),
+            InvalidFrameTime => write!(f, "invalid frame time"),
+            InvalidYawField => write!(f, "invalid yaw field"),
+            InvalidPitch => write!(f, "invalid pitch value"),
+            InvalidFrameCount => write!(f, "invalid frame count"),
+            InvalidChangeTarget => write!(
+                f,
+                "invalid change target (only \"yaw\", \"pitch\", \"target_yaw\" and \
+                 \"target_yaw_offset\" allowed)"
+            ),
+            NoChangeFinalValue => write!(f, "missing \"to\" and the final value"),
+            NoChangeDuration => write!(f, "missing \"over\" and the duration in seconds"),
+            InvalidOverrideValues => write!(f, "missing or invalid override values"),
+            InvalidLookAtEntity => write!(f, "invalid look_at entity index"),
+            InvalidLookAtCoordinates => write!(f, "missing or invalid look_at coordinates"),
+            InvalidSeeds => write!(f, "invalid seeds (expected shared and non-shared seed)"),
+            InvalidHLStrafeVersion => write!(f, "invalid hlstrafe_version value"),
+            NoFrames => write!(f, "missing the frames section"),
         }
     }
 }
 
-impl Display for Error<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut line = 0;
-        let mut column = 0;
-        let mut offset = self.whole_input.offset(self.input);
-        let mut just_error_line = None;
-
-        for (j, l) in self.whole_input.lines().enumerate() {
-            if offset <= l.len() {
-                line = j;
-                column = offset;
-                just_error_line = Some(l);
-                break;
-            } else {
-                offset = offset - l.len() - 1;
-            }
+impl Context {
+    /// Returns the stable code of this error, like `"E0005"`.
+    ///
+    /// Codes never change meaning between versions, so they can be used to look up or suppress
+    /// specific errors.
+    pub fn code(self) -> &'static str {
+        use Context::*;
+        match self {
+            ErrorReadingVersion => "E0001",
+            VersionTooHigh => "E0002",
+            BothAutoJumpAndDuckTap => "E0003",
+            NoLeaveGroundAction => "E0004",
+            TimesOnLeaveGroundAction => "E0005",
+            NoSaveName => "E0006",
+            NoSeed => "E0007",
+            NoYaw => "E0008",
+            NoButtons => "E0009",
+            NoLGAGSTMinSpeed => "E0010",
+            NoResetSeed => "E0011",
+            ErrorParsingLine => "E0012",
+            InvalidStrafingAlgorithm => "E0013",
+            NoConstraints => "E0014",
+            NoPlusMinusBeforeTolerance => "E0015",
+            NoFromToParameters => "E0016",
+            NoTo => "E0017",
+            NoYawspeed => "E0018",
+            UnsupportedConstantYawspeedDir => "E0019",
+            NegativeYawspeed => "E0020",
+            NoYawOffset => "E0021",
+            NoYawOffsetAcceleration => "E0022",
+            TooFewAutoActionsCharacters => "E0023",
+            InvalidAutoActions => "E0024",
+            InvalidMovementKeys => "E0025",
+            InvalidActionKeys => "E0026",
+            InvalidFrameTime => "E0027",
+            InvalidYawField => "E0028",
+            InvalidPitch => "E0029",
+            InvalidFrameCount => "E0030",
+            InvalidChangeTarget => "E0031",
+            NoChangeFinalValue => "E0032",
+            NoChangeDuration => "E0033",
+            InvalidOverrideValues => "E0034",
+            InvalidLookAtEntity => "E0035",
+            InvalidLookAtCoordinates => "E0036",
+            InvalidSeeds => "E0037",
+            InvalidHLStrafeVersion => "E0038",
+            NoFrames => "E0039",
         }
+    }
+}
 
-        if let Some(context) = self.context {
-            context.fmt(f)?;
-        } else {
-            match self.kind {
-                ErrorKind::ExpectedChar(c) => {
-                    if let Some(next_char) = self.input.chars().next() {
-                        write!(f, "expected '{}', got '{}'", c, next_char)?;
-                    } else {
-                        write!(f, "expected '{}', got EOF", c)?;
-                    }
-                }
-                ErrorKind::Other(nom_kind) => {
-                    write!(f, "error applying {}", nom_kind.description())?
-                }
-            }
-        }
+impl Display for Error<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
 
         // Can happen if whole_input is some unrelated &str.
-        if just_error_line.is_none() {
-            return Ok(());
-        }
-        let just_error_line = just_error_line.unwrap();
+        let (line, column, just_error_line) = match self.position() {
+            Some(position) => position,
+            None => return Ok(()),
+        };
 
         let line_number = format!("{} | ", line);
 
@@ -242,19 +336,143 @@ impl<'a, E> FromExternalError<&'a str, E> for Error<'a> {
 impl Error<'_> {
     /// Returns the line number on which the error has occurred.
     pub fn line(&self) -> usize {
-        let mut line = 0;
-        let mut offset = self.whole_input.offset(self.input);
+        self.position().map(|(line, _, _)| line).unwrap_or(0)
+    }
+
+    /// Returns the column, in bytes, at which the error has occurred.
+    pub fn column(&self) -> usize {
+        self.position().map(|(_, column, _)| column).unwrap_or(0)
+    }
+
+    /// Returns the byte offset into the input at which the error has occurred.
+    pub fn offset(&self) -> usize {
+        self.whole_input.offset(self.input)
+    }
+
+    /// Returns the line number, the column and the text of the line where the error occurred.
+    fn position(&self) -> Option<(usize, usize, &str)> {
+        let mut offset = self.offset();
 
         for (j, l) in self.whole_input.lines().enumerate() {
             if offset <= l.len() {
-                line = j;
-                break;
+                return Some((j, offset, l));
             } else {
                 offset = offset - l.len() - 1;
             }
         }
 
-        line
+        None
+    }
+
+    /// Writes the error message without the location.
+    fn fmt_message(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(context) = self.context {
+            return context.fmt(f);
+        }
+
+        match self.kind {
+            ErrorKind::ExpectedChar(c) => {
+                if let Some(next_char) = self.input.chars().next() {
+                    write!(f, "expected '{}', got '{}'", c, next_char)
+                } else {
+                    write!(f, "expected '{}', got EOF", c)
+                }
+            }
+            ErrorKind::Other(nom_kind) => write!(f, "error applying {}", nom_kind.description()),
+        }
+    }
+
+    /// Converts the error into an owned [`Diagnostic`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "version 1\nframes\nsave ";
+    /// let diagnostic = HLTAS::from_str(contents).unwrap_err().to_diagnostic();
+    /// assert_eq!(diagnostic.code, "E0006");
+    /// assert_eq!(diagnostic.line, 2);
+    /// assert_eq!(diagnostic.column, 5);
+    /// assert_eq!(diagnostic.message, "missing save name");
+    /// ```
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let start = self.offset();
+        let (line, column) = self
+            .position()
+            .map(|(line, column, _)| (line, column))
+            .unwrap_or((0, 0));
+
+        // Highlight the offending word.
+        let len = self
+            .input
+            .find(|c: char| c.is_whitespace() || c == '|')
+            .unwrap_or(self.input.len());
+
+        let mut message = String::new();
+        let _ = write!(message, "{}", Message(self));
+
+        let mut diagnostic = Diagnostic {
+            code: self
+                .context
+                .map(Context::code)
+                .unwrap_or("E0000")
+                .to_owned(),
+            severity: Severity::Error,
+            message,
+            span: start..start + len,
+            line,
+            column,
+            notes: Vec::new(),
+            suggestion: None,
+        };
+
+        if let (Some(_), ErrorKind::ExpectedChar(c)) = (self.context, self.kind) {
+            diagnostic.notes.push(format!("expected '{}'", c));
+        }
+
+        diagnostic.suggestion = match self.context {
+            Some(Context::VersionTooHigh) => Some(Suggestion {
+                message: "use version 1".to_owned(),
+                span: start..start + 1,
+                replacement: "1".to_owned(),
+            }),
+            Some(Context::NoPlusMinusBeforeTolerance) => Some(Suggestion {
+                message: "add +- before the tolerance".to_owned(),
+                span: start..start,
+                replacement: "+-".to_owned(),
+            }),
+            Some(Context::NegativeYawspeed) => Some(Suggestion {
+                message: "remove the minus sign".to_owned(),
+                span: start..start + 1,
+                replacement: String::new(),
+            }),
+            Some(Context::NoTo) => Some(Suggestion {
+                message: "add \"to\" between the angles".to_owned(),
+                span: start..start,
+                replacement: " to".to_owned(),
+            }),
+            _ => None,
+        };
+
+        diagnostic
+    }
+}
+
+/// Displays just the message of an [`Error`].
+struct Message<'a, 'b>(&'b Error<'a>);
+
+impl Display for Message<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt_message(f)
+    }
+}
+
+impl From<Error<'_>> for Diagnostic {
+    #[inline]
+    fn from(error: Error<'_>) -> Self {
+        error.to_diagnostic()
     }
 }
 
@@ -280,14 +498,14 @@ fn context<'a, T>(
     }
 }
 
-fn non_zero_u32(i: &str) -> IResult<NonZeroU32> {
+fn non_zero_u32(i: &str) -> IResult<'_, NonZeroU32> {
     map_res(
         recognize(pair(one_of("123456789"), digit0)),
         NonZeroU32::from_str,
     )(i)
 }
 
-fn version(i: &str) -> IResult<()> {
+fn version(i: &str) -> IResult<'_, ()> {
     // This is a little involved to report the correct HLTAS error.
     // When we can't parse the version as a number at all, we should report ErrorReadingVersion.
     // When we can parse it as a number and it's above 1, we should report VersionTooHigh.
@@ -303,7 +521,7 @@ fn version(i: &str) -> IResult<()> {
 }
 
 /// Parses a line ending character, followed by any additional whitespace.
-fn whitespace(i: &str) -> IResult<()> {
+fn whitespace(i: &str) -> IResult<'_, ()> {
     let (i, _) = preceded(line_ending, multispace0)(i)?;
     Ok((i, ()))
 }
@@ -329,11 +547,48 @@ fn whitespace(i: &str) -> IResult<()> {
 ///
 /// assert!(hltas::read::all_consuming_lines(lines).is_err());
 /// ```
-pub fn all_consuming_lines(i: &str) -> IResult<Vec<Line>> {
+pub fn all_consuming_lines(i: &str) -> IResult<'_, Vec<Line>> {
     let many_lines = separated_list0(whitespace, line);
     all_consuming(delimited(opt(multispace0), many_lines, opt(multispace0)))(i)
 }
 
+/// Parses a [`Line`], falling back to [`Line::Unknown`] if the line isn't recognized.
+///
+/// Keywords are matched as whole words, so `reset_all 5` isn't recognized as a `reset` line. Lines
+/// that are recognized but contain invalid values, like a `save` line without a save name, are
+/// still errors.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate hltas;
+/// use hltas::types::Line;
+///
+/// let line = hltas::read::line_lenient("reset 1234").unwrap().1;
+/// assert_eq!(line, Line::Reset { non_shared_seed: 1234 });
+///
+/// let line = hltas::read::line_lenient("something new").unwrap().1;
+/// assert_eq!(line, Line::Unknown("something new".to_owned()));
+///
+/// let line = hltas::read::line_lenient("reset_all 5").unwrap().1;
+/// assert_eq!(line, Line::Unknown("reset_all 5".to_owned()));
+/// ```
+pub fn line_lenient(i: &str) -> IResult<'_, Line> {
+    match terminated(line, peek(alt((line_ending, eof))))(i) {
+        Err(nom::Err::Error(_)) => map(
+            verify(not_line_ending, |text: &str| !text.is_empty()),
+            |text: &str| Line::Unknown(text.to_owned()),
+        )(i),
+        result => result,
+    }
+}
+
+/// Parses [`Line`]s with [`line_lenient`], ensuring nothing is left in the input.
+pub fn all_consuming_lines_lenient(i: &str) -> IResult<'_, Vec<Line>> {
+    let many_lines = separated_list0(whitespace, line_lenient);
+    all_consuming(delimited(opt(multispace0), many_lines, opt(multispace0)))(i)
+}
+
 /// Parses an entire HLTAS script, ensuring nothing is left in the input.
 ///
 /// This is a lower-level function. You might be looking for [`HLTAS::from_str`] instead.
@@ -360,18 +615,121 @@ pub fn all_consuming_lines(i: &str) -> IResult<Vec<Line>> {
 ///
 /// assert!(hltas::read::hltas(contents).is_err());
 /// ```
-pub fn hltas(i: &str) -> IResult<HLTAS> {
+pub fn hltas(i: &str) -> IResult<'_, HLTAS> {
+    hltas_with(i, all_consuming_lines)
+}
+
+/// Parses an entire HLTAS script with unrecognized lines kept as [`Line::Unknown`].
+///
+/// This is a lower-level function. You might be looking for [`HLTAS::from_str_lenient`] instead.
+pub fn hltas_lenient(i: &str) -> IResult<'_, HLTAS> {
+    hltas_with(i, all_consuming_lines_lenient)
+}
+
+fn hltas_with<'a>(
+    i: &'a str,
+    lines: impl FnMut(&'a str) -> IResult<'a, Vec<Line>>,
+) -> IResult<'a, HLTAS> {
     let (i, _) = context(Context::ErrorReadingVersion, version)(i)?;
     let (i, properties) = properties(i)?;
-    let (i, _) = preceded(many1(line_ending), tag("frames"))(i)?;
-    let (i, lines) = context(
-        Context::ErrorParsingLine,
-        preceded(whitespace, all_consuming_lines),
+    let (i, _) = context(
+        Context::NoFrames,
+        preceded(many1(line_ending), tag("frames")),
     )(i)?;
+    let (i, lines) = context(Context::ErrorParsingLine, preceded(whitespace, lines))(i)?;
 
     Ok((i, HLTAS { properties, lines }))
 }
 
+/// Parses an entire HLTAS script, recovering from errors at line boundaries.
+///
+/// Every line that fails to parse is reported and skipped, and parsing resumes on the next line.
+/// Returns the script made of everything that parsed successfully along with all errors, in the
+/// order they appear in the input. If the returned errors are empty, the script is the same as
+/// the one returned by [`hltas()`].
+///
+/// This is a lower-level function. You might be looking for [`HLTAS::from_str_recovering`]
+/// instead.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate hltas;
+/// use hltas::read::Context;
+///
+/// let contents = "\
+/// version 1
+/// frames
+/// ------b---|------|------|0.001|-|-|5
+/// save
+/// ------b---|------|------|0.001|-|-|10
+/// seed";
+///
+/// let (hltas, errors) = hltas::read::hltas_recovering(contents);
+/// assert_eq!(hltas.lines.len(), 2);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[0].line(), 3);
+/// assert_eq!(errors[1].line(), 5);
+/// ```
+pub fn hltas_recovering<'a>(input: &'a str) -> (HLTAS, Vec<Error<'a>>) {
+    let mut hltas = HLTAS::default();
+    let mut errors = Vec::new();
+
+    let mut report = |error: nom::Err<Error<'a>>| match error {
+        nom::Err::Error(mut e) | nom::Err::Failure(mut e) => {
+            e.whole_input = input;
+            errors.push(e);
+        }
+        nom::Err::Incomplete(_) => unreachable!(), // We don't use streaming parsers.
+    };
+
+    let mut lines = input
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line));
+
+    let version_line = lines.next().unwrap();
+    if let Err(e) = all_consuming(context(Context::ErrorReadingVersion, version))(version_line) {
+        report(e);
+    }
+
+    let mut found_frames = false;
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            continue;
+        }
+        if line == "frames" {
+            found_frames = true;
+            break;
+        }
+
+        let result = all_consuming(property)(line)
+            .and_then(|(_, (name, value))| set_property(&mut hltas.properties, name, value));
+        if let Err(e) = result {
+            report(e);
+        }
+    }
+
+    if !found_frames {
+        let mut error = Error::from_error_kind(&input[input.len()..], nom::error::ErrorKind::Tag);
+        error.add_context(Context::NoFrames);
+        report(nom::Err::Error(error));
+    }
+
+    for l in lines {
+        let content = l.trim_start();
+        if content.trim_end().is_empty() {
+            continue;
+        }
+
+        match context(Context::ErrorParsingLine, all_consuming(line))(content) {
+            Ok((_, line)) => hltas.lines.push(line),
+            Err(e) => report(e),
+        }
+    }
+
+    (hltas, errors)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -10,11 +10,11 @@ use nom::{
 };
 
 use crate::{
-    read::{non_zero_u32, IResult},
+    read::{context, non_zero_u32, Context, IResult},
     types::{Properties, Seeds},
 };
 
-pub(crate) fn property(i: &str) -> IResult<(&str, &str)> {
+pub(crate) fn property(i: &str) -> IResult<'_, (&str, &str)> {
     separated_pair(
         take_while1(|c: char| c.is_alphanum() || c == '_'),
         space1,
@@ -22,40 +22,53 @@ pub(crate) fn property(i: &str) -> IResult<(&str, &str)> {
     )(i)
 }
 
-pub(crate) fn shared_seed(i: &str) -> IResult<u32> {
+pub(crate) fn shared_seed(i: &str) -> IResult<'_, u32> {
     map_res(digit1, u32::from_str)(i)
 }
 
-pub(crate) fn non_shared_seed(i: &str) -> IResult<i64> {
+pub(crate) fn non_shared_seed(i: &str) -> IResult<'_, i64> {
     map_res(recognize(pair(opt(char('-')), digit1)), i64::from_str)(i)
 }
 
-pub(crate) fn seeds(i: &str) -> IResult<Seeds> {
+pub(crate) fn seeds(i: &str) -> IResult<'_, Seeds> {
     map(
         separated_pair(shared_seed, space1, non_shared_seed),
         |(shared, non_shared)| Seeds { shared, non_shared },
     )(i)
 }
 
-fn nl_property(i: &str) -> IResult<(&str, &str)> {
+fn nl_property(i: &str) -> IResult<'_, (&str, &str)> {
     preceded(many1(line_ending), property)(i)
 }
 
-pub(crate) fn properties(mut i: &str) -> IResult<Properties> {
+/// Stores the value of a property into `properties`.
+pub(crate) fn set_property<'a>(
+    properties: &mut Properties,
+    name: &'a str,
+    value: &'a str,
+) -> IResult<'a, ()> {
+    match name {
+        "demo" => properties.demo = Some(value.to_owned()),
+        "save" => properties.save = Some(value.to_owned()),
+        "frametime0ms" => properties.frametime_0ms = Some(value.to_owned()),
+        "seed" => properties.seeds = Some(context(Context::InvalidSeeds, seeds)(value)?.1),
+        "hlstrafe_version" => {
+            properties.hlstrafe_version =
+                Some(context(Context::InvalidHLStrafeVersion, non_zero_u32)(value)?.1)
+        }
+        "load_command" => properties.load_command = Some(value.to_owned()),
+        _ => properties.custom.push((name.to_owned(), value.to_owned())),
+    }
+
+    Ok((value, ()))
+}
+
+pub(crate) fn properties(mut i: &str) -> IResult<'_, Properties> {
     let mut properties = Properties::default();
 
     while let Ok((input, (name, value))) = nl_property(i) {
         i = input;
-
-        match name {
-            "demo" => properties.demo = Some(value.to_owned()),
-            "save" => properties.save = Some(value.to_owned()),
-            "frametime0ms" => properties.frametime_0ms = Some(value.to_owned()),
-            "seed" => properties.seeds = Some(seeds(value)?.1),
-            "hlstrafe_version" => properties.hlstrafe_version = Some(non_zero_u32(value)?.1),
-            "load_command" => properties.load_command = Some(value.to_owned()),
-            _ => continue,
-        }
+        set_property(&mut properties, name, value)?;
     }
 
     Ok((i, properties))
@@ -0,0 +1,342 @@
+//! Streaming reading of `.hltas` scripts.
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead},
+};
+
+use nom::combinator::all_consuming;
+
+use super::{context, line, property, set_property, version, Context, Error, IResult};
+use crate::{
+    diagnostic::Diagnostic,
+    types::{Line, Properties},
+};
+
+/// Error returned by [`Reader`].
+#[derive(Debug)]
+pub enum ReaderError {
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+    /// A line of the script failed to parse.
+    ///
+    /// The line, column and span of the diagnostic refer to the whole input.
+    Parse(Box<Diagnostic>),
+}
+
+/// Streaming reader of `.hltas` scripts.
+///
+/// The header is parsed when the reader is created, and the lines of the frames section are then
+/// parsed one at a time as the reader is iterated over. Only the line that is currently being
+/// parsed is kept in memory, which makes it possible to process scripts that are too big to read
+/// into a `String` or an [`HLTAS`](crate::HLTAS) at once.
+///
+/// A line that fails to parse produces an error and is skipped; iteration continues with the next
+/// line, like with [`hltas_recovering()`](super::hltas_recovering). Iteration stops after an I/O
+/// error.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate hltas;
+/// # fn foo() -> Result<(), hltas::read::ReaderError> {
+/// use hltas::{read::Reader, types::Line};
+///
+/// let contents = "\
+/// version 1
+/// demo test
+/// frames
+/// ------b---|------|------|0.001|-|-|5
+/// ------b---|------|------|0.001|-|-|10";
+///
+/// let mut reader = Reader::new(contents.as_bytes())?;
+/// assert_eq!(reader.properties().demo.as_deref(), Some("test"));
+///
+/// let mut total = 0;
+/// for line in reader {
+///     if let Line::FrameBulk(frame_bulk) = line? {
+///         total += frame_bulk.frame_count.get();
+///     }
+/// }
+/// assert_eq!(total, 15);
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: R,
+    properties: Properties,
+    buf: String,
+    /// Number of the next line to be read, starting from 0.
+    line: usize,
+    /// Byte offset of the next line to be read.
+    offset: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new `Reader`, reading the version and the properties.
+    ///
+    /// After this function returns successfully, the reader is positioned right after the
+    /// `frames` line.
+    pub fn new(reader: R) -> Result<Self, ReaderError> {
+        let mut rv = Self {
+            reader,
+            properties: Properties::default(),
+            buf: String::new(),
+            line: 0,
+            offset: 0,
+            done: false,
+        };
+
+        if !rv.read_line()? {
+            return Err(rv.diagnostic(Context::ErrorReadingVersion));
+        }
+        rv.parse_current(|i| all_consuming(context(Context::ErrorReadingVersion, version))(i))?;
+
+        loop {
+            if !rv.read_line()? {
+                return Err(rv.diagnostic(Context::NoFrames));
+            }
+
+            let current = rv.current();
+            if current.is_empty() {
+                continue;
+            }
+            if current == "frames" {
+                break;
+            }
+
+            let mut properties = std::mem::take(&mut rv.properties);
+            let result = rv.parse_current(|i| {
+                let (_, (name, value)) = all_consuming(property)(i)?;
+                set_property(&mut properties, name, value)
+            });
+            rv.properties = properties;
+            result?;
+        }
+
+        Ok(rv)
+    }
+
+    /// Returns the properties from the header of the script.
+    #[inline]
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Consumes the reader, returning the properties from the header of the script.
+    #[inline]
+    pub fn into_properties(self) -> Properties {
+        self.properties
+    }
+
+    /// Reads the next line into the buffer, returning `false` on EOF.
+    fn read_line(&mut self) -> Result<bool, ReaderError> {
+        self.offset += self.buf.len();
+        if !self.buf.is_empty() {
+            self.line += 1;
+        }
+
+        self.buf.clear();
+        let read = self.reader.read_line(&mut self.buf).map_err(|e| {
+            self.done = true;
+            ReaderError::Io(e)
+        })?;
+        Ok(read > 0)
+    }
+
+    /// Returns the current line without the line ending.
+    fn current(&self) -> &str {
+        let line = self.buf.strip_suffix('\n').unwrap_or(&self.buf);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// Parses the current line, converting the error into a diagnostic for the whole input.
+    fn parse_current<'a, T>(
+        &'a self,
+        mut parser: impl FnMut(&'a str) -> IResult<'a, T>,
+    ) -> Result<T, ReaderError> {
+        let current = self.current();
+
+        match parser(current) {
+            Ok((_, value)) => Ok(value),
+            Err(nom::Err::Error(mut e)) | Err(nom::Err::Failure(mut e)) => {
+                e.whole_input = current;
+                Err(self.to_reader_error(&e))
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!(), // We don't use streaming parsers.
+        }
+    }
+
+    /// Returns an error with the given context at the start of the current line.
+    fn diagnostic(&self, context: Context) -> ReaderError {
+        let current = self.current();
+        let mut error = <Error as nom::error::ParseError<&str>>::from_error_kind(
+            &current[current.len()..],
+            nom::error::ErrorKind::Eof,
+        );
+        error.add_context(context);
+        self.to_reader_error(&error)
+    }
+
+    fn to_reader_error(&self, error: &Error<'_>) -> ReaderError {
+        let mut diagnostic = error.to_diagnostic();
+        diagnostic.line += self.line;
+        diagnostic.span.start += self.offset;
+        diagnostic.span.end += self.offset;
+        if let Some(suggestion) = &mut diagnostic.suggestion {
+            suggestion.span.start += self.offset;
+            suggestion.span.end += self.offset;
+        }
+        ReaderError::Parse(Box::new(diagnostic))
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Line, ReaderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => return Some(Err(e)),
+            }
+
+            if self.done || self.current().trim().is_empty() {
+                continue;
+            }
+
+            return Some(self.parse_current(|i| {
+                context(Context::ErrorParsingLine, all_consuming(line))(i.trim_start())
+            }));
+        }
+
+        None
+    }
+}
+
+impl Display for ReaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReaderError::Io(e) => write!(f, "error reading the script: {}", e),
+            ReaderError::Parse(diagnostic) => diagnostic.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ReaderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReaderError::Io(e) => Some(e),
+            ReaderError::Parse(diagnostic) => Some(diagnostic.as_ref()),
+        }
+    }
+}
+
+impl From<io::Error> for ReaderError {
+    #[inline]
+    fn from(x: io::Error) -> Self {
+        Self::Io(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::{test_util::for_each_test_script, HLTAS};
+
+    fn read_all(input: &str) -> Result<HLTAS, ReaderError> {
+        let mut reader = Reader::new(input.as_bytes())?;
+        let lines = reader.by_ref().collect::<Result<_, _>>()?;
+        Ok(HLTAS {
+            properties: reader.into_properties(),
+            lines,
+        })
+    }
+
+    fn diagnostic(error: ReaderError) -> Diagnostic {
+        match error {
+            ReaderError::Parse(diagnostic) => *diagnostic,
+            ReaderError::Io(e) => panic!("unexpected I/O error: {}", e),
+        }
+    }
+
+    #[test]
+    fn matches_from_str() {
+        for_each_test_script(|path, contents| {
+            let expected = HLTAS::from_str(contents).unwrap();
+            let hltas = read_all(contents).unwrap();
+            assert_eq!(hltas, expected, "{}", path.display());
+
+            let contents = contents.replace('\n', "\r\n");
+            let hltas = read_all(&contents).unwrap();
+            assert_eq!(hltas, expected, "{} with CRLF", path.display());
+        });
+    }
+
+    #[test]
+    fn header_errors() {
+        let d = diagnostic(Reader::new(&b"version 2\nframes\n"[..]).unwrap_err());
+        assert_eq!(d.code, Context::VersionTooHigh.code());
+        assert_eq!((d.line, d.column), (0, 8));
+
+        let d = diagnostic(Reader::new(&b"version 1\ndemo test\nseed x\n"[..]).unwrap_err());
+        assert_eq!(d.code, Context::InvalidSeeds.code());
+        assert_eq!((d.line, d.column), (2, 5));
+        assert_eq!(d.span, 25..26);
+
+        let d = diagnostic(Reader::new(&b"version 1\ndemo test\n"[..]).unwrap_err());
+        assert_eq!(d.code, Context::NoFrames.code());
+
+        let d = diagnostic(Reader::new(&b""[..]).unwrap_err());
+        assert_eq!(d.code, Context::ErrorReadingVersion.code());
+    }
+
+    #[test]
+    fn line_errors_continue() {
+        let contents = read_to_string("test-data/error/multiple-errors.hltas").unwrap();
+        let (_, expected) = HLTAS::from_str_recovering(&contents);
+        // The header error is returned from Reader::new().
+        let contents = contents.replace("seed x\n", "");
+
+        let reader = Reader::new(contents.as_bytes()).unwrap();
+        let errors: Vec<_> = reader
+            .filter_map(Result::err)
+            .map(diagnostic)
+            .map(|d| (d.line + 1, d.code))
+            .collect();
+
+        let expected: Vec<_> = expected[1..]
+            .iter()
+            .map(|e| (e.line(), e.to_diagnostic().code))
+            .collect();
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn io_error_stops_iteration() {
+        struct Failing<'a>(&'a [u8]);
+
+        impl io::Read for Failing<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::other("oops"));
+                }
+                let len = self.0.read(buf)?;
+                Ok(len)
+            }
+        }
+
+        let input = Failing(b"version 1\nframes\n----------|------|------|0.001|-|-|5\n");
+        let mut reader = Reader::new(io::BufReader::new(input)).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next(), Some(Err(ReaderError::Io(_)))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,458 @@
+//! Retiming scripts to a different frame time.
+//!
+//! These functions back [`HLTAS::retime()`], which converts every frame bulk to a target frame
+//! time while keeping the timing of the script in seconds:
+//! - The end time of every frame bulk is rounded to the nearest frame at the target frame time,
+//!   so rounding errors don't build up over the script. Every frame bulk keeps at least one frame.
+//! - Left-right strafing counts are rounded to the nearest number of frames, at least one.
+//! - Override lines are resampled to cover the same time as before, with every new frame taking
+//!   the value of the old frame running at its midpoint.
+//! - `change` lines are timed in seconds already and are left as is.
+//!
+//! Durations are computed exactly from the frame times as written, see [`Time`]. Every frame
+//! bulk, left-right count and override line whose duration changed because of rounding is
+//! reported as a [`Rounding`], in the order of the lines.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{retime::RoundingKind, HLTAS};
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|1000
+//! ----------|------|------|0.001|-|-|10";
+//!
+//! let mut hltas = HLTAS::from_str(contents).unwrap();
+//! let roundings = hltas.retime("0.004").unwrap();
+//!
+//! let mut frame_bulks = hltas.frame_bulks();
+//! assert_eq!(frame_bulks.next().unwrap().frame_count.get(), 250);
+//! assert_eq!(frame_bulks.next().unwrap().frame_count.get(), 3);
+//!
+//! // 10 ms don't fit into whole 4 ms frames.
+//! assert_eq!(roundings.len(), 1);
+//! assert_eq!(roundings[0].line_index, 1);
+//! assert_eq!(roundings[0].kind, RoundingKind::FrameCount);
+//! assert_eq!(roundings[0].retimed.as_millis(), 12);
+//! ```
+//!
+//! [`HLTAS::retime()`]: crate::HLTAS::retime
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    num::NonZeroU32,
+};
+
+use crate::{
+    overrides,
+    timeline::{Time, TimelineError},
+    types::{AutoMovement, Line, StrafeDir},
+    HLTAS,
+};
+
+/// Error returned by [`HLTAS::retime()`](crate::HLTAS::retime).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetimeError {
+    /// The target frame time isn't a positive decimal number.
+    InvalidTargetFrameTime(String),
+    /// A frame bulk has an invalid frame time, or the duration of the script or of the retimed
+    /// script overflows.
+    Timeline(TimelineError),
+    /// A frame bulk would have more frames than a frame count can hold.
+    TooManyFrames {
+        /// Index of the frame bulk in [`HLTAS::lines`].
+        line_index: usize,
+    },
+}
+
+/// A place where rounding to whole frames changed a duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounding {
+    /// Index of the line in [`HLTAS::lines`].
+    pub line_index: usize,
+    /// What was rounded.
+    pub kind: RoundingKind,
+    /// The duration before retiming.
+    pub original: Time,
+    /// The duration after retiming.
+    pub retimed: Time,
+}
+
+/// What was rounded in a [`Rounding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingKind {
+    /// The frame count of a frame bulk.
+    FrameCount,
+    /// The count of left-right or right-left strafing, with the durations of one side.
+    LeftRightCount,
+    /// The number of values of an override line.
+    OverrideValues,
+}
+
+/// Returns `time / frame_time` rounded to the nearest integer, with halves rounded up.
+fn round_div(time: Time, frame_time: Time) -> u128 {
+    let (time, frame_time) = (time.as_attos(), frame_time.as_attos());
+    let remainder = time % frame_time;
+    time / frame_time + u128::from(remainder >= frame_time - remainder)
+}
+
+pub(crate) fn retime(hltas: &mut HLTAS, frame_time: &str) -> Result<Vec<Rounding>, RetimeError> {
+    let target: Time = frame_time
+        .parse()
+        .ok()
+        .filter(|&time| time > Time::ZERO)
+        .ok_or_else(|| RetimeError::InvalidTargetFrameTime(frame_time.to_owned()))?;
+    let timeline = hltas.timeline().map_err(RetimeError::Timeline)?;
+    let overflow = |line_index| RetimeError::Timeline(TimelineError::Overflow { line_index });
+
+    let mut roundings = Vec::new();
+    let mut rounding = |line_index, kind, original, retimed| {
+        if original != retimed {
+            roundings.push(Rounding {
+                line_index,
+                kind,
+                original,
+                retimed,
+            });
+        }
+    };
+
+    // New frame counts and left-right counts of every frame bulk.
+    let mut frame_bulks = Vec::new();
+    let mut last_frame_time = None;
+    let mut frame = 0;
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        let frame_bulk = match line {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => continue,
+        };
+
+        let old_frame_time = timeline.frame_time(line_index).unwrap();
+        let start = timeline.start_time(line_index).unwrap();
+        // Can't overflow, the timeline has checked it.
+        let duration = old_frame_time * u64::from(frame_bulk.frame_count.get());
+
+        let end_frame = round_div(start + duration, target).max(frame + 1);
+        let frame_count = u32::try_from(end_frame - frame)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .ok_or(RetimeError::TooManyFrames { line_index })?;
+        frame = end_frame;
+        let retimed = target
+            .checked_mul(u64::from(frame_count.get()))
+            .ok_or_else(|| overflow(line_index))?;
+        rounding(line_index, RoundingKind::FrameCount, duration, retimed);
+
+        let left_right_count = match frame_bulk.auto_actions.movement {
+            Some(AutoMovement::Strafe(settings)) => match settings.dir {
+                StrafeDir::LeftRight(count) | StrafeDir::RightLeft(count) => {
+                    let side = old_frame_time
+                        .checked_mul(u64::from(count.get()))
+                        .ok_or_else(|| overflow(line_index))?;
+                    let count = u32::try_from(round_div(side, target).max(1))
+                        .ok()
+                        .and_then(NonZeroU32::new)
+                        .ok_or(RetimeError::TooManyFrames { line_index })?;
+                    let retimed = target
+                        .checked_mul(u64::from(count.get()))
+                        .ok_or_else(|| overflow(line_index))?;
+                    rounding(line_index, RoundingKind::LeftRightCount, side, retimed);
+                    Some(count)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        frame_bulks.push((line_index, frame_count, left_right_count));
+        last_frame_time = Some(old_frame_time);
+    }
+
+    // Values past the end of the script are assumed to last the frame time of the last frame
+    // bulk.
+    let total_frames = timeline.total_frames();
+    let time_at_frame = |frame: u64| match timeline.time_at_frame(frame) {
+        Some(time) => Some(time),
+        None => last_frame_time
+            .unwrap_or(target)
+            .checked_mul(frame - total_frames)
+            .and_then(|time| time.checked_add(timeline.total_time())),
+    };
+
+    // Resampled values of every override line.
+    let mut overrides = Vec::new();
+    for application in overrides::analyze(hltas).applications {
+        let values = application.values;
+        if values.is_empty() {
+            continue;
+        }
+
+        // Start times of the frames the values apply to, and the end time of the last one.
+        let line_index = application.line_index;
+        let boundaries: Vec<Time> = (0..=values.len() as u64)
+            .map(|offset| time_at_frame(application.start_frame + offset))
+            .collect::<Option<_>>()
+            .ok_or_else(|| overflow(line_index))?;
+        let start = boundaries[0];
+        let duration = boundaries[values.len()] - start;
+
+        let count = round_div(duration, target).max(1) as usize;
+        let retimed = target
+            .checked_mul(count as u64)
+            .ok_or_else(|| overflow(line_index))?;
+        rounding(line_index, RoundingKind::OverrideValues, duration, retimed);
+
+        let resampled = (0..count as u64)
+            .map(|frame| {
+                // The midpoint of the new frame relative to `start`, rounded down. An old frame
+                // ending at or before it in whole attoseconds ends at or before the exact midpoint.
+                let midpoint = target
+                    .checked_mul(frame)?
+                    .checked_add(Time::from_attos(target.as_attos() / 2))?;
+                let index =
+                    boundaries[1..values.len()].partition_point(|&end| end - start <= midpoint);
+                Some(values[index])
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| overflow(line_index))?;
+        overrides.push((application.line_index, resampled));
+    }
+
+    for (line_index, frame_count, left_right_count) in frame_bulks {
+        if let Line::FrameBulk(frame_bulk) = &mut hltas.lines[line_index] {
+            frame_bulk.frame_time = frame_time.to_owned();
+            frame_bulk.frame_count = frame_count;
+
+            if let (Some(count), Some(AutoMovement::Strafe(settings))) =
+                (left_right_count, &mut frame_bulk.auto_actions.movement)
+            {
+                match &mut settings.dir {
+                    StrafeDir::LeftRight(x) | StrafeDir::RightLeft(x) => *x = count,
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    for (line_index, resampled) in overrides {
+        match &mut hltas.lines[line_index] {
+            Line::TargetYawOverride(values)
+            | Line::RenderYawOverride(values)
+            | Line::PitchOverride(values)
+            | Line::RenderPitchOverride(values) => *values = resampled,
+            _ => unreachable!(),
+        }
+    }
+
+    roundings.sort_by_key(|rounding| rounding.line_index);
+    Ok(roundings)
+}
+
+impl Display for RetimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RetimeError::InvalidTargetFrameTime(frame_time) => {
+                write!(f, "invalid target frame time \"{}\"", frame_time)
+            }
+            RetimeError::Timeline(error) => error.fmt(f),
+            RetimeError::TooManyFrames { line_index } => write!(
+                f,
+                "the frame bulk in line {} would have too many frames",
+                line_index
+            ),
+        }
+    }
+}
+
+impl error::Error for RetimeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RetimeError::Timeline(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{frame_counts, parse_frames};
+
+    #[test]
+    fn exact() {
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|1000\n\
+             ----------|------|------|0.002|-|-|500\n\
+             s06-------|------|------|0.001|8|-|8",
+        );
+        let roundings = hltas.retime("0.004").unwrap();
+
+        assert!(roundings.is_empty());
+        assert_eq!(frame_counts(&hltas), [250, 250, 2]);
+        assert!(hltas.frame_bulks().all(|x| x.frame_time == "0.004"));
+    }
+
+    #[test]
+    fn rounding_does_not_accumulate() {
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|3\n\
+             ----------|------|------|0.001|-|-|3\n\
+             ----------|------|------|0.001|-|-|3\n\
+             ----------|------|------|0.001|-|-|3",
+        );
+        let roundings = hltas.retime("0.004").unwrap();
+
+        // Ends at 3, 6, 9 and 12 ms round to frames 1, 2, 2 and 3, but every frame bulk needs a
+        // frame.
+        assert_eq!(frame_counts(&hltas), [1, 1, 1, 1]);
+        assert_eq!(roundings.len(), 4);
+        assert_eq!(roundings[0].original, Time::from_millis(3));
+        assert_eq!(roundings[0].retimed, Time::from_millis(4));
+
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|6\n\
+             ----------|------|------|0.001|-|-|6\n\
+             ----------|------|------|0.001|-|-|6\n\
+             ----------|------|------|0.001|-|-|6",
+        );
+        hltas.retime("0.004").unwrap();
+        assert_eq!(frame_counts(&hltas), [2, 1, 2, 1]);
+        assert_eq!(
+            hltas.timeline().unwrap().total_time(),
+            Time::from_millis(24)
+        );
+    }
+
+    #[test]
+    fn inexact_target() {
+        let mut hltas = parse_frames("----------|------|------|0.001|-|-|1000");
+        let roundings = hltas.retime("0.010000001").unwrap();
+
+        assert_eq!(frame_counts(&hltas), [100]);
+        assert_eq!(
+            roundings,
+            [Rounding {
+                line_index: 0,
+                kind: RoundingKind::FrameCount,
+                original: "1".parse().unwrap(),
+                retimed: "1.0000001".parse().unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn left_right() {
+        let mut hltas = parse_frames("s07-------|------|------|0.001|5|-|8");
+        let roundings = hltas.retime("0.004").unwrap();
+
+        let frame_bulk = hltas.frame_bulks().next().unwrap();
+        assert!(matches!(
+            frame_bulk.auto_actions.movement,
+            Some(AutoMovement::Strafe(s)) if s.dir == StrafeDir::RightLeft(NonZeroU32::new(1).unwrap())
+        ));
+        assert_eq!(
+            roundings,
+            [Rounding {
+                line_index: 0,
+                kind: RoundingKind::LeftRightCount,
+                original: Time::from_millis(5),
+                retimed: Time::from_millis(4),
+            }]
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let mut hltas = parse_frames(
+            "----------|------|------|0.001|-|-|2\n\
+             pitch_override 0 1 2 3 4 5 6 7\n\
+             target_yaw_override 1 2 3\n\
+             ----------|------|------|0.001|-|-|10",
+        );
+        let roundings = hltas.retime("0.004").unwrap();
+
+        assert_eq!(hltas.lines[1], Line::PitchOverride(vec![2., 6.]));
+        assert_eq!(hltas.lines[2], Line::TargetYawOverride(vec![3.]));
+        assert_eq!(frame_counts(&hltas), [1, 2]);
+        assert_eq!(
+            roundings
+                .iter()
+                .map(|x| (x.line_index, x.kind))
+                .collect::<Vec<_>>(),
+            [
+                (0, RoundingKind::FrameCount),
+                (2, RoundingKind::OverrideValues),
+                (3, RoundingKind::FrameCount),
+            ]
+        );
+    }
+
+    #[test]
+    fn overrides_past_the_end() {
+        let mut hltas = parse_frames(
+            "pitch_override 1 2 3 4 5 6\n\
+             ----------|------|------|0.002|-|-|1",
+        );
+        hltas.retime("0.004").unwrap();
+        assert_eq!(hltas.lines[0], Line::PitchOverride(vec![2., 4., 6.]));
+
+        let mut hltas = parse_frames("pitch_override 1 2 3 4 5 6");
+        hltas.retime("0.004").unwrap();
+        assert_eq!(
+            hltas.lines[0],
+            Line::PitchOverride(vec![1., 2., 3., 4., 5., 6.])
+        );
+    }
+
+    #[test]
+    fn changes_are_kept() {
+        let contents = "\
+            change yaw to 90 over 1 s\n\
+            ----------|------|------|0.001|-|-|1000";
+        let mut hltas = parse_frames(contents);
+        hltas.retime("0.01").unwrap();
+        assert_eq!(hltas.lines[0], parse_frames(contents).lines[0]);
+    }
+
+    #[test]
+    fn errors() {
+        let mut hltas = parse_frames("----------|------|------|0.001|-|-|10");
+        let original = hltas.clone();
+
+        for frame_time in ["0", "-0.001", "abc", ""] {
+            assert_eq!(
+                hltas.retime(frame_time),
+                Err(RetimeError::InvalidTargetFrameTime(frame_time.to_owned()))
+            );
+        }
+        assert_eq!(
+            hltas.retime("0.000000000000000001"),
+            Err(RetimeError::TooManyFrames { line_index: 0 })
+        );
+        assert_eq!(hltas, original);
+
+        if let Line::FrameBulk(frame_bulk) = &mut hltas.lines[0] {
+            frame_bulk.frame_time = "fast".to_owned();
+        }
+        assert!(matches!(
+            hltas.retime("0.001"),
+            Err(RetimeError::Timeline(TimelineError::InvalidFrameTime {
+                line_index: 0,
+                ..
+            }))
+        ));
+
+        let overflow = Err(RetimeError::Timeline(TimelineError::Overflow {
+            line_index: 0,
+        }));
+        let mut hltas = parse_frames("----------|------|------|100000000000000000000|-|-|10");
+        assert_eq!(hltas.retime("0.001"), overflow);
+        let mut hltas = parse_frames("----------|------|------|100000000000000000000|-|-|3");
+        assert_eq!(hltas.retime("200000000000000000000"), overflow);
+        assert!(hltas.retime("300000000000000000000").is_ok());
+    }
+}
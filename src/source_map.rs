@@ -0,0 +1,255 @@
+//! Mapping between [`HLTAS::lines`] and locations in the text of a script.
+//!
+//! Blank lines and the header shift the numbering, so the index of a [`Line`] in
+//! [`HLTAS::lines`] generally differs from the line of the file it came from. [`SourceMap`] stores
+//! the location of every [`Line`], so tools like editors and linters can point at the right place.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::HLTAS;
+//!
+//! let contents = "\
+//! version 1
+//! demo test
+//! frames
+//! ------b---|------|------|0.001|-|-|5
+//!
+//! reset 1234";
+//!
+//! let (hltas, source_map) = HLTAS::from_str_with_source_map(contents).unwrap();
+//!
+//! let location = source_map.get(1).unwrap();
+//! assert_eq!(location.line, 5);
+//! assert_eq!(&contents[location.span.clone()], "reset 1234");
+//!
+//! assert_eq!(source_map.index_at_line(3), Some(0));
+//! assert_eq!(source_map.index_at_line(4), None);
+//! ```
+//!
+//! [`HLTAS::lines`]: crate::HLTAS::lines
+//! [`HLTAS`]: crate::HLTAS
+//! [`Line`]: crate::types::Line
+
+use std::{io::Write, ops::Range, slice};
+
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+/// Location of a [`Line`](crate::types::Line) in the text of a script.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Location {
+    /// Line number in the text, starting from 0.
+    pub line: usize,
+    /// Column of the start of `span`, in bytes from the start of the line, starting from 0.
+    pub column: usize,
+    /// Byte range of the line in the text, without surrounding whitespace and the line ending.
+    pub span: Range<usize>,
+}
+
+/// Locations of every element of [`HLTAS::lines`](crate::HLTAS::lines), in order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct SourceMap {
+    locations: Vec<Location>,
+}
+
+impl SourceMap {
+    /// Creates a source map from the text of a script.
+    ///
+    /// `input` must be a script that was successfully parsed with [`HLTAS::from_str`] or
+    /// [`HLTAS::from_str_lenient`]; every non-blank line after `frames` is then exactly one
+    /// element of [`HLTAS::lines`].
+    ///
+    /// [`HLTAS::from_str`]: crate::HLTAS::from_str
+    /// [`HLTAS::from_str_lenient`]: crate::HLTAS::from_str_lenient
+    /// [`HLTAS::lines`]: crate::HLTAS::lines
+    pub fn from_input(input: &str) -> Self {
+        let mut locations = Vec::new();
+
+        let mut offset = 0;
+        let mut in_frames = false;
+        for (number, text) in input.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += text.len();
+
+            let content = text.trim_end_matches(['\n', '\r']);
+            if !in_frames {
+                in_frames = content == "frames";
+                continue;
+            }
+
+            let trimmed = content.trim_start();
+            let column = content.len() - trimmed.len();
+            let start = start + column;
+            let trimmed = trimmed.trim_end();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            locations.push(Location {
+                line: number,
+                column,
+                span: start..start + trimmed.len(),
+            });
+        }
+
+        Self { locations }
+    }
+
+    /// Returns the location of the element of [`HLTAS::lines`](crate::HLTAS::lines) at `index`.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Location> {
+        self.locations.get(index)
+    }
+
+    /// Returns the index of the element of [`HLTAS::lines`](crate::HLTAS::lines) on the given
+    /// line of the text.
+    ///
+    /// Returns `None` if the line is blank, in the header, or out of bounds.
+    pub fn index_at_line(&self, line: usize) -> Option<usize> {
+        self.locations
+            .binary_search_by_key(&line, |location| location.line)
+            .ok()
+    }
+
+    /// Returns the index of the element of [`HLTAS::lines`](crate::HLTAS::lines) whose span
+    /// contains the given byte offset of the text.
+    ///
+    /// The end of the span is included, so the offset right after a line still maps to it.
+    pub fn index_at_offset(&self, offset: usize) -> Option<usize> {
+        let index = self
+            .locations
+            .partition_point(|location| location.span.start <= offset)
+            .checked_sub(1)?;
+
+        if offset <= self.locations[index].span.end {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of locations, which is the number of lines in the script.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns `true` if the source map has no locations.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Returns an iterator over the locations, in the order of the lines.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, Location> {
+        self.locations.iter()
+    }
+
+    pub(crate) fn push(&mut self, location: Location) {
+        self.locations.push(location);
+    }
+}
+
+impl<'a> IntoIterator for &'a SourceMap {
+    type Item = &'a Location;
+    type IntoIter = slice::Iter<'a, Location>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Writer which keeps track of the number of bytes and lines written.
+pub(crate) struct Counting<W> {
+    pub(crate) inner: W,
+    pub(crate) bytes: usize,
+    pub(crate) lines: usize,
+}
+
+impl<W: Write> Counting<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            lines: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written;
+        self.lines += buf[..written].iter().filter(|&&b| b == b'\n').count();
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::for_each_test_script, HLTAS};
+
+    #[test]
+    fn parse_maps_to_lines() {
+        for_each_test_script(|path, contents| {
+            let (hltas, source_map) = HLTAS::from_str_with_source_map(contents).unwrap();
+            assert_eq!(source_map.len(), hltas.lines.len(), "{}", path.display());
+
+            for (index, (line, location)) in hltas.lines.iter().zip(&source_map).enumerate() {
+                let text = &contents[location.span.clone()];
+                assert_eq!(contents.lines().nth(location.line).unwrap().trim(), text);
+                let line_start = location.span.start - location.column;
+                assert!(line_start == 0 || contents.as_bytes()[line_start - 1] == b'\n');
+                assert_eq!(crate::read::line(text).unwrap().1, *line);
+                assert_eq!(source_map.index_at_line(location.line), Some(index));
+                assert_eq!(source_map.index_at_offset(location.span.start), Some(index));
+            }
+        });
+    }
+
+    #[test]
+    fn write_maps_to_lines() {
+        for_each_test_script(|path, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+
+            let mut buf = Vec::new();
+            let source_map = hltas.to_writer_with_source_map(&mut buf).unwrap();
+            let written = String::from_utf8(buf).unwrap();
+
+            assert_eq!(
+                source_map,
+                SourceMap::from_input(&written),
+                "{}",
+                path.display()
+            );
+        });
+    }
+
+    #[test]
+    fn indentation_and_crlf() {
+        let contents = "version 1\r\nframes\r\n\r\n  reset 1\r\n\treset 2\r\n";
+        let (_, source_map) = HLTAS::from_str_with_source_map(contents).unwrap();
+
+        let spans: Vec<_> = source_map
+            .iter()
+            .map(|l| (l.line, l.column, l.span.clone()))
+            .collect();
+        assert_eq!(spans, [(3, 2, 23..30), (4, 1, 33..40)]);
+
+        assert_eq!(source_map.index_at_offset(22), None);
+        assert_eq!(source_map.index_at_offset(30), Some(0));
+        assert_eq!(source_map.index_at_offset(35), Some(1));
+        assert_eq!(source_map.index_at_offset(100), None);
+    }
+}
@@ -0,0 +1,915 @@
+//! Lossless syntax tree of `.hltas` scripts.
+//!
+//! [`HLTAS`] only stores the meaning of a script: blank lines, indentation, line endings and the
+//! exact spelling of numbers are lost during parsing, and writing it back produces the canonical
+//! form. [`Document`] keeps the original text of every line alongside the typed view, so a script
+//! can be edited and written back with all untouched regions coming out byte-for-byte identical.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::syntax::Document;
+//!
+//! let contents = "version 1\r\n\
+//!                 frames\r\n\
+//!                 ------b---|------|------|0.0010|-|-|5\r\n\
+//!                 \r\n\
+//!                 //  a comment\r\n";
+//!
+//! let mut document = Document::parse(contents).unwrap();
+//! assert_eq!(document.to_string(), contents);
+//!
+//! // Edit the typed view and apply the changes back.
+//! let mut hltas = document.hltas().clone();
+//! hltas.lines[0].frame_bulk_mut().unwrap().frame_count = 10.try_into().unwrap();
+//! document.apply(&hltas);
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "version 1\r\n\
+//!      frames\r\n\
+//!      ------b---|------|------|0.0010|-|-|10\r\n\
+//!      \r\n\
+//!      //  a comment\r\n"
+//! );
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+};
+
+use crate::{
+    read,
+    types::{Line, Properties, Seeds, HLTAS},
+    write,
+};
+
+/// A `.hltas` script with all of its formatting preserved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    lines: Vec<SyntaxLine>,
+    hltas: HLTAS,
+}
+
+/// A physical line of a `.hltas` script.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SyntaxLine {
+    kind: LineKind,
+    text: String,
+    ending: LineEnding,
+}
+
+/// What a [`SyntaxLine`] contains.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineKind {
+    /// The `version` line.
+    Version,
+    /// A property in the header.
+    Property,
+    /// The `frames` line separating the header from the frames section.
+    Frames,
+    /// An empty or whitespace-only line.
+    Blank,
+    /// A [`Line`] in the frames section.
+    Line,
+}
+
+/// The line terminator of a [`SyntaxLine`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineEnding {
+    /// The last line of a script that doesn't end with a newline.
+    None,
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+/// A piece of text of a [`SyntaxLine`].
+///
+/// Concatenating the text of all tokens of a line gives back the line text exactly.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Token<'a> {
+    /// The kind of the token.
+    pub kind: TokenKind,
+    /// The text of the token.
+    pub text: &'a str,
+    /// Byte offset of the token from the start of the line.
+    pub offset: usize,
+}
+
+/// Kinds of [`Token`]s.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TokenKind {
+    /// Spaces and tabs.
+    Whitespace,
+    /// A run of non-whitespace characters, such as a keyword or a number.
+    Word,
+    /// The `|` separating frame bulk fields.
+    Pipe,
+    /// A frame bulk field with its index, starting from `0` for the auto actions field.
+    ///
+    /// The console command is field `7` and is never split further.
+    Field(usize),
+    /// The `//` starting a comment.
+    CommentMarker,
+    /// Text of a comment after the `//`.
+    Comment,
+}
+
+/// Names of the known properties in the order [`write::gen_hltas`] outputs them.
+const PROPERTY_NAMES: [&str; 6] = [
+    "demo",
+    "save",
+    "seed",
+    "frametime0ms",
+    "hlstrafe_version",
+    "load_command",
+];
+
+/// Number of fields of a frame bulk, including the console command.
+const FRAME_BULK_FIELDS: usize = 8;
+
+impl Document {
+    /// Parses a `.hltas` script, keeping its formatting.
+    ///
+    /// The script must be valid as accepted by [`HLTAS::from_str`].
+    pub fn parse(input: &str) -> Result<Self, read::Error<'_>> {
+        let hltas = HLTAS::from_str(input)?;
+        Ok(Self::with_hltas(input, hltas))
+    }
+
+    /// Parses a `.hltas` script in the lenient mode, keeping its formatting.
+    ///
+    /// Unrecognized lines are kept as [`Line::Unknown`], see [`HLTAS::from_str_lenient`].
+    pub fn parse_lenient(input: &str) -> Result<Self, read::Error<'_>> {
+        let (hltas, _) = HLTAS::from_str_lenient(input)?;
+        Ok(Self::with_hltas(input, hltas))
+    }
+
+    fn with_hltas(input: &str, hltas: HLTAS) -> Self {
+        let mut lines = Vec::new();
+        let mut in_frames_section = false;
+        for (i, raw) in input.split_inclusive('\n').enumerate() {
+            let (text, ending) = if let Some(text) = raw.strip_suffix("\r\n") {
+                (text, LineEnding::CrLf)
+            } else if let Some(text) = raw.strip_suffix('\n') {
+                (text, LineEnding::Lf)
+            } else {
+                (raw, LineEnding::None)
+            };
+
+            let kind = if i == 0 {
+                LineKind::Version
+            } else if is_blank(text) {
+                LineKind::Blank
+            } else if in_frames_section {
+                LineKind::Line
+            } else if text == "frames" {
+                in_frames_section = true;
+                LineKind::Frames
+            } else {
+                LineKind::Property
+            };
+
+            lines.push(SyntaxLine {
+                kind,
+                text: text.to_owned(),
+                ending,
+            });
+        }
+
+        debug_assert_eq!(
+            lines.iter().filter(|l| l.kind == LineKind::Line).count(),
+            hltas.lines.len()
+        );
+
+        Self { lines, hltas }
+    }
+
+    /// Returns the typed view of the script.
+    #[inline]
+    pub fn hltas(&self) -> &HLTAS {
+        &self.hltas
+    }
+
+    /// Returns the physical lines of the script.
+    #[inline]
+    pub fn lines(&self) -> &[SyntaxLine] {
+        &self.lines
+    }
+
+    /// Returns the index into [`Document::lines()`] of the physical line holding
+    /// `self.hltas().lines[index]`.
+    pub fn syntax_line_index(&self, index: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.kind == LineKind::Line)
+            .nth(index)
+            .map(|(i, _)| i)
+    }
+
+    /// Outputs the script.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for line in &self.lines {
+            writer.write_all(line.text.as_bytes())?;
+            writer.write_all(line.ending.as_str().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the line at `index` in the frames section.
+    ///
+    /// The indentation and the line ending are kept. When both the old and the new line are frame
+    /// bulks, the original text of every field that didn't change is kept too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_line(&mut self, index: usize, line: Line) {
+        let syntax_index = self.syntax_line_index(index).expect("index out of bounds");
+        let old = &self.hltas.lines[index];
+        if *old != line {
+            let text = &mut self.lines[syntax_index].text;
+            *text = reformat_line(text, old, &line);
+            self.hltas.lines[index] = line;
+        }
+    }
+
+    /// Inserts a line at `index` in the frames section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > self.hltas().lines.len()`.
+    pub fn insert_line(&mut self, index: usize, line: Line) {
+        assert!(index <= self.hltas.lines.len(), "index out of bounds");
+
+        // Insert right after the previous line to keep blank lines attached to the lines below.
+        let syntax_index = match index.checked_sub(1) {
+            Some(prev) => self.syntax_line_index(prev).unwrap() + 1,
+            None => self.frames_line_index() + 1,
+        };
+
+        let text = line_text(&line);
+        self.insert_syntax_line(syntax_index, LineKind::Line, text);
+        self.hltas.lines.insert(index, line);
+    }
+
+    /// Appends a line to the end of the frames section.
+    pub fn push_line(&mut self, line: Line) {
+        self.insert_line(self.hltas.lines.len(), line);
+    }
+
+    /// Removes the line at `index` from the frames section and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_line(&mut self, index: usize) -> Line {
+        let syntax_index = self.syntax_line_index(index).expect("index out of bounds");
+        self.remove_syntax_line(syntax_index);
+        self.hltas.lines.remove(index)
+    }
+
+    /// Sets the header properties.
+    ///
+    /// Lines of unchanged properties are left as is, removed properties have their lines removed
+    /// and new properties are added at the end of the header.
+    pub fn set_properties(&mut self, properties: Properties) {
+        for name in PROPERTY_NAMES {
+            let old = property_value(&self.hltas.properties, name);
+            let new = property_value(&properties, name);
+            if old == new {
+                continue;
+            }
+
+            let mut existing: Vec<_> = self
+                .lines
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| l.kind == LineKind::Property && property_name(&l.text) == name)
+                .map(|(i, _)| i)
+                .collect();
+
+            // The last occurrence of the property is the one that takes effect, so replace it and
+            // remove the earlier ones, which would otherwise come back into effect.
+            let last = match new {
+                Some(_) => existing.pop(),
+                None => None,
+            };
+
+            for &i in existing.iter().rev() {
+                self.remove_syntax_line(i);
+            }
+
+            let text = match new {
+                Some(value) => format!("{} {}", name, value),
+                None => continue,
+            };
+
+            match last {
+                Some(i) => self.lines[i - existing.len()].text = text,
+                None => {
+                    let i = self.frames_line_index();
+                    self.insert_syntax_line(i, LineKind::Property, text);
+                }
+            }
+        }
+
+        if self.hltas.properties.custom != properties.custom {
+            self.set_custom_properties(&properties.custom);
+        }
+
+        self.hltas.properties = properties;
+    }
+
+    /// Updates the document to match `hltas`, touching as little text as possible.
+    ///
+    /// Lines that are present in both the document and `hltas` keep their original text, including
+    /// the surrounding blank lines. Changed lines are reformatted in place with
+    /// [`Document::set_line()`], and the rest are inserted or removed.
+    pub fn apply(&mut self, hltas: &HLTAS) {
+        self.set_properties(hltas.properties.clone());
+
+        let old = &self.hltas.lines;
+        let new = &hltas.lines;
+
+        // Skip the common prefix and suffix to keep the alignment cheap for local edits.
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let ops = align(
+            &old[prefix..old.len() - suffix],
+            &new[prefix..new.len() - suffix],
+        );
+
+        // Apply the edits front to back, tracking the current position in the new lines.
+        let mut index = prefix;
+        let mut new_index = prefix;
+        for op in ops {
+            match op {
+                Op::Keep => {
+                    index += 1;
+                    new_index += 1;
+                }
+                Op::Replace => {
+                    self.set_line(index, new[new_index].clone());
+                    index += 1;
+                    new_index += 1;
+                }
+                Op::Insert => {
+                    self.insert_line(index, new[new_index].clone());
+                    index += 1;
+                    new_index += 1;
+                }
+                Op::Remove => {
+                    self.remove_line(index);
+                }
+            }
+        }
+
+        debug_assert_eq!(self.hltas.lines, hltas.lines);
+    }
+
+    /// Rewrites the custom property lines in order, adding or removing lines as needed.
+    fn set_custom_properties(&mut self, custom: &[(String, String)]) {
+        let existing: Vec<_> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| {
+                l.kind == LineKind::Property && !PROPERTY_NAMES.contains(&property_name(&l.text))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let old = &self.hltas.properties.custom;
+
+        for (k, &i) in existing.iter().enumerate().take(custom.len()) {
+            if old.get(k) != custom.get(k) {
+                let (name, value) = &custom[k];
+                self.lines[i].text = format!("{} {}", name, value);
+            }
+        }
+
+        for &i in existing.iter().skip(custom.len()).rev() {
+            self.remove_syntax_line(i);
+        }
+
+        for (name, value) in custom.iter().skip(existing.len()) {
+            let i = self.frames_line_index();
+            self.insert_syntax_line(i, LineKind::Property, format!("{} {}", name, value));
+        }
+    }
+
+    fn frames_line_index(&self) -> usize {
+        self.lines
+            .iter()
+            .position(|l| l.kind == LineKind::Frames)
+            .unwrap()
+    }
+
+    /// Returns the line ending used by the document, defaulting to `\n`.
+    fn line_ending(&self) -> LineEnding {
+        self.lines
+            .first()
+            .map(|l| l.ending)
+            .filter(|&e| e != LineEnding::None)
+            .unwrap_or(LineEnding::Lf)
+    }
+
+    fn insert_syntax_line(&mut self, index: usize, kind: LineKind, text: String) {
+        let mut ending = self.line_ending();
+
+        // Inserting past the unterminated last line: it needs a newline now, and the new line
+        // becomes the unterminated one instead.
+        if index == self.lines.len() {
+            let last = self.lines.last_mut().unwrap();
+            if last.ending == LineEnding::None {
+                last.ending = ending;
+                ending = LineEnding::None;
+            }
+        }
+
+        self.lines.insert(index, SyntaxLine { kind, text, ending });
+    }
+
+    fn remove_syntax_line(&mut self, index: usize) {
+        let removed = self.lines.remove(index);
+
+        // Keep the script unterminated if it was.
+        if index == self.lines.len() && removed.ending == LineEnding::None {
+            self.lines.last_mut().unwrap().ending = LineEnding::None;
+        }
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            line.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl SyntaxLine {
+    /// Returns what this line contains.
+    #[inline]
+    pub fn kind(&self) -> LineKind {
+        self.kind
+    }
+
+    /// Returns the text of the line without the line ending.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the line ending.
+    #[inline]
+    pub fn ending(&self) -> LineEnding {
+        self.ending
+    }
+
+    /// Splits the line into tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::syntax::{Document, TokenKind};
+    ///
+    /// let document = Document::parse("version 1\nframes\n  seed 5").unwrap();
+    /// let line = &document.lines()[2];
+    /// let kinds: Vec<_> = line.tokens().into_iter().map(|t| t.kind).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         TokenKind::Whitespace,
+    ///         TokenKind::Word,
+    ///         TokenKind::Whitespace,
+    ///         TokenKind::Word
+    ///     ]
+    /// );
+    /// ```
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        let text = self.text.as_str();
+        let mut tokens = Vec::new();
+
+        let content_start = text.len() - text.trim_start().len();
+        if content_start > 0 {
+            tokens.push(Token {
+                kind: TokenKind::Whitespace,
+                text: &text[..content_start],
+                offset: 0,
+            });
+        }
+        let content = &text[content_start..];
+
+        if self.kind == LineKind::Line && content.starts_with("//") {
+            tokens.push(Token {
+                kind: TokenKind::CommentMarker,
+                text: "//",
+                offset: content_start,
+            });
+            if content.len() > 2 {
+                tokens.push(Token {
+                    kind: TokenKind::Comment,
+                    text: &content[2..],
+                    offset: content_start + 2,
+                });
+            }
+        } else if self.kind == LineKind::Line && content.contains('|') {
+            let mut offset = content_start;
+            for (field, text) in content.splitn(FRAME_BULK_FIELDS, '|').enumerate() {
+                if field > 0 {
+                    tokens.push(Token {
+                        kind: TokenKind::Pipe,
+                        text: "|",
+                        offset,
+                    });
+                    offset += 1;
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Field(field),
+                    text,
+                    offset,
+                });
+                offset += text.len();
+            }
+        } else {
+            let mut offset = content_start;
+            let mut rest = content;
+            while let Some(c) = rest.chars().next() {
+                let is_space = is_space(c);
+                let len = rest
+                    .find(|c| is_space != self::is_space(c))
+                    .unwrap_or(rest.len());
+                tokens.push(Token {
+                    kind: if is_space {
+                        TokenKind::Whitespace
+                    } else {
+                        TokenKind::Word
+                    },
+                    text: &rest[..len],
+                    offset,
+                });
+                offset += len;
+                rest = &rest[len..];
+            }
+        }
+
+        tokens
+    }
+}
+
+impl Display for SyntaxLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)?;
+        f.write_str(self.ending.as_str())
+    }
+}
+
+impl LineEnding {
+    /// Returns the text of the line ending.
+    #[inline]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::None => "",
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_whitespace())
+}
+
+fn property_name(text: &str) -> &str {
+    text.split(is_space).next().unwrap()
+}
+
+/// Returns the canonical text of a known property's value.
+fn property_value(properties: &Properties, name: &str) -> Option<String> {
+    match name {
+        "demo" => properties.demo.clone(),
+        "save" => properties.save.clone(),
+        "seed" => properties
+            .seeds
+            .map(|Seeds { shared, non_shared }| format!("{} {}", shared, non_shared)),
+        "frametime0ms" => properties.frametime_0ms.clone(),
+        "hlstrafe_version" => properties.hlstrafe_version.map(|v| v.to_string()),
+        "load_command" => properties.load_command.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the canonical text of a line.
+fn line_text(line: &Line) -> String {
+    let mut buf = Vec::new();
+    write::gen_line(&mut buf, line).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Returns the new text for a line that changed from `old` to `new`, keeping as much of the
+/// original `text` as possible.
+fn reformat_line(text: &str, old: &Line, new: &Line) -> String {
+    let indent_len = text.len() - text.trim_start().len();
+    let (indent, content) = text.split_at(indent_len);
+
+    let content = match (old, new) {
+        (Line::FrameBulk(_), Line::FrameBulk(_)) => {
+            let old_canonical = line_text(old);
+            let new_canonical = line_text(new);
+
+            let original: Vec<_> = content.splitn(FRAME_BULK_FIELDS, '|').collect();
+            let old_canonical: Vec<_> = old_canonical.splitn(FRAME_BULK_FIELDS, '|').collect();
+
+            // A field with the same canonical text means the same thing, so its original spelling
+            // can stay.
+            let fields: Vec<_> = new_canonical
+                .splitn(FRAME_BULK_FIELDS, '|')
+                .enumerate()
+                .map(|(i, field)| match (old_canonical.get(i), original.get(i)) {
+                    (Some(&old_field), Some(&original)) if old_field == field => original,
+                    _ => field,
+                })
+                .collect();
+
+            fields.join("|")
+        }
+        _ => line_text(new),
+    };
+
+    format!("{}{}", indent, content)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Op {
+    Keep,
+    Replace,
+    Insert,
+    Remove,
+}
+
+/// Aligns `old` with `new` using their longest common subsequence.
+///
+/// Runs of removals followed by insertions are turned into replacements so that changed lines keep
+/// their formatting.
+fn align(old: &[Line], new: &[Line]) -> Vec<Op> {
+    // Quadratic alignment is too slow for huge edits; fall back to pairing the lines by position.
+    const MAX_CELLS: usize = 4_000_000;
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        let common = old.len().min(new.len());
+        let mut ops = vec![Op::Replace; common];
+        ops.extend(std::iter::repeat_n(Op::Remove, old.len() - common));
+        ops.extend(std::iter::repeat_n(Op::Insert, new.len() - common));
+        return ops;
+    }
+
+    // lcs[i][j] is the LCS length of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut raw = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            raw.push(Op::Keep);
+            i += 1;
+            j += 1;
+        } else if j < new.len()
+            && (i == old.len() || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j])
+        {
+            raw.push(Op::Insert);
+            j += 1;
+        } else {
+            raw.push(Op::Remove);
+            i += 1;
+        }
+    }
+
+    // Pair up removals and insertions between kept lines.
+    let mut ops = Vec::with_capacity(raw.len());
+    for chunk in raw.split_inclusive(|&op| op == Op::Keep) {
+        let (edits, keep) = match chunk.split_last() {
+            Some((&Op::Keep, edits)) => (edits, true),
+            _ => (chunk, false),
+        };
+
+        let removed = edits.iter().filter(|&&op| op == Op::Remove).count();
+        let inserted = edits.len() - removed;
+        let replaced = removed.min(inserted);
+        ops.extend(std::iter::repeat_n(Op::Replace, replaced));
+        ops.extend(std::iter::repeat_n(Op::Remove, removed - replaced));
+        ops.extend(std::iter::repeat_n(Op::Insert, inserted - replaced));
+
+        if keep {
+            ops.push(Op::Keep);
+        }
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::num::NonZeroU32;
+
+    use crate::{
+        test_util::for_each_test_script,
+        types::{FrameBulk, LeaveGroundActionSpeed},
+    };
+
+    #[test]
+    fn round_trip() {
+        for_each_test_script(|_, contents| {
+            let document = Document::parse(contents).unwrap();
+            assert_eq!(document.to_string(), contents);
+            assert_eq!(*document.hltas(), HLTAS::from_str(contents).unwrap());
+
+            let mut output = Vec::new();
+            document.to_writer(&mut output).unwrap();
+            assert_eq!(output, contents.as_bytes());
+        });
+    }
+
+    #[test]
+    fn round_trip_crlf_and_whitespace() {
+        let contents =
+            "version 1\r\ndemo  test\r\n\r\nframes\r\n\r\n  \t\r\n   seed 1\r\n//x\r\n\r\n";
+        let document = Document::parse(contents).unwrap();
+        assert_eq!(document.to_string(), contents);
+    }
+
+    #[test]
+    fn tokens_are_lossless() {
+        let contents = "version 1\nframes\n  s03lj-b---|f-----|------|0.0010|90.0|-|5|echo a|b\n";
+        let document = Document::parse(contents).unwrap();
+        for line in document.lines() {
+            let text: String = line.tokens().iter().map(|t| t.text).collect();
+            assert_eq!(text, line.text());
+        }
+
+        let tokens = document.lines()[2].tokens();
+        assert_eq!(tokens[0].kind, TokenKind::Whitespace);
+        let last = tokens.last().unwrap();
+        assert_eq!(last.kind, TokenKind::Field(7));
+        assert_eq!(last.text, "echo a|b");
+    }
+
+    #[test]
+    fn set_line_keeps_untouched_fields() {
+        let contents = "version 1\nframes\n  s03lj-b---|f-----|------|0.0010|90.0|-|-\n";
+        let mut document = Document::parse(contents).unwrap();
+
+        let mut frame_bulk = document.hltas().lines[0].frame_bulk().unwrap().clone();
+        frame_bulk.frame_count = NonZeroU32::new(3).unwrap();
+        if let Some(lgagst) = frame_bulk.auto_actions.leave_ground_action.as_mut() {
+            lgagst.speed = LeaveGroundActionSpeed::OptimalWithFullMaxspeed;
+        }
+        document.set_line(0, Line::FrameBulk(frame_bulk));
+
+        assert_eq!(
+            document.to_string(),
+            "version 1\nframes\n  s03Lj-b---|f-----|------|0.0010|90.0|-|3\n"
+        );
+    }
+
+    #[test]
+    fn apply_preserves_untouched_lines() {
+        let contents = "\
+version 1
+demo  test
+frames
+----------|------|------|0.0010|-|-|5
+
+// comment
+seed 1
+----------|------|------|0.0010|-|-|10
+";
+        let mut document = Document::parse(contents).unwrap();
+        let mut hltas = document.hltas().clone();
+        hltas.lines.remove(2);
+        hltas.lines.insert(
+            0,
+            Line::FrameBulk(FrameBulk::with_frame_time("0.004".to_owned())),
+        );
+        hltas.lines[3].frame_bulk_mut().unwrap().frame_count = NonZeroU32::new(20).unwrap();
+        hltas.properties.save = Some("end".to_owned());
+        document.apply(&hltas);
+
+        assert_eq!(
+            document.to_string(),
+            "\
+version 1
+demo  test
+save end
+frames
+----------|------|------|0.004|-|-|1
+----------|------|------|0.0010|-|-|5
+
+// comment
+----------|------|------|0.0010|-|-|20
+"
+        );
+        assert_eq!(*document.hltas(), hltas);
+        assert_eq!(HLTAS::from_str(&document.to_string()).unwrap(), hltas);
+    }
+
+    #[test]
+    fn push_line_to_unterminated_script() {
+        let mut document = Document::parse("version 1\nframes\nseed 1").unwrap();
+        document.push_line(Line::SharedSeed(2));
+        assert_eq!(document.to_string(), "version 1\nframes\nseed 1\nseed 2");
+
+        document.remove_line(1);
+        assert_eq!(document.to_string(), "version 1\nframes\nseed 1");
+    }
+
+    #[test]
+    fn set_custom_properties() {
+        let mut document = Document::parse("version 1\na  1\ndemo x\nb 2\nc 3\nframes\n").unwrap();
+        let mut properties = document.hltas().properties.clone();
+        properties.custom = vec![
+            ("a".to_owned(), "1".to_owned()),
+            ("b".to_owned(), "4".to_owned()),
+        ];
+        document.set_properties(properties.clone());
+        assert_eq!(
+            document.to_string(),
+            "version 1\na  1\ndemo x\nb 4\nframes\n"
+        );
+
+        properties.custom.push(("d".to_owned(), "5".to_owned()));
+        document.set_properties(properties);
+        assert_eq!(
+            document.to_string(),
+            "version 1\na  1\ndemo x\nb 4\nd 5\nframes\n"
+        );
+    }
+
+    #[test]
+    fn unknown_lines_round_trip() {
+        let contents = "version 1\nframes\n  future_line  1\nseed 1\n";
+        let mut document = Document::parse_lenient(contents).unwrap();
+        assert_eq!(document.to_string(), contents);
+
+        document.set_line(1, Line::SharedSeed(2));
+        assert_eq!(
+            document.to_string(),
+            "version 1\nframes\n  future_line  1\nseed 2\n"
+        );
+    }
+
+    #[test]
+    fn remove_property() {
+        let mut document = Document::parse("version 1\ndemo a\nsave b\nframes\n").unwrap();
+        let mut properties = document.hltas().properties.clone();
+        properties.demo = None;
+        document.set_properties(properties);
+        assert_eq!(document.to_string(), "version 1\nsave b\nframes\n");
+    }
+
+    #[test]
+    fn set_duplicate_property() {
+        let mut document = Document::parse("version 1\ndemo a\nsave s\ndemo b\nframes\n").unwrap();
+        let mut properties = document.hltas().properties.clone();
+        properties.demo = Some("c".to_owned());
+        document.set_properties(properties);
+        assert_eq!(document.to_string(), "version 1\nsave s\ndemo c\nframes\n");
+
+        let mut document = Document::parse("version 1\ndemo a\ndemo b\nframes\n").unwrap();
+        let mut properties = document.hltas().properties.clone();
+        properties.demo = None;
+        document.set_properties(properties);
+        assert_eq!(document.to_string(), "version 1\nframes\n");
+
+        let reparsed = Document::parse(&document.to_string()).unwrap();
+        assert_eq!(reparsed.hltas().properties.demo, None);
+    }
+}
@@ -0,0 +1,576 @@
+//! Script duration and lookups between lines, frames and time.
+//!
+//! Frame times are stored as strings in the script, so summing them as floats accumulates errors
+//! over long scripts. [`Timeline`] parses them as exact decimals and keeps frame counts in `u64`,
+//! so durations are exact.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{timeline::Time, HLTAS};
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|1000
+//! reset 1
+//! ----------|------|------|0.004|-|-|250";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let timeline = hltas.timeline().unwrap();
+//!
+//! assert_eq!(timeline.total_frames(), 1250);
+//! assert_eq!(timeline.total_time().to_string(), "00:02.000");
+//!
+//! assert_eq!(timeline.line_at_frame(1000), Some(2));
+//! assert_eq!(timeline.start_frame(1), Some(1000));
+//! assert_eq!(timeline.line_at_time("1.5".parse().unwrap()), Some(2));
+//! assert_eq!(timeline.frame_at_time(Time::from_millis(1500)), Some(1125));
+//! ```
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Sub},
+    str::FromStr,
+};
+
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+use crate::{types::Line, HLTAS};
+
+/// Number of attoseconds in a second.
+const ATTOS_PER_SEC: u128 = 1_000_000_000_000_000_000;
+/// Number of decimal digits in the fractional part of a [`Time`].
+const DIGITS: usize = 18;
+
+/// An exact non-negative duration with attosecond precision.
+///
+/// Displays as `mm:ss.mmm`, truncating to milliseconds. Minutes don't wrap into hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct Time {
+    attos: u128,
+}
+
+/// Error returned when parsing a [`Time`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseTimeError;
+
+/// Error returned when building a [`Timeline`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineError {
+    /// A frame bulk has a frame time that isn't a valid [`Time`].
+    InvalidFrameTime {
+        /// Index of the frame bulk in [`HLTAS::lines`].
+        line_index: usize,
+        /// The invalid frame time.
+        frame_time: String,
+    },
+    /// The elapsed time at the end of a frame bulk is too large to be represented.
+    Overflow {
+        /// Index of the frame bulk in [`HLTAS::lines`].
+        line_index: usize,
+    },
+}
+
+impl Time {
+    /// Zero duration.
+    pub const ZERO: Time = Time { attos: 0 };
+
+    /// Creates a `Time` from a number of milliseconds.
+    #[inline]
+    pub fn from_millis(millis: u64) -> Self {
+        Self {
+            attos: u128::from(millis) * (ATTOS_PER_SEC / 1000),
+        }
+    }
+
+    /// Creates a `Time` from a number of attoseconds (10<sup>-18</sup> seconds).
+    #[inline]
+    pub fn from_attos(attos: u128) -> Self {
+        Self { attos }
+    }
+
+    /// Returns the number of attoseconds (10<sup>-18</sup> seconds).
+    #[inline]
+    pub fn as_attos(self) -> u128 {
+        self.attos
+    }
+
+    /// Returns the number of whole milliseconds.
+    #[inline]
+    pub fn as_millis(self) -> u128 {
+        self.attos / (ATTOS_PER_SEC / 1000)
+    }
+
+    /// Returns the number of seconds as a float.
+    #[inline]
+    pub fn as_secs_f64(self) -> f64 {
+        (self.attos / ATTOS_PER_SEC) as f64 + (self.attos % ATTOS_PER_SEC) as f64 * 1e-18
+    }
+
+    /// Adds `rhs`, returning `None` if the result would overflow.
+    #[inline]
+    pub fn checked_add(self, rhs: Time) -> Option<Time> {
+        self.attos.checked_add(rhs.attos).map(Time::from_attos)
+    }
+
+    /// Multiplies by `rhs`, returning `None` if the result would overflow.
+    #[inline]
+    pub fn checked_mul(self, rhs: u64) -> Option<Time> {
+        self.attos
+            .checked_mul(u128::from(rhs))
+            .map(Time::from_attos)
+    }
+
+    /// Subtracts `rhs`, returning `None` if the result would be negative.
+    #[inline]
+    pub fn checked_sub(self, rhs: Time) -> Option<Time> {
+        self.attos.checked_sub(rhs.attos).map(Time::from_attos)
+    }
+
+    /// Returns how many whole times `rhs` fits into `self`, or `None` if `rhs` is zero.
+    #[inline]
+    pub fn div_floor(self, rhs: Time) -> Option<u128> {
+        self.attos.checked_div(rhs.attos)
+    }
+}
+
+impl FromStr for Time {
+    type Err = ParseTimeError;
+
+    /// Parses a non-negative decimal number of seconds, like a frame time.
+    ///
+    /// An exponent is allowed. Digits past attosecond precision must be zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('+').unwrap_or(s);
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(index) => (
+                &s[..index],
+                s[index + 1..].parse::<i32>().map_err(|_| ParseTimeError)?,
+            ),
+            None => (s, 0),
+        };
+
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(ParseTimeError);
+        }
+        if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(ParseTimeError);
+        }
+
+        // The value is digits * 10^(exponent - frac.len()) seconds.
+        let digits = format!("{}{}", int, frac);
+        let digits = digits.trim_start_matches('0');
+        let shift = i64::from(exponent) + DIGITS as i64 - frac.len() as i64;
+
+        let digits = if shift < 0 {
+            // Digits past attosecond precision must be zero.
+            let cut = digits.len().saturating_sub(shift.unsigned_abs() as usize);
+            if digits[cut..].bytes().any(|b| b != b'0') {
+                return Err(ParseTimeError);
+            }
+            &digits[..cut]
+        } else {
+            digits
+        };
+        if digits.is_empty() {
+            return Ok(Time::ZERO);
+        }
+
+        let value = digits.parse::<u128>().map_err(|_| ParseTimeError)?;
+        u32::try_from(shift.max(0))
+            .ok()
+            .and_then(|shift| 10u128.checked_pow(shift))
+            .and_then(|scale| value.checked_mul(scale))
+            .map(Time::from_attos)
+            .ok_or(ParseTimeError)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let millis = self.as_millis();
+        write!(
+            f,
+            "{:02}:{:02}.{:03}",
+            millis / 60_000,
+            millis / 1000 % 60,
+            millis % 1000
+        )
+    }
+}
+
+impl Add for Time {
+    type Output = Time;
+
+    /// # Panics
+    ///
+    /// Panics if the result would overflow.
+    #[inline]
+    fn add(self, rhs: Time) -> Time {
+        self.checked_add(rhs).expect("time overflow")
+    }
+}
+
+impl AddAssign for Time {
+    /// # Panics
+    ///
+    /// Panics if the result would overflow.
+    #[inline]
+    fn add_assign(&mut self, rhs: Time) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Time {
+    type Output = Time;
+
+    /// # Panics
+    ///
+    /// Panics if the result would be negative.
+    #[inline]
+    fn sub(self, rhs: Time) -> Time {
+        self.checked_sub(rhs).expect("negative time")
+    }
+}
+
+impl Mul<u64> for Time {
+    type Output = Time;
+
+    /// # Panics
+    ///
+    /// Panics if the result would overflow.
+    #[inline]
+    fn mul(self, rhs: u64) -> Time {
+        self.checked_mul(rhs).expect("time overflow")
+    }
+}
+
+impl Sum for Time {
+    fn sum<I: Iterator<Item = Time>>(iter: I) -> Time {
+        iter.fold(Time::ZERO, Add::add)
+    }
+}
+
+impl Display for ParseTimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid time")
+    }
+}
+
+impl error::Error for ParseTimeError {}
+
+impl Display for TimelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineError::InvalidFrameTime {
+                line_index,
+                frame_time,
+            } => write!(
+                f,
+                "invalid frame time \"{}\" in line {}",
+                frame_time, line_index
+            ),
+            TimelineError::Overflow { line_index } => write!(
+                f,
+                "the elapsed time overflows at the frame bulk in line {}",
+                line_index
+            ),
+        }
+    }
+}
+
+impl error::Error for TimelineError {}
+
+/// A frame bulk placed on the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    line_index: usize,
+    start_frame: u64,
+    frame_count: u64,
+    start_time: Time,
+    frame_time: Time,
+}
+
+impl Span {
+    fn end_frame(&self) -> u64 {
+        self.start_frame + self.frame_count
+    }
+
+    fn end_time(&self) -> Time {
+        self.start_time + self.frame_time * self.frame_count
+    }
+}
+
+/// Timing of every line of a script.
+///
+/// Created with [`HLTAS::timeline()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    /// Frame bulks in order.
+    spans: Vec<Span>,
+    /// For every line, the index into `spans` of the first frame bulk at or after it.
+    next_span: Vec<usize>,
+}
+
+impl Timeline {
+    pub(crate) fn new(hltas: &HLTAS) -> Result<Self, TimelineError> {
+        let mut spans = Vec::new();
+        let mut next_span = Vec::with_capacity(hltas.lines.len());
+        let mut frame = 0;
+        let mut time = Time::ZERO;
+
+        for (line_index, line) in hltas.lines.iter().enumerate() {
+            next_span.push(spans.len());
+
+            if let Line::FrameBulk(frame_bulk) = line {
+                let frame_time: Time =
+                    frame_bulk
+                        .frame_time
+                        .parse()
+                        .map_err(|_| TimelineError::InvalidFrameTime {
+                            line_index,
+                            frame_time: frame_bulk.frame_time.clone(),
+                        })?;
+
+                let span = Span {
+                    line_index,
+                    start_frame: frame,
+                    frame_count: u64::from(frame_bulk.frame_count.get()),
+                    start_time: time,
+                    frame_time,
+                };
+                frame = span.end_frame();
+                // Checked here so that the end time of every span can be computed without
+                // overflowing later.
+                time = frame_time
+                    .checked_mul(span.frame_count)
+                    .and_then(|duration| time.checked_add(duration))
+                    .ok_or(TimelineError::Overflow { line_index })?;
+                spans.push(span);
+            }
+        }
+
+        Ok(Self { spans, next_span })
+    }
+
+    /// Returns the total number of frames.
+    pub fn total_frames(&self) -> u64 {
+        self.spans.last().map(Span::end_frame).unwrap_or(0)
+    }
+
+    /// Returns the total duration.
+    pub fn total_time(&self) -> Time {
+        self.spans.last().map(Span::end_time).unwrap_or(Time::ZERO)
+    }
+
+    /// Returns the index in [`HLTAS::lines`] of the frame bulk that runs the given frame.
+    pub fn line_at_frame(&self, frame: u64) -> Option<usize> {
+        self.span_at_frame(frame).map(|span| span.line_index)
+    }
+
+    /// Returns the index in [`HLTAS::lines`] of the frame bulk running at the given elapsed time.
+    ///
+    /// A frame runs from its start time inclusive to the start time of the next frame exclusive.
+    pub fn line_at_time(&self, time: Time) -> Option<usize> {
+        self.span_at_time(time).map(|span| span.line_index)
+    }
+
+    /// Returns the frame running at the given elapsed time.
+    pub fn frame_at_time(&self, time: Time) -> Option<u64> {
+        let span = self.span_at_time(time)?;
+        let offset = (time - span.start_time)
+            .div_floor(span.frame_time)
+            .unwrap_or(0);
+        Some(span.start_frame + offset as u64)
+    }
+
+    /// Returns the elapsed time at the start of the given frame.
+    ///
+    /// The frame right after the end of the script is accepted and returns the total time.
+    pub fn time_at_frame(&self, frame: u64) -> Option<Time> {
+        if frame == self.total_frames() {
+            return Some(self.total_time());
+        }
+
+        let span = self.span_at_frame(frame)?;
+        Some(span.start_time + span.frame_time * (frame - span.start_frame))
+    }
+
+    /// Returns the frame on which the line at the given index in [`HLTAS::lines`] takes effect.
+    ///
+    /// For a frame bulk this is its first frame. Other lines take effect on the first frame of
+    /// the next frame bulk, or at the end of the script if there isn't one.
+    pub fn start_frame(&self, line_index: usize) -> Option<u64> {
+        let span = *self.next_span.get(line_index)?;
+        Some(
+            self.spans
+                .get(span)
+                .map(|span| span.start_frame)
+                .unwrap_or_else(|| self.total_frames()),
+        )
+    }
+
+    /// Returns the elapsed time at which the line at the given index in [`HLTAS::lines`] takes
+    /// effect.
+    ///
+    /// See [`Timeline::start_frame()`] for details.
+    pub fn start_time(&self, line_index: usize) -> Option<Time> {
+        let span = *self.next_span.get(line_index)?;
+        Some(
+            self.spans
+                .get(span)
+                .map(|span| span.start_time)
+                .unwrap_or_else(|| self.total_time()),
+        )
+    }
+
+    /// Returns the exact frame time of the frame bulk at the given index in [`HLTAS::lines`].
+    pub fn frame_time(&self, line_index: usize) -> Option<Time> {
+        let span = self.spans.get(*self.next_span.get(line_index)?)?;
+        (span.line_index == line_index).then_some(span.frame_time)
+    }
+
+    fn span_at_frame(&self, frame: u64) -> Option<&Span> {
+        let index = self.spans.partition_point(|span| span.end_frame() <= frame);
+        self.spans.get(index)
+    }
+
+    fn span_at_time(&self, time: Time) -> Option<&Span> {
+        // Zero frame time spans take no time and are never running.
+        let index = self.spans.partition_point(|span| span.end_time() <= time);
+        self.spans.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use super::*;
+
+    fn time(s: &str) -> Time {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_time() {
+        assert_eq!(time("0.001"), Time::from_millis(1));
+        assert_eq!(time("1"), Time::from_millis(1000));
+        assert_eq!(time(".5"), Time::from_millis(500));
+        assert_eq!(time("2."), Time::from_millis(2000));
+        assert_eq!(time("0.0100000001").as_attos(), 10_000_000_100_000_000);
+        assert_eq!(time("0.000000000000000001000").as_attos(), 1);
+        assert_eq!(time("1e-3"), Time::from_millis(1));
+        assert_eq!(time("+10E-4"), Time::from_millis(1));
+        assert_eq!(time("0.00e5"), Time::ZERO);
+
+        for invalid in [
+            "",
+            ".",
+            "-0.001",
+            "1e",
+            "0.0000000000000000001",
+            "1e-19",
+            "inf",
+            "1.2.3",
+        ] {
+            assert_eq!(invalid.parse::<Time>(), Err(ParseTimeError), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn display_time() {
+        assert_eq!(Time::ZERO.to_string(), "00:00.000");
+        assert_eq!(time("0.0019").to_string(), "00:00.001");
+        assert_eq!(time("61.25").to_string(), "01:01.250");
+        assert_eq!(time("7322.5").to_string(), "122:02.500");
+    }
+
+    #[test]
+    fn exact_sum() {
+        let mut hltas = HLTAS::from_str(
+            "version 1\nframes\n----------|------|------|0.1|-|-|4000000000\n\
+             ----------|------|------|0.1|-|-|4000000000",
+        )
+        .unwrap();
+        let timeline = hltas.timeline().unwrap();
+        assert_eq!(timeline.total_frames(), 8_000_000_000);
+        assert_eq!(timeline.total_time(), time("800000000"));
+
+        hltas.lines[1].frame_bulk_mut().unwrap().frame_time = "x".to_owned();
+        assert_eq!(
+            hltas.timeline(),
+            Err(TimelineError::InvalidFrameTime {
+                line_index: 1,
+                frame_time: "x".to_owned()
+            })
+        );
+
+        let mut hltas = HLTAS::from_str(
+            "version 1\nframes\n\
+             ----------|------|------|100000000000000|-|-|10\n\
+             ----------|------|------|100000000000000000000|-|-|10",
+        )
+        .unwrap();
+        assert_eq!(
+            hltas.timeline(),
+            Err(TimelineError::Overflow { line_index: 1 })
+        );
+        hltas.lines[1].frame_bulk_mut().unwrap().frame_count = NonZeroU32::new(1).unwrap();
+        assert_eq!(
+            hltas.timeline().unwrap().total_time(),
+            time("100001000000000000000")
+        );
+    }
+
+    #[test]
+    fn lookups() {
+        let hltas = HLTAS::from_str(
+            "version 1\nframes\n\
+             // comment\n\
+             ----------|------|------|0.01|-|-|10\n\
+             ----------|------|------|0|-|-|5\n\
+             reset 1\n\
+             ----------|------|------|0.02|-|-|5\n\
+             reset 2",
+        )
+        .unwrap();
+        let timeline = hltas.timeline().unwrap();
+
+        assert_eq!(timeline.total_frames(), 20);
+        assert_eq!(timeline.total_time(), time("0.2"));
+
+        assert_eq!(timeline.line_at_frame(0), Some(1));
+        assert_eq!(timeline.line_at_frame(9), Some(1));
+        assert_eq!(timeline.line_at_frame(10), Some(2));
+        assert_eq!(timeline.line_at_frame(15), Some(4));
+        assert_eq!(timeline.line_at_frame(20), None);
+
+        assert_eq!(timeline.start_frame(0), Some(0));
+        assert_eq!(timeline.start_frame(3), Some(15));
+        assert_eq!(timeline.start_frame(5), Some(20));
+        assert_eq!(timeline.start_frame(6), None);
+        assert_eq!(timeline.start_time(3), Some(time("0.1")));
+
+        assert_eq!(timeline.time_at_frame(12), Some(time("0.1")));
+        assert_eq!(timeline.time_at_frame(16), Some(time("0.12")));
+        assert_eq!(timeline.time_at_frame(20), Some(time("0.2")));
+        assert_eq!(timeline.time_at_frame(21), None);
+
+        // The zero frame time bulk is skipped.
+        assert_eq!(timeline.line_at_time(time("0.1")), Some(4));
+        assert_eq!(timeline.frame_at_time(time("0.099")), Some(9));
+        assert_eq!(timeline.frame_at_time(time("0.13")), Some(16));
+        assert_eq!(timeline.frame_at_time(time("0.2")), None);
+
+        assert_eq!(timeline.frame_time(4), Some(time("0.02")));
+        assert_eq!(timeline.frame_time(3), None);
+    }
+}
@@ -0,0 +1,474 @@
+//! Run timing from console commands.
+//!
+//! Scripts control the Bunnymod XT timer, pausing and demo recording with console commands in
+//! their frame bulks. This module finds these commands and computes:
+//! - timed segments from `bxt_timer_start` to `bxt_timer_stop`,
+//! - demo recordings from `record` or `bxt_record` to `stop`,
+//! - script time, which counts every frame, versus game time, which doesn't count paused frames,
+//!   for every segment, every demo and the whole script.
+//!
+//! `bxt_timer_reset` sets the timer back to zero, `pause` toggles pausing, `setpause` pauses and
+//! `unpause` and `unsetpause` unpause. The timer counts game time, so it doesn't run while paused.
+//!
+//! The console command of a frame bulk runs at the start of every one of its frames, so a
+//! segment started by a frame bulk includes its first frame, and a `pause` in a frame bulk with
+//! several frames toggles pausing on every frame.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{timeline::Time, timing, HLTAS};
+//!
+//! let contents = "\
+//! version 1
+//! frames
+//! ----------|------|------|0.001|-|-|10|record demo;bxt_timer_start
+//! ----------|------|------|0.001|-|-|1|pause
+//! ----------|------|------|0.001|-|-|5
+//! ----------|------|------|0.001|-|-|10|unpause
+//! ----------|------|------|0.001|-|-|1|bxt_timer_stop;stop";
+//!
+//! let hltas = HLTAS::from_str(contents).unwrap();
+//! let analysis = timing::analyze(&hltas).unwrap();
+//!
+//! assert_eq!(analysis.script_time, Time::from_millis(27));
+//! assert_eq!(analysis.game_time, Time::from_millis(21));
+//!
+//! let segment = &analysis.segments[0];
+//! assert_eq!(segment.frames, 0..26);
+//! assert_eq!(segment.game_time, Time::from_millis(20));
+//!
+//! let demo = &analysis.demos[0];
+//! assert_eq!(demo.name.as_deref(), Some("demo"));
+//! assert_eq!(demo.frames, 0..26);
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+use crate::{
+    console::ConsoleCommand,
+    timeline::{Time, TimelineError},
+    types::Line,
+    HLTAS,
+};
+
+/// A part of the script with the timer running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Index of the frame bulk that starts the timer in [`HLTAS::lines`].
+    pub start_line_index: usize,
+    /// Index of the frame bulk that stops the timer in [`HLTAS::lines`], or `None` if the timer
+    /// runs until the end of the script.
+    pub end_line_index: Option<usize>,
+    /// Frames with the timer running.
+    pub frames: Range<u64>,
+    /// Duration of the frames.
+    pub script_time: Time,
+    /// Duration of the frames that aren't paused, which is the time the timer counts.
+    pub game_time: Time,
+}
+
+/// A part of the script recorded into a demo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Demo {
+    /// Name of the demo, or `None` if the command doesn't give one.
+    pub name: Option<String>,
+    /// Index of the frame bulk that starts recording in [`HLTAS::lines`].
+    pub start_line_index: usize,
+    /// Index of the frame bulk that stops recording in [`HLTAS::lines`], or `None` if recording
+    /// goes on until the end of the script.
+    pub end_line_index: Option<usize>,
+    /// Frames recorded into the demo.
+    pub frames: Range<u64>,
+    /// Duration of the frames.
+    pub script_time: Time,
+    /// Duration of the frames that aren't paused.
+    pub game_time: Time,
+}
+
+/// Timing of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// Parts of the script with the timer running, in order.
+    pub segments: Vec<Segment>,
+    /// Demo recordings, in order.
+    pub demos: Vec<Demo>,
+    /// Number of frames in the script.
+    pub total_frames: u64,
+    /// Duration of all frames.
+    pub script_time: Time,
+    /// Duration of the frames that aren't paused.
+    pub game_time: Time,
+    /// Value of the timer at the end of the script, counted since the last reset.
+    pub timer_time: Time,
+}
+
+/// Timer, pause and recording state along with the results so far.
+struct Simulation {
+    analysis: Analysis,
+    timer_running: bool,
+    paused: bool,
+    recording: bool,
+}
+
+impl Simulation {
+    /// Runs the commands, returning `true` if they changed anything.
+    fn execute(&mut self, line_index: usize, console_command: &ConsoleCommand) -> bool {
+        let frame = self.analysis.total_frames;
+        let mut changed = false;
+
+        for command in console_command.commands() {
+            let name = match command.name() {
+                Some(name) => name.to_ascii_lowercase(),
+                None => continue,
+            };
+
+            match name.as_str() {
+                "bxt_timer_start" if !self.timer_running => {
+                    self.timer_running = true;
+                    self.analysis.segments.push(Segment {
+                        start_line_index: line_index,
+                        end_line_index: None,
+                        frames: frame..frame,
+                        script_time: Time::ZERO,
+                        game_time: Time::ZERO,
+                    });
+                }
+                "bxt_timer_stop" if self.timer_running => {
+                    self.timer_running = false;
+                    self.analysis.segments.last_mut().unwrap().end_line_index = Some(line_index);
+                }
+                "bxt_timer_reset" if self.analysis.timer_time != Time::ZERO => {
+                    self.analysis.timer_time = Time::ZERO;
+                }
+                "pause" => self.paused = !self.paused,
+                "setpause" if !self.paused => self.paused = true,
+                "unpause" | "unsetpause" if self.paused => self.paused = false,
+                "record" | "bxt_record" if !self.recording => {
+                    self.recording = true;
+                    self.analysis.demos.push(Demo {
+                        name: command.args().next().map(ToOwned::to_owned),
+                        start_line_index: line_index,
+                        end_line_index: None,
+                        frames: frame..frame,
+                        script_time: Time::ZERO,
+                        game_time: Time::ZERO,
+                    });
+                }
+                "stop" if self.recording => {
+                    self.recording = false;
+                    self.analysis.demos.last_mut().unwrap().end_line_index = Some(line_index);
+                }
+                _ => continue,
+            }
+
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Runs the given number of frames with the given frame time.
+    fn advance(&mut self, frame_time: Time, frames: u64) {
+        let duration = frame_time * frames;
+        let game_duration = if self.paused { Time::ZERO } else { duration };
+
+        let analysis = &mut self.analysis;
+        analysis.total_frames += frames;
+        analysis.script_time += duration;
+        analysis.game_time += game_duration;
+
+        if self.timer_running {
+            analysis.timer_time += game_duration;
+
+            let segment = analysis.segments.last_mut().unwrap();
+            segment.frames.end += frames;
+            segment.script_time += duration;
+            segment.game_time += game_duration;
+        }
+
+        if self.recording {
+            let demo = analysis.demos.last_mut().unwrap();
+            demo.frames.end += frames;
+            demo.script_time += duration;
+            demo.game_time += game_duration;
+        }
+    }
+}
+
+/// Computes the timed segments, demo recordings and durations of a script.
+///
+/// # Errors
+///
+/// Returns an error if a frame bulk has an invalid frame time or if the duration of the script
+/// overflows.
+pub fn analyze(hltas: &HLTAS) -> Result<Analysis, TimelineError> {
+    let timeline = hltas.timeline()?;
+
+    let mut simulation = Simulation {
+        analysis: Analysis {
+            segments: Vec::new(),
+            demos: Vec::new(),
+            total_frames: 0,
+            script_time: Time::ZERO,
+            game_time: Time::ZERO,
+            timer_time: Time::ZERO,
+        },
+        timer_running: false,
+        paused: false,
+        recording: false,
+    };
+
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        let frame_bulk = match line {
+            Line::FrameBulk(frame_bulk) => frame_bulk,
+            _ => continue,
+        };
+
+        let frame_time = timeline.frame_time(line_index).unwrap();
+        let frame_count = u64::from(frame_bulk.frame_count.get());
+        let console_command = frame_bulk.console_commands();
+
+        // Once running the commands changes nothing, the remaining frames all run the same.
+        for frame in 0..frame_count {
+            let changed = simulation.execute(line_index, &console_command);
+            if changed {
+                simulation.advance(frame_time, 1);
+            } else {
+                simulation.advance(frame_time, frame_count - frame);
+                break;
+            }
+        }
+    }
+
+    Ok(simulation.analysis)
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frames {}..{}, game time {}, script time {}",
+            self.frames.start, self.frames.end, self.game_time, self.script_time
+        )?;
+        if self.end_line_index.is_none() {
+            write!(f, ", running until the end")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Demo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: frames {}..{}, game time {}, script time {}",
+            self.name.as_deref().unwrap_or("(unnamed)"),
+            self.frames.start,
+            self.frames.end,
+            self.game_time,
+            self.script_time
+        )?;
+        if self.end_line_index.is_none() {
+            write!(f, ", recording until the end")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Frames: {}", self.total_frames)?;
+        writeln!(f, "Script time: {}", self.script_time)?;
+        writeln!(f, "Game time: {}", self.game_time)?;
+        write!(f, "Timer: {}", self.timer_time)?;
+
+        if !self.segments.is_empty() {
+            write!(f, "\nSegments:")?;
+            for (i, segment) in self.segments.iter().enumerate() {
+                write!(f, "\n  {}: {}", i + 1, segment)?;
+            }
+        }
+
+        if !self.demos.is_empty() {
+            write!(f, "\nDemos:")?;
+            for demo in &self.demos {
+                write!(f, "\n  {}", demo)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{for_each_test_script, parse_frames};
+
+    fn analyze_str(contents: &str) -> Analysis {
+        analyze(&parse_frames(contents)).unwrap()
+    }
+
+    #[test]
+    fn segments() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|5\n\
+             ----------|------|------|0.001|-|-|10|bxt_timer_start\n\
+             ----------|------|------|0.002|-|-|5|bxt_timer_stop\n\
+             ----------|------|------|0.001|-|-|5|BXT_TIMER_START\n\
+             ----------|------|------|0.001|-|-|5",
+        );
+
+        assert_eq!(
+            analysis.segments,
+            [
+                Segment {
+                    start_line_index: 1,
+                    end_line_index: Some(2),
+                    frames: 5..15,
+                    script_time: Time::from_millis(10),
+                    game_time: Time::from_millis(10),
+                },
+                Segment {
+                    start_line_index: 3,
+                    end_line_index: None,
+                    frames: 20..30,
+                    script_time: Time::from_millis(10),
+                    game_time: Time::from_millis(10),
+                },
+            ]
+        );
+        assert_eq!(analysis.total_frames, 30);
+        assert_eq!(analysis.script_time, Time::from_millis(35));
+        assert_eq!(analysis.game_time, Time::from_millis(35));
+        assert_eq!(analysis.timer_time, Time::from_millis(20));
+    }
+
+    #[test]
+    fn reset() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|10|bxt_timer_start\n\
+             ----------|------|------|0.001|-|-|3|bxt_timer_reset\n\
+             ----------|------|------|0.001|-|-|5",
+        );
+        // The reset runs on every frame of its frame bulk.
+        assert_eq!(analysis.timer_time, Time::from_millis(6));
+        assert_eq!(analysis.segments.len(), 1);
+        assert_eq!(analysis.segments[0].game_time, Time::from_millis(18));
+    }
+
+    #[test]
+    fn pauses() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|10|bxt_timer_start\n\
+             ----------|------|------|0.001|-|-|3|pause\n\
+             ----------|------|------|0.001|-|-|10|setpause\n\
+             ----------|------|------|0.001|-|-|10|unsetpause\n\
+             ----------|------|------|0.001|-|-|10|setpause;unpause",
+        );
+        // The `pause` toggles on frames 10, 11 and 12, so frames 10, 12 and 13 to 22 are paused.
+        assert_eq!(analysis.script_time, Time::from_millis(43));
+        assert_eq!(analysis.game_time, Time::from_millis(31));
+        assert_eq!(analysis.timer_time, Time::from_millis(31));
+        assert_eq!(analysis.segments[0].script_time, Time::from_millis(43));
+    }
+
+    #[test]
+    fn demos() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|10|record first\n\
+             ----------|------|------|0.001|-|-|10|record second;pause\n\
+             ----------|------|------|0.001|-|-|1|stop\n\
+             ----------|------|------|0.001|-|-|5|bxt_record\n\
+             ----------|------|------|0.001|-|-|5",
+        );
+
+        assert_eq!(
+            analysis.demos,
+            [
+                Demo {
+                    name: Some("first".to_owned()),
+                    start_line_index: 0,
+                    end_line_index: Some(2),
+                    frames: 0..20,
+                    script_time: Time::from_millis(20),
+                    game_time: Time::from_millis(15),
+                },
+                Demo {
+                    name: None,
+                    start_line_index: 3,
+                    end_line_index: None,
+                    frames: 21..31,
+                    script_time: Time::from_millis(10),
+                    game_time: Time::from_millis(10),
+                },
+            ]
+        );
+        assert!(analysis.segments.is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let analysis = analyze_str(
+            "----------|------|------|0.001|-|-|1000|record run;bxt_timer_start\n\
+             ----------|------|------|0.01|-|-|100|bxt_timer_stop",
+        );
+        assert_eq!(
+            analysis.to_string(),
+            "Frames: 1100\n\
+             Script time: 00:02.000\n\
+             Game time: 00:02.000\n\
+             Timer: 00:01.000\n\
+             Segments:\n  \
+             1: frames 0..1000, game time 00:01.000, script time 00:01.000\n\
+             Demos:\n  \
+             run: frames 0..1100, game time 00:02.000, script time 00:02.000, recording until the end"
+        );
+
+        let analysis = analyze_str("----------|------|------|0.001|-|-|1");
+        assert_eq!(
+            analysis.to_string(),
+            "Frames: 1\n\
+             Script time: 00:00.001\n\
+             Game time: 00:00.001\n\
+             Timer: 00:00.000"
+        );
+    }
+
+    #[test]
+    fn invalid_frame_time() {
+        let mut hltas =
+            HLTAS::from_str("version 1\nframes\n----------|------|------|0.001|-|-|1").unwrap();
+        hltas.lines[0].frame_bulk_mut().unwrap().frame_time = "x".to_owned();
+        assert_eq!(
+            analyze(&hltas),
+            Err(TimelineError::InvalidFrameTime {
+                line_index: 0,
+                frame_time: "x".to_owned(),
+            })
+        );
+
+        let hltas = HLTAS::from_str(
+            "version 1\nframes\n----------|------|------|100000000000000000000|-|-|10",
+        )
+        .unwrap();
+        assert_eq!(
+            analyze(&hltas),
+            Err(TimelineError::Overflow { line_index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_data_script_time_matches_timeline() {
+        for_each_test_script(|_, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+            let analysis = analyze(&hltas).unwrap();
+            assert_eq!(analysis.script_time, hltas.timeline().unwrap().total_time());
+        });
+    }
+}
@@ -0,0 +1,396 @@
+//! Geometric transforms of scripts.
+//!
+//! These functions back [`HLTAS::mirror()`], [`HLTAS::rotate()`] and [`HLTAS::translate()`].
+//! Yaw angles are in degrees, counter-clockwise from the positive X axis, like in the game.
+//!
+//! A transform changes every part of the script that depends on the world orientation or
+//! position:
+//! - yaw angles of frame bulks, target yaw and render yaw overrides, yaw `change` lines and yaw
+//!   constraints of vectorial strafing,
+//! - coordinates of point strafing and of `look_at` constraints, which are only translated when
+//!   they aren't relative to an entity.
+//!
+//! Mirroring also swaps left and right: strafe directions, strafing buttons, movement keys and
+//! yaw offsets in `change` lines. Transformed yaw angles aren't normalized, so smoothly changing
+//! angles stay smooth. Console commands are left as is.
+//!
+//! [`HLTAS::mirror()`]: crate::HLTAS::mirror
+//! [`HLTAS::rotate()`]: crate::HLTAS::rotate
+//! [`HLTAS::translate()`]: crate::HLTAS::translate
+
+use crate::{
+    types::{
+        AutoMovement, Button, Buttons, ChangeTarget, FrameBulk, Line, StrafeDir,
+        VectorialStrafingConstraints,
+    },
+    HLTAS,
+};
+
+/// An affine transform of the horizontal plane, with a vertical offset.
+#[derive(Debug, Clone, Copy)]
+struct Transform {
+    /// Linear part, applied to X and Y.
+    matrix: [[f64; 2]; 2],
+    /// Offset added after the linear part.
+    offset: [f64; 3],
+    /// Angle added to yaw angles, after negating them if `mirrored` is set.
+    yaw_offset: f64,
+    /// Whether the transform swaps left and right.
+    mirrored: bool,
+}
+
+/// Returns the cosine and the sine of the angle in degrees, exact for multiples of 90 degrees.
+fn cos_sin(degrees: f64) -> (f64, f64) {
+    let quarter = degrees / 90.;
+    if quarter.fract() == 0. {
+        match quarter.rem_euclid(4.) as u8 {
+            0 => (1., 0.),
+            1 => (0., 1.),
+            2 => (-1., 0.),
+            _ => (0., -1.),
+        }
+    } else {
+        let radians = degrees.to_radians();
+        (radians.cos(), radians.sin())
+    }
+}
+
+impl Transform {
+    fn mirror(axis: f32) -> Self {
+        let (cos, sin) = cos_sin(f64::from(axis) * 2.);
+        Self {
+            matrix: [[cos, sin], [sin, -cos]],
+            offset: [0.; 3],
+            yaw_offset: f64::from(axis) * 2.,
+            mirrored: true,
+        }
+    }
+
+    fn rotate(angle: f32) -> Self {
+        let (cos, sin) = cos_sin(f64::from(angle));
+        Self {
+            matrix: [[cos, -sin], [sin, cos]],
+            offset: [0.; 3],
+            yaw_offset: f64::from(angle),
+            mirrored: false,
+        }
+    }
+
+    fn translate(x: f32, y: f32, z: f32) -> Self {
+        Self {
+            matrix: [[1., 0.], [0., 1.]],
+            offset: [f64::from(x), f64::from(y), f64::from(z)],
+            yaw_offset: 0.,
+            mirrored: false,
+        }
+    }
+
+    fn yaw(&self, yaw: f32) -> f32 {
+        let yaw = f64::from(yaw);
+        let yaw = if self.mirrored { -yaw } else { yaw };
+        (yaw + self.yaw_offset) as f32
+    }
+
+    /// Transforms a yaw offset or a difference between yaw angles.
+    fn yaw_difference(&self, difference: f32) -> f32 {
+        if self.mirrored {
+            -difference
+        } else {
+            difference
+        }
+    }
+
+    /// Transforms a direction, leaving out the offset.
+    fn vector(&self, x: f32, y: f32) -> (f32, f32) {
+        let [[a, b], [c, d]] = self.matrix;
+        let (x, y) = (f64::from(x), f64::from(y));
+        ((a * x + b * y) as f32, (c * x + d * y) as f32)
+    }
+
+    fn point(&self, x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+        let [[a, b], [c, d]] = self.matrix;
+        let [dx, dy, dz] = self.offset;
+        let (x, y, z) = (f64::from(x), f64::from(y), f64::from(z));
+        (
+            (a * x + b * y + dx) as f32,
+            (c * x + d * y + dy) as f32,
+            (z + dz) as f32,
+        )
+    }
+
+    fn yaws(&self, yaws: &mut [f32]) {
+        for yaw in yaws {
+            *yaw = self.yaw(*yaw);
+        }
+    }
+
+    fn strafe_dir(&self, dir: StrafeDir) -> StrafeDir {
+        match dir {
+            StrafeDir::Left if self.mirrored => StrafeDir::Right,
+            StrafeDir::Right if self.mirrored => StrafeDir::Left,
+            StrafeDir::LeftRight(count) if self.mirrored => StrafeDir::RightLeft(count),
+            StrafeDir::RightLeft(count) if self.mirrored => StrafeDir::LeftRight(count),
+            StrafeDir::Yaw(yaw) => StrafeDir::Yaw(self.yaw(yaw)),
+            StrafeDir::Line { yaw } => StrafeDir::Line { yaw: self.yaw(yaw) },
+            StrafeDir::Point { x, y } => {
+                let (x, y, _) = self.point(x, y, 0.);
+                StrafeDir::Point { x, y }
+            }
+            dir => dir,
+        }
+    }
+
+    fn frame_bulk(&self, frame_bulk: &mut FrameBulk) {
+        match &mut frame_bulk.auto_actions.movement {
+            Some(AutoMovement::SetYaw(yaw)) => *yaw = self.yaw(*yaw),
+            Some(AutoMovement::Strafe(settings)) => settings.dir = self.strafe_dir(settings.dir),
+            None => (),
+        }
+
+        if self.mirrored {
+            let keys = &mut frame_bulk.movement_keys;
+            std::mem::swap(&mut keys.left, &mut keys.right);
+        }
+    }
+
+    fn buttons(&self, buttons: Buttons) -> Buttons {
+        fn mirror(button: Button) -> Button {
+            match button {
+                Button::ForwardLeft => Button::ForwardRight,
+                Button::Left => Button::Right,
+                Button::BackLeft => Button::BackRight,
+                Button::BackRight => Button::BackLeft,
+                Button::Right => Button::Left,
+                Button::ForwardRight => Button::ForwardLeft,
+                button => button,
+            }
+        }
+
+        match buttons {
+            Buttons::Set {
+                air_left,
+                air_right,
+                ground_left,
+                ground_right,
+            } if self.mirrored => Buttons::Set {
+                air_left: mirror(air_right),
+                air_right: mirror(air_left),
+                ground_left: mirror(ground_right),
+                ground_right: mirror(ground_left),
+            },
+            buttons => buttons,
+        }
+    }
+
+    fn constraints(
+        &self,
+        constraints: VectorialStrafingConstraints,
+    ) -> VectorialStrafingConstraints {
+        match constraints {
+            VectorialStrafingConstraints::Yaw { yaw, tolerance } => {
+                VectorialStrafingConstraints::Yaw {
+                    yaw: self.yaw(yaw),
+                    tolerance,
+                }
+            }
+            // Mirroring reverses the direction of the range.
+            VectorialStrafingConstraints::YawRange { from, to } if self.mirrored => {
+                VectorialStrafingConstraints::YawRange {
+                    from: self.yaw(to),
+                    to: self.yaw(from),
+                }
+            }
+            VectorialStrafingConstraints::YawRange { from, to } => {
+                VectorialStrafingConstraints::YawRange {
+                    from: self.yaw(from),
+                    to: self.yaw(to),
+                }
+            }
+            VectorialStrafingConstraints::LookAt {
+                entity: None,
+                x,
+                y,
+                z,
+            } => {
+                let (x, y, z) = self.point(x, y, z);
+                VectorialStrafingConstraints::LookAt {
+                    entity: None,
+                    x,
+                    y,
+                    z,
+                }
+            }
+            // Coordinates relative to an entity don't move with the world.
+            VectorialStrafingConstraints::LookAt {
+                entity: Some(entity),
+                x,
+                y,
+                z,
+            } => {
+                let (x, y) = self.vector(x, y);
+                VectorialStrafingConstraints::LookAt {
+                    entity: Some(entity),
+                    x,
+                    y,
+                    z,
+                }
+            }
+            constraints => constraints,
+        }
+    }
+
+    fn apply(&self, hltas: &mut HLTAS) {
+        for line in &mut hltas.lines {
+            match line {
+                Line::FrameBulk(frame_bulk) => self.frame_bulk(frame_bulk),
+                Line::Buttons(buttons) => *buttons = self.buttons(*buttons),
+                Line::VectorialStrafingConstraints(constraints) => {
+                    *constraints = self.constraints(*constraints)
+                }
+                Line::Change(change) => match change.target {
+                    ChangeTarget::Yaw | ChangeTarget::VectorialStrafingYaw => {
+                        change.final_value = self.yaw(change.final_value)
+                    }
+                    ChangeTarget::VectorialStrafingYawOffset => {
+                        change.final_value = self.yaw_difference(change.final_value)
+                    }
+                    ChangeTarget::Pitch => (),
+                },
+                Line::TargetYawOverride(yaws) | Line::RenderYawOverride(yaws) => self.yaws(yaws),
+                _ => (),
+            }
+        }
+    }
+}
+
+pub(crate) fn mirror(hltas: &mut HLTAS, axis: f32) {
+    Transform::mirror(axis).apply(hltas)
+}
+
+pub(crate) fn rotate(hltas: &mut HLTAS, angle: f32) {
+    Transform::rotate(angle).apply(hltas)
+}
+
+pub(crate) fn translate(hltas: &mut HLTAS, x: f32, y: f32, z: f32) {
+    Transform::translate(x, y, z).apply(hltas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{for_each_test_script, parse_frames};
+
+    fn transformed(contents: &str, f: impl FnOnce(&mut HLTAS)) -> HLTAS {
+        let mut hltas = parse_frames(contents);
+        f(&mut hltas);
+        hltas
+    }
+
+    const CONTENTS: &str = "\
+        s06-------|------|------|0.001|3|-|10\n\
+        s00-------|-l----|------|0.001|-|-|10\n\
+        s03-------|------|------|0.001|30|-|10\n\
+        s04-------|------|------|0.001|100 -20|-|10\n\
+        ----------|------|------|0.001|45|-|10\n\
+        buttons 1 6 3 0\n\
+        target_yaw velocity_lock +-1\n\
+        target_yaw 10 +-5\n\
+        target_yaw from 10 to 40\n\
+        target_yaw look_at 100 200 300\n\
+        target_yaw look_at entity 1 10 20 30\n\
+        change yaw to 90 over 1 s\n\
+        change target_yaw_offset to 5 over 1 s\n\
+        change pitch to 5 over 1 s\n\
+        target_yaw_override 10 20\n\
+        render_yaw_override 30\n\
+        pitch_override 40";
+
+    #[test]
+    fn mirror() {
+        let hltas = transformed(CONTENTS, |hltas| hltas.mirror(0.));
+        let expected = parse_frames(
+            "\
+            s07-------|------|------|0.001|3|-|10\n\
+            s01-------|--r---|------|0.001|-|-|10\n\
+            s03-------|------|------|0.001|-30|-|10\n\
+            s04-------|------|------|0.001|100 20|-|10\n\
+            ----------|------|------|0.001|-45|-|10\n\
+            buttons 2 7 0 5\n\
+            target_yaw velocity_lock +-1\n\
+            target_yaw -10 +-5\n\
+            target_yaw from -40 to -10\n\
+            target_yaw look_at 100 -200 300\n\
+            target_yaw look_at entity 1 10 -20 30\n\
+            change yaw to -90 over 1 s\n\
+            change target_yaw_offset to -5 over 1 s\n\
+            change pitch to 5 over 1 s\n\
+            target_yaw_override -10 -20\n\
+            render_yaw_override -30\n\
+            pitch_override 40",
+        );
+        assert_eq!(hltas, expected);
+
+        let hltas = transformed(CONTENTS, |hltas| hltas.mirror(90.));
+        assert_eq!(
+            hltas.lines[2],
+            parse_frames("s03-------|------|------|0.001|150|-|10").lines[0]
+        );
+        assert_eq!(
+            hltas.lines[3],
+            parse_frames("s04-------|------|------|0.001|-100 -20|-|10").lines[0]
+        );
+    }
+
+    #[test]
+    fn rotate() {
+        let hltas = transformed(CONTENTS, |hltas| hltas.rotate(90.));
+        let expected = parse_frames(
+            "\
+            s06-------|------|------|0.001|3|-|10\n\
+            s00-------|-l----|------|0.001|-|-|10\n\
+            s03-------|------|------|0.001|120|-|10\n\
+            s04-------|------|------|0.001|20 100|-|10\n\
+            ----------|------|------|0.001|135|-|10\n\
+            buttons 1 6 3 0\n\
+            target_yaw velocity_lock +-1\n\
+            target_yaw 100 +-5\n\
+            target_yaw from 100 to 130\n\
+            target_yaw look_at -200 100 300\n\
+            target_yaw look_at entity 1 -20 10 30\n\
+            change yaw to 180 over 1 s\n\
+            change target_yaw_offset to 5 over 1 s\n\
+            change pitch to 5 over 1 s\n\
+            target_yaw_override 100 110\n\
+            render_yaw_override 120\n\
+            pitch_override 40",
+        );
+        assert_eq!(hltas, expected);
+    }
+
+    #[test]
+    fn translate() {
+        let hltas = transformed(CONTENTS, |hltas| hltas.translate(1., 2., 3.));
+        let expected = parse_frames(
+            &CONTENTS
+                .replace("100 -20", "101 -18")
+                .replace("look_at 100 200 300", "look_at 101 202 303"),
+        );
+        assert_eq!(hltas, expected);
+    }
+
+    #[test]
+    fn inverse() {
+        for_each_test_script(|path, contents| {
+            let original = HLTAS::from_str(contents).unwrap();
+
+            let mut hltas = original.clone();
+            hltas.mirror(0.);
+            hltas.mirror(0.);
+            assert_eq!(hltas, original, "{}", path.display());
+
+            hltas.translate(8., 16., 32.);
+            hltas.translate(-8., -16., -32.);
+            assert_eq!(hltas, original, "{}", path.display());
+        });
+    }
+}
@@ -1,6 +1,6 @@
 //! Types representing various parts of `.hltas` scripts.
 
-use std::{io::Write, iter, num::NonZeroU32, slice};
+use std::{io::Write, iter, num::NonZeroU32, ops::Range, slice};
 
 use cookie_factory::GenError;
 #[cfg(feature = "proptest1")]
@@ -10,7 +10,18 @@ use proptest_derive::Arbitrary;
 #[cfg(feature = "serde1")]
 use serde::{Deserialize, Serialize};
 
-use crate::{read, write};
+use crate::{
+    console::ConsoleCommand,
+    edit::{self, EditError},
+    frames::Frames,
+    normalize, read,
+    retime::{self, RetimeError, Rounding},
+    source_map::SourceMap,
+    timeline::{Timeline, TimelineError},
+    transform,
+    validate::{self, ValidationError},
+    write::{self, WriterError},
+};
 
 /// A HLTAS script.
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -68,6 +79,14 @@ pub struct Properties {
         proptest(strategy = "prop::option::of(arbitrary_property_value())")
     )]
     pub load_command: Option<String>,
+    /// Properties not recognized by this crate, in the order they appear in the script.
+    ///
+    /// These are kept so that reading and writing a script doesn't lose them.
+    #[cfg_attr(
+        feature = "proptest1",
+        proptest(strategy = "prop::collection::vec(arbitrary_custom_property(), 0..5)")
+    )]
+    pub custom: Vec<(String, String)>,
 }
 
 /// Shared and non-shared RNG seeds.
@@ -141,6 +160,12 @@ pub enum Line {
         )]
         Vec<f32>,
     ),
+    /// A line that wasn't recognized, with its text.
+    ///
+    /// Only produced by the lenient parsing mode (see [`HLTAS::from_str_lenient`]) and written
+    /// back verbatim, which lets scripts using newer syntax be edited and saved.
+    #[cfg_attr(feature = "proptest1", proptest(skip))]
+    Unknown(String),
 }
 
 /// A buttons line.
@@ -550,6 +575,20 @@ fn arbitrary_property_value() -> impl Strategy<Value = String> {
     any_with::<String>("\\S|\\S\\PC*\\S".into())
 }
 
+/// Generates arbitrary properties with names not recognized by this crate.
+#[cfg(feature = "proptest1")]
+fn arbitrary_custom_property() -> impl Strategy<Value = (String, String)> {
+    (
+        "[a-zA-Z0-9_]+".prop_filter("known property", |name| {
+            !matches!(
+                name.as_str(),
+                "demo" | "save" | "frametime0ms" | "seed" | "hlstrafe_version" | "load_command"
+            )
+        }),
+        arbitrary_property_value(),
+    )
+}
+
 /// Generate arbitrary strafe settings which for now help with constant yawspeed cases.
 #[cfg(feature = "proptest1")]
 fn arbitrary_strafe_settings() -> impl Strategy<Value = AutoMovement> {
@@ -585,7 +624,7 @@ impl HLTAS {
     /// # }
     /// ```
     #[allow(clippy::should_implement_trait)] // FromStr does not allow borrowing from the &str.
-    pub fn from_str(input: &str) -> Result<Self, read::Error> {
+    pub fn from_str(input: &str) -> Result<Self, read::Error<'_>> {
         match read::hltas(input) {
             Ok((_, hltas)) => Ok(hltas),
             Err(nom::Err::Error(mut e)) | Err(nom::Err::Failure(mut e)) => {
@@ -597,6 +636,93 @@ impl HLTAS {
         }
     }
 
+    /// Parses a `.hltas` script, keeping unrecognized lines as [`Line::Unknown`].
+    ///
+    /// Lines that aren't recognized by any of the line parsers are kept instead of failing the
+    /// whole script. Lines that are recognized but contain invalid values are still errors.
+    ///
+    /// Returns the script along with the locations of the unrecognized lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::Line, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5
+    /// some_future_line 1 2 3";
+    ///
+    /// assert!(HLTAS::from_str(contents).is_err());
+    ///
+    /// let (hltas, unknown_lines) = HLTAS::from_str_lenient(contents).unwrap();
+    /// assert_eq!(
+    ///     hltas.lines[1],
+    ///     Line::Unknown("some_future_line 1 2 3".to_owned())
+    /// );
+    /// assert_eq!(unknown_lines[0].index, 1);
+    /// assert_eq!(unknown_lines[0].line, 3);
+    /// ```
+    pub fn from_str_lenient(
+        input: &str,
+    ) -> Result<(Self, Vec<read::UnknownLine>), read::Error<'_>> {
+        match read::hltas_lenient(input) {
+            Ok((_, hltas)) => {
+                let unknown_lines = SourceMap::from_input(input)
+                    .iter()
+                    .zip(&hltas.lines)
+                    .enumerate()
+                    .filter(|(_, (_, line))| matches!(line, Line::Unknown(_)))
+                    .map(|(index, (location, _))| read::UnknownLine {
+                        index,
+                        line: location.line,
+                    })
+                    .collect();
+                Ok((hltas, unknown_lines))
+            }
+            Err(nom::Err::Error(mut e)) | Err(nom::Err::Failure(mut e)) => {
+                e.whole_input = input;
+                Err(e)
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!(), // We don't use streaming parsers.
+        }
+    }
+
+    /// Parses a `.hltas` script, also returning the location of every line in `input`.
+    ///
+    /// See the [`source_map`](crate::source_map) module for an example.
+    pub fn from_str_with_source_map(input: &str) -> Result<(Self, SourceMap), read::Error<'_>> {
+        let hltas = Self::from_str(input)?;
+        Ok((hltas, SourceMap::from_input(input)))
+    }
+
+    /// Parses a `.hltas` script, reporting every error instead of stopping at the first one.
+    ///
+    /// Lines that fail to parse are skipped. Returns the script made of everything that parsed
+    /// successfully along with all errors, see [`read::hltas_recovering`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate hltas;
+    /// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::fs::read_to_string;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = read_to_string("script.hltas")?;
+    /// let (hltas, errors) = HLTAS::from_str_recovering(&contents);
+    /// for error in errors {
+    ///     println!("{}", error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_str_recovering(input: &str) -> (Self, Vec<read::Error<'_>>) {
+        read::hltas_recovering(input)
+    }
+
     /// Outputs the script in the `.hltas` format.
     ///
     /// # Examples
@@ -616,6 +742,82 @@ impl HLTAS {
         write::gen_hltas(writer, self)
     }
 
+    /// Outputs the script in the `.hltas` format, checking it with [`HLTAS::validate()`] first.
+    ///
+    /// Nothing is written if the script is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::Line, write::WriterError, HLTAS};
+    ///
+    /// let mut hltas = HLTAS::default();
+    /// hltas.lines.push(Line::Comment("two\nlines".to_owned()));
+    ///
+    /// let mut buf = Vec::new();
+    /// let result = hltas.to_writer_checked(&mut buf);
+    /// assert!(matches!(result, Err(WriterError::Invalid(_))));
+    /// assert!(buf.is_empty());
+    /// ```
+    pub fn to_writer_checked<W: Write>(&self, writer: W) -> Result<(), WriterError> {
+        self.validate().map_err(WriterError::Invalid)?;
+        self.to_writer(writer)?;
+        Ok(())
+    }
+
+    /// Checks that the script can be written and read back.
+    ///
+    /// Returns an error naming the first value that the `.hltas` format can't represent, such as
+    /// a comment with a line break or constant yawspeed strafing with a yaw direction. See the
+    /// [`validate`](crate::validate) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{
+    ///     types::{FrameBulk, Line},
+    ///     validate::{Field, Problem},
+    ///     HLTAS,
+    /// };
+    ///
+    /// let mut hltas = HLTAS::default();
+    /// hltas.lines.push(Line::FrameBulk(FrameBulk::with_frame_time("fast".to_owned())));
+    ///
+    /// let error = hltas.validate().unwrap_err();
+    /// assert_eq!(error.line_index, Some(0));
+    /// assert_eq!(error.field, Field::FrameTime);
+    /// assert_eq!(error.problem, Problem::InvalidFrameTime);
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate::validate(self)
+    }
+
+    /// Outputs the script in the `.hltas` format, returning the location of every line in the
+    /// output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::Line, HLTAS};
+    ///
+    /// let mut hltas = HLTAS::default();
+    /// hltas.properties.demo = Some("test".to_owned());
+    /// hltas.lines.push(Line::Reset { non_shared_seed: 1234 });
+    ///
+    /// let mut buf = Vec::new();
+    /// let source_map = hltas.to_writer_with_source_map(&mut buf).unwrap();
+    ///
+    /// let location = source_map.get(0).unwrap();
+    /// assert_eq!(location.line, 3);
+    /// assert_eq!(&buf[location.span.clone()], b"reset 1234");
+    /// ```
+    pub fn to_writer_with_source_map<W: Write>(&self, writer: W) -> Result<SourceMap, GenError> {
+        write::gen_hltas_with_source_map(writer, self)
+    }
+
     /// Returns an iterator over frame bulks of the script.
     ///
     /// # Examples
@@ -634,10 +836,340 @@ impl HLTAS {
     #[inline]
     pub fn frame_bulks(
         &self,
-    ) -> iter::FilterMap<slice::Iter<Line>, fn(&Line) -> Option<&FrameBulk>> {
+    ) -> iter::FilterMap<slice::Iter<'_, Line>, fn(&Line) -> Option<&FrameBulk>> {
         self.lines.iter().filter_map(Line::frame_bulk)
     }
 
+    /// Returns an iterator over individual frames of the script with the state carried over from
+    /// the preceding lines resolved.
+    ///
+    /// See the [`frames`](crate::frames) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5
+    /// ------b---|------|------|0.001|-|-|10";
+    ///
+    /// let hltas = HLTAS::from_str(contents).unwrap();
+    /// assert_eq!(hltas.frames().count(), 15);
+    /// ```
+    #[inline]
+    pub fn frames(&self) -> Frames<'_> {
+        Frames::new(self)
+    }
+
+    /// Returns the timing of every line of the script.
+    ///
+    /// Returns an error if a frame bulk has a frame time that isn't a non-negative decimal number,
+    /// or if the duration of the script is too large to be represented. See the
+    /// [`timeline`](crate::timeline) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5
+    /// ------b---|------|------|0.01|-|-|10";
+    ///
+    /// let hltas = HLTAS::from_str(contents).unwrap();
+    /// let timeline = hltas.timeline().unwrap();
+    /// assert_eq!(timeline.total_frames(), 15);
+    /// assert_eq!(timeline.total_time().to_string(), "00:00.105");
+    /// ```
+    #[inline]
+    pub fn timeline(&self) -> Result<Timeline, TimelineError> {
+        Timeline::new(self)
+    }
+
+    /// Splits the frame bulk containing the given frame so that a frame bulk starts at it.
+    ///
+    /// Returns the index in [`lines`](Self::lines) of the frame bulk starting at `frame`, or the
+    /// number of lines if `frame` is the number of frames in the script. Returns an error if
+    /// `frame` is past the end of the script. Auto-actions with limited times stay on the first
+    /// part. See the [`edit`](crate::edit) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5
+    /// ------b---|------|------|0.001|-|-|10";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// assert_eq!(hltas.split_at_frame(8), Ok(2));
+    ///
+    /// let frame_counts: Vec<_> = hltas.frame_bulks().map(|x| x.frame_count.get()).collect();
+    /// assert_eq!(frame_counts, [5, 3, 7]);
+    /// ```
+    #[inline]
+    pub fn split_at_frame(&mut self, frame: u64) -> Result<usize, EditError> {
+        edit::split_at_frame(self, frame)
+    }
+
+    /// Inserts lines so that they take effect at the given frame.
+    ///
+    /// The frame bulk containing `frame` is split if needed. At a frame bulk boundary, the lines
+    /// are inserted right after the preceding frame bulk, before any lines setting up the next
+    /// one. Override values left for the frames after `frame` are moved after the inserted lines.
+    /// Returns an error if `frame` is past the end of the script.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::FrameBulk, types::Line, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// let frame_bulk = FrameBulk::with_frame_time("0.004".to_owned());
+    /// hltas.insert_lines_at_frame(2, [Line::FrameBulk(frame_bulk)]).unwrap();
+    ///
+    /// let frame_times: Vec<_> = hltas.frame_bulks().map(|x| x.frame_time.as_str()).collect();
+    /// assert_eq!(frame_times, ["0.001", "0.004", "0.001"]);
+    /// ```
+    #[inline]
+    pub fn insert_lines_at_frame(
+        &mut self,
+        frame: u64,
+        lines: impl IntoIterator<Item = Line>,
+    ) -> Result<(), EditError> {
+        edit::insert_lines_at_frame(self, frame, lines)
+    }
+
+    /// Deletes the given range of frames, which may span several frame bulks.
+    ///
+    /// Lines other than frame bulks inside the range are kept, except for override values applied
+    /// to the deleted frames. Returns an error if the range is invalid or goes past the end of the
+    /// script.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::HLTAS;
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5
+    /// ------b---|------|------|0.001|-|-|10";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.delete_frames(3..12).unwrap();
+    ///
+    /// let frame_counts: Vec<_> = hltas.frame_bulks().map(|x| x.frame_count.get()).collect();
+    /// assert_eq!(frame_counts, [3, 3]);
+    /// ```
+    #[inline]
+    pub fn delete_frames(&mut self, range: Range<u64>) -> Result<(), EditError> {
+        edit::delete_frames(self, range)
+    }
+
+    /// Sets the yaw angle of the given range of frames.
+    ///
+    /// Frame bulks that strafe are changed to strafe towards `yaw`, keeping line strafing, and
+    /// other frame bulks are changed to set the yaw. Returns an error if the range is invalid or
+    /// goes past the end of the script, or if a frame bulk in the range uses constant yawspeed
+    /// strafing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::AutoMovement, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ------b---|------|------|0.001|-|-|5";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.set_yaw(4..5, 90.).unwrap();
+    ///
+    /// let movement = hltas.frame_bulks().last().unwrap().auto_actions.movement;
+    /// assert_eq!(movement, Some(AutoMovement::SetYaw(90.)));
+    /// ```
+    #[inline]
+    pub fn set_yaw(&mut self, range: Range<u64>, yaw: f32) -> Result<(), EditError> {
+        edit::set_yaw(self, range, yaw)
+    }
+
+    /// Cleans up the script without changing what any of its frames do.
+    ///
+    /// Merges consecutive equivalent frame bulks and removes lines that have no effect. See the
+    /// [`normalize`](crate::normalize) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{normalize::Options, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// buttons
+    /// ------b---|------|------|0.001|-|-|5
+    /// // Comment
+    /// ------b---|------|------|0.001|-|-|10";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.normalize(Options {
+    ///     remove_comments: true,
+    /// });
+    ///
+    /// assert_eq!(hltas.lines.len(), 1);
+    /// assert_eq!(hltas.frame_bulks().next().unwrap().frame_count.get(), 15);
+    /// ```
+    #[inline]
+    pub fn normalize(&mut self, options: normalize::Options) {
+        normalize::normalize(self, options)
+    }
+
+    /// Converts every frame bulk to the given frame time, keeping the timing in seconds.
+    ///
+    /// Rescales frame counts and left-right strafing counts, resamples override lines and leaves
+    /// `change` lines as is. Returns every place where rounding to whole frames changed a
+    /// duration. See the [`retime`](crate::retime) module for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame time or the frame time of a frame bulk isn't a valid
+    /// non-negative decimal number, if a frame bulk would have too many frames, or if a duration
+    /// is too large to be represented. The script is left unchanged in this case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::Line, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// pitch_override 1 2 3 4 5 6 7 8
+    /// ----------|------|------|0.001|-|-|8";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// let roundings = hltas.retime("0.004").unwrap();
+    /// assert!(roundings.is_empty());
+    ///
+    /// assert_eq!(hltas.lines[0], Line::PitchOverride(vec![3., 7.]));
+    ///
+    /// let frame_bulk = hltas.frame_bulks().next().unwrap();
+    /// assert_eq!(frame_bulk.frame_time, "0.004");
+    /// assert_eq!(frame_bulk.frame_count.get(), 2);
+    /// ```
+    #[inline]
+    pub fn retime(&mut self, frame_time: &str) -> Result<Vec<Rounding>, RetimeError> {
+        retime::retime(self, frame_time)
+    }
+
+    /// Mirrors the script across a line going through the origin at the given yaw angle.
+    ///
+    /// Transforms yaw angles and coordinates and swaps left and right. See the
+    /// [`transform`](crate::transform) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::AutoMovement, types::StrafeDir, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// s00-------|------|------|0.001|-|-|5
+    /// ----------|------|------|0.001|30|-|5";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.mirror(0.);
+    ///
+    /// let mut movements = hltas.frame_bulks().map(|x| x.auto_actions.movement.unwrap());
+    /// assert!(matches!(movements.next(), Some(AutoMovement::Strafe(s)) if s.dir == StrafeDir::Right));
+    /// assert_eq!(movements.next(), Some(AutoMovement::SetYaw(-30.)));
+    /// ```
+    #[inline]
+    pub fn mirror(&mut self, axis: f32) {
+        transform::mirror(self, axis)
+    }
+
+    /// Rotates the script counter-clockwise around the origin by the given angle in degrees.
+    ///
+    /// Transforms yaw angles and coordinates. See the [`transform`](crate::transform) module for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::AutoMovement, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// ----------|------|------|0.001|30|-|5";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.rotate(90.);
+    ///
+    /// let movement = hltas.frame_bulks().next().unwrap().auto_actions.movement;
+    /// assert_eq!(movement, Some(AutoMovement::SetYaw(120.)));
+    /// ```
+    #[inline]
+    pub fn rotate(&mut self, angle: f32) {
+        transform::rotate(self, angle)
+    }
+
+    /// Moves every coordinate in the script by the given offset.
+    ///
+    /// Coordinates relative to an entity are left as is. See the [`transform`](crate::transform)
+    /// module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::{types::AutoMovement, types::StrafeDir, HLTAS};
+    ///
+    /// let contents = "\
+    /// version 1
+    /// frames
+    /// s04-------|------|------|0.001|100 200|-|5";
+    ///
+    /// let mut hltas = HLTAS::from_str(contents).unwrap();
+    /// hltas.translate(1., 2., 3.);
+    ///
+    /// let movement = hltas.frame_bulks().next().unwrap().auto_actions.movement;
+    /// let expected = StrafeDir::Point { x: 101., y: 202. };
+    /// assert!(matches!(movement, Some(AutoMovement::Strafe(s)) if s.dir == expected));
+    /// ```
+    #[inline]
+    pub fn translate(&mut self, x: f32, y: f32, z: f32) {
+        transform::translate(self, x, y, z)
+    }
+
     /// Returns an iterator over mutable frame bulks of the script.
     ///
     /// # Examples
@@ -656,7 +1188,7 @@ impl HLTAS {
     #[inline]
     pub fn frame_bulks_mut(
         &mut self,
-    ) -> iter::FilterMap<slice::IterMut<Line>, fn(&mut Line) -> Option<&mut FrameBulk>> {
+    ) -> iter::FilterMap<slice::IterMut<'_, Line>, fn(&mut Line) -> Option<&mut FrameBulk>> {
         self.lines.iter_mut().filter_map(Line::frame_bulk_mut)
     }
 }
@@ -739,6 +1271,52 @@ impl FrameBulk {
             console_command: None,
         }
     }
+
+    /// Returns the console command split into individual commands.
+    ///
+    /// See the [`console`](crate::console) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::types::FrameBulk;
+    ///
+    /// let mut frame_bulk = FrameBulk::with_frame_time("0.001".to_owned());
+    /// frame_bulk.console_command = Some("bxt_setpos 128 -1880 -536;//r_norefresh 1".to_owned());
+    ///
+    /// let console_command = frame_bulk.console_commands();
+    /// assert_eq!(console_command.names().collect::<Vec<_>>(), ["bxt_setpos"]);
+    /// ```
+    #[inline]
+    pub fn console_commands(&self) -> ConsoleCommand {
+        ConsoleCommand::parse(self.console_command.as_deref().unwrap_or(""))
+    }
+
+    /// Sets the console command, removing it if there are no commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate hltas;
+    /// use hltas::types::FrameBulk;
+    ///
+    /// let mut frame_bulk = FrameBulk::with_frame_time("0.001".to_owned());
+    /// frame_bulk.console_command = Some("stop;quit".to_owned());
+    ///
+    /// let mut console_command = frame_bulk.console_commands();
+    /// console_command.retain(|command| command.name() != Some("quit"));
+    /// frame_bulk.set_console_commands(&console_command);
+    /// assert_eq!(frame_bulk.console_command.as_deref(), Some("stop"));
+    ///
+    /// console_command.remove(0);
+    /// frame_bulk.set_console_commands(&console_command);
+    /// assert_eq!(frame_bulk.console_command, None);
+    /// ```
+    pub fn set_console_commands(&mut self, console_command: &ConsoleCommand) {
+        let text = console_command.to_string();
+        self.console_command = if text.is_empty() { None } else { Some(text) };
+    }
 }
 
 impl From<u32> for Times {
@@ -766,6 +1344,7 @@ impl From<Times> for u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::for_each_test_script;
 
     use std::{
         fs::{read_dir, read_to_string},
@@ -823,6 +1402,7 @@ mod tests {
                 seeds: None,
                 hlstrafe_version: Some(NonZeroU32::new(1).unwrap()),
                 load_command: None,
+                custom: Vec::new(),
             },
             lines: vec![
                 Line::FrameBulk(FrameBulk {
@@ -913,6 +1493,136 @@ mod tests {
         assert_eq!(hltas, gt);
     }
 
+    #[test]
+    fn custom_properties() {
+        let contents = read_to_string("test-data/parse/custom_properties.hltas").unwrap();
+        let hltas = HLTAS::from_str(&contents).unwrap();
+
+        let custom = vec![
+            ("runner".to_owned(), "someone".to_owned()),
+            ("route_revision".to_owned(), "12".to_owned()),
+            ("runner".to_owned(), "someone else".to_owned()),
+        ];
+        assert_eq!(hltas.properties.custom, custom);
+
+        let mut output = Vec::new();
+        hltas.to_writer(&mut output).unwrap();
+
+        let hltas = HLTAS::from_str(from_utf8(&output).unwrap()).unwrap();
+        assert_eq!(hltas.properties.custom, custom);
+    }
+
+    #[test]
+    fn unknown_lines() {
+        let contents = "version 1\nframes\n\n  future_line a\r\n//x\nseed 1\nfuture|line";
+        assert!(HLTAS::from_str(contents).is_err());
+
+        let (hltas, unknown_lines) = HLTAS::from_str_lenient(contents).unwrap();
+        assert_eq!(
+            hltas.lines,
+            [
+                Line::Unknown("future_line a".to_owned()),
+                Line::Comment("x".to_owned()),
+                Line::SharedSeed(1),
+                Line::Unknown("future|line".to_owned()),
+            ]
+        );
+        assert_eq!(
+            unknown_lines,
+            [
+                read::UnknownLine { index: 0, line: 3 },
+                read::UnknownLine { index: 3, line: 6 }
+            ]
+        );
+
+        let mut output = Vec::new();
+        hltas.to_writer(&mut output).unwrap();
+        let (hltas_2, _) = HLTAS::from_str_lenient(from_utf8(&output).unwrap()).unwrap();
+        assert_eq!(hltas, hltas_2);
+    }
+
+    #[test]
+    fn lenient_still_reports_invalid_values() {
+        let contents = "version 1\nframes\nsave \nfuture_line";
+        let err = HLTAS::from_str_lenient(contents).unwrap_err();
+        assert_eq!(err.context, Some(read::Context::NoSaveName));
+
+        for line in [
+            "seed x",
+            "change yaw to 1",
+            "target_yaw_override a",
+            "----------|------|------|0.001|-|-|",
+            "s03-------|------|------|abc|-|-|5",
+        ] {
+            let contents = format!("version 1\nframes\n{}", line);
+            assert!(HLTAS::from_str_lenient(&contents).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn lenient_matches_whole_keywords() {
+        let lines = [
+            "seedling 5",
+            "strafing_mode yaw",
+            "reset_all 5",
+            "target_yaw_override2 1",
+            "change_foo 1",
+            "buttonsx",
+        ];
+
+        let contents = format!("version 1\nframes\n{}", lines.join("\n"));
+        let (hltas, unknown_lines) = HLTAS::from_str_lenient(&contents).unwrap();
+
+        let expected: Vec<_> = lines
+            .iter()
+            .map(|&line| Line::Unknown(line.to_owned()))
+            .collect();
+        assert_eq!(hltas.lines, expected);
+        assert_eq!(unknown_lines.len(), lines.len());
+
+        // The known keywords themselves still parse.
+        let (hltas, _) =
+            HLTAS::from_str_lenient("version 1\nframes\nseed 5\nstrafing yaw\nbuttons").unwrap();
+        assert_eq!(
+            hltas.lines,
+            [
+                Line::SharedSeed(5),
+                Line::VectorialStrafing(false),
+                Line::Buttons(Buttons::Reset),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovering_matches_strict() {
+        for_each_test_script(|_, contents| {
+            let (hltas, errors) = HLTAS::from_str_recovering(contents);
+            assert!(errors.is_empty());
+            assert_eq!(hltas, HLTAS::from_str(contents).unwrap());
+        });
+    }
+
+    #[test]
+    fn recovering_reports_every_error() {
+        let contents = read_to_string("test-data/error/multiple-errors.hltas").unwrap();
+        let (hltas, errors) = HLTAS::from_str_recovering(&contents);
+
+        let errors: Vec<_> = errors.iter().map(|e| (e.line(), e.context)).collect();
+        assert_eq!(
+            errors,
+            [
+                (2, Some(read::Context::InvalidSeeds)),
+                (5, Some(read::Context::NoSaveName)),
+                (7, Some(read::Context::BothAutoJumpAndDuckTap)),
+                (8, Some(read::Context::ErrorParsingLine)),
+                (10, Some(read::Context::NoYaw)),
+            ]
+        );
+
+        assert_eq!(hltas.properties.demo.as_deref(), Some("test"));
+        assert_eq!(hltas.lines.len(), 3);
+    }
+
     #[test]
     fn write_to_too_small_buffer() {
         let contents = read_to_string("test-data/parse/bhop.hltas").unwrap();
@@ -971,6 +1681,15 @@ mod tests {
     test_error! { error_max_accel_yaw_offset_single_yaw, "max-accel-yaw-offset-single-yaw", NoYawspeed }
     test_error! { error_max_accel_yaw_offset_no_accel, "max-accel-yaw-offset-no-accel", NoYawOffsetAcceleration }
 
+    test_error! { error_too_few_dashes_field_0, "too-few-dashes-field-0", TooFewAutoActionsCharacters }
+    test_error! { error_invalid_frame_count, "invalid-frame-count", InvalidFrameCount }
+    test_error! { error_change_invalid_target, "change-invalid-target", InvalidChangeTarget }
+    test_error! { error_change_no_duration, "change-no-duration", NoChangeDuration }
+    test_error! { error_override_invalid_values, "override-invalid-values", InvalidOverrideValues }
+    test_error! { error_look_at_invalid_entity, "look-at-invalid-entity", InvalidLookAtEntity }
+    test_error! { error_invalid_seeds, "invalid-seeds", InvalidSeeds }
+    test_error! { error_no_frames, "no-frames", NoFrames }
+
     #[cfg(feature = "proptest1")]
     proptest! {
         #[test]
@@ -0,0 +1,658 @@
+//! Checking that a script can be written and read back.
+//!
+//! The types allow values that the `.hltas` format can't represent, such as a comment with a line
+//! break or a non-numeric frame time. Writing such a script succeeds, but produces a file that
+//! fails to parse or parses into something else. [`HLTAS::validate()`] finds such values, and
+//! [`HLTAS::to_writer_checked()`] refuses to write them.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate hltas;
+//! use hltas::{
+//!     types::Line,
+//!     validate::{Field, Problem},
+//!     HLTAS,
+//! };
+//!
+//! let mut hltas = HLTAS::default();
+//! hltas.lines.push(Line::Save("my save".to_owned()));
+//!
+//! let error = hltas.validate().unwrap_err();
+//! assert_eq!(error.line_index, Some(0));
+//! assert_eq!(error.field, Field::SaveName);
+//! assert_eq!(error.problem, Problem::Whitespace);
+//! ```
+//!
+//! [`HLTAS::validate()`]: crate::HLTAS::validate
+//! [`HLTAS::to_writer_checked()`]: crate::HLTAS::to_writer_checked
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+};
+
+use nom::{combinator::all_consuming, number::complete::recognize_float};
+
+use crate::{
+    read::{self, Context},
+    types::{
+        AutoMovement, FrameBulk, Line, Properties, StrafeDir, StrafeSettings, StrafeType,
+        VectorialStrafingConstraints,
+    },
+    HLTAS,
+};
+
+/// Part of the script that holds an invalid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// The name or the value of the property with this name.
+    Property(String),
+    /// Strafing settings in the auto-actions of a frame bulk.
+    Strafing,
+    /// Frame time of a frame bulk.
+    FrameTime,
+    /// Yaw field of a frame bulk, which holds the yaw, point, count or yawspeed.
+    YawField,
+    /// Pitch of a frame bulk.
+    Pitch,
+    /// Console command of a frame bulk.
+    ConsoleCommand,
+    /// Name of a `save` line.
+    SaveName,
+    /// Value of an `lgagstminspeed` line.
+    LGAGSTMinSpeed,
+    /// Text of a comment.
+    Comment,
+    /// Values of a `target_yaw` line.
+    Constraints,
+    /// Final value of a `change` line.
+    ChangeFinalValue,
+    /// Duration of a `change` line.
+    ChangeDuration,
+    /// Values of an override line.
+    OverrideValues,
+    /// Text of an unrecognized line.
+    Text,
+}
+
+/// What is wrong with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// Constant yawspeed strafing is used with a direction other than left, right or best.
+    UnsupportedConstantYawspeedDir,
+    /// The yawspeed is negative.
+    NegativeYawspeed,
+    /// The text contains a line break.
+    LineBreak,
+    /// The text contains whitespace.
+    Whitespace,
+    /// The value is empty.
+    Empty,
+    /// The frame time is not a number.
+    InvalidFrameTime,
+    /// The number is infinite or NaN.
+    NonFinite,
+    /// The property name contains characters other than ASCII letters, digits and underscores.
+    InvalidPropertyName,
+    /// The custom property has the name of a known property or of a header keyword, so it would
+    /// be read back as something else.
+    ReservedPropertyName,
+    /// The value starts or ends with whitespace, which is lost when reading it back.
+    SurroundingWhitespace,
+    /// The text of an unrecognized line would be read back as a known line, or as a known line with
+    /// invalid values.
+    KnownLine,
+}
+
+/// A value that can't be written as a valid script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Index of the offending line in [`HLTAS::lines`], or `None` if the value is in the
+    /// properties.
+    pub line_index: Option<usize>,
+    /// Part of the script that holds the value.
+    pub field: Field,
+    /// What is wrong with the value.
+    pub problem: Problem,
+}
+
+impl ValidationError {
+    /// Returns the parsing error context for the error the parser would report when reading the
+    /// written value back, if there is a matching one.
+    ///
+    /// Values with line breaks or whitespace split into several lines or fields, so the parser
+    /// reports an error elsewhere, if at all.
+    pub fn context(&self) -> Option<Context> {
+        Some(match (self.problem, &self.field) {
+            (Problem::UnsupportedConstantYawspeedDir, _) => Context::UnsupportedConstantYawspeedDir,
+            (Problem::NegativeYawspeed, _) => Context::NegativeYawspeed,
+            (Problem::InvalidFrameTime, _) => Context::InvalidFrameTime,
+            (Problem::Empty, Field::SaveName) => Context::NoSaveName,
+            (Problem::Empty | Problem::NonFinite, Field::OverrideValues) => {
+                Context::InvalidOverrideValues
+            }
+            (Problem::NonFinite, Field::YawField) => Context::InvalidYawField,
+            (Problem::NonFinite, Field::Pitch) => Context::InvalidPitch,
+            (Problem::NonFinite, Field::ChangeFinalValue) => Context::NoChangeFinalValue,
+            (Problem::NonFinite, Field::ChangeDuration) => Context::NoChangeDuration,
+            _ => return None,
+        })
+    }
+}
+
+/// Names that custom properties can't have: the known properties and the header keywords.
+const RESERVED_PROPERTY_NAMES: [&str; 8] = [
+    "version",
+    "frames",
+    "demo",
+    "save",
+    "seed",
+    "frametime0ms",
+    "hlstrafe_version",
+    "load_command",
+];
+
+/// Returns an error for the first value in the properties that can't be written.
+pub(crate) fn validate_properties(properties: &Properties) -> Result<(), ValidationError> {
+    let error = |name: &str, problem| ValidationError {
+        line_index: None,
+        field: Field::Property(name.to_owned()),
+        problem,
+    };
+
+    let known = [
+        ("demo", &properties.demo),
+        ("save", &properties.save),
+        ("frametime0ms", &properties.frametime_0ms),
+        ("load_command", &properties.load_command),
+    ];
+    for (name, value) in known {
+        if let Some(value) = value {
+            property_value(value).map_err(|problem| error(name, problem))?;
+        }
+    }
+
+    for (name, value) in &properties.custom {
+        let valid_name =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(error(name, Problem::InvalidPropertyName));
+        }
+
+        if RESERVED_PROPERTY_NAMES.contains(&name.as_str()) {
+            return Err(error(name, Problem::ReservedPropertyName));
+        }
+
+        property_value(value).map_err(|problem| error(name, problem))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the field and the problem of the first value in the line that can't be written.
+pub(crate) fn validate_line(line: &Line) -> Result<(), (Field, Problem)> {
+    match line {
+        Line::FrameBulk(frame_bulk) => validate_frame_bulk(frame_bulk),
+        Line::Save(name) => {
+            if name.is_empty() {
+                Err((Field::SaveName, Problem::Empty))
+            } else if has_line_break(name) {
+                Err((Field::SaveName, Problem::LineBreak))
+            } else if name.contains(char::is_whitespace) {
+                Err((Field::SaveName, Problem::Whitespace))
+            } else {
+                Ok(())
+            }
+        }
+        Line::LGAGSTMinSpeed(speed) => finite(Field::LGAGSTMinSpeed, &[*speed]),
+        Line::Comment(text) => no_line_break(Field::Comment, text),
+        Line::VectorialStrafingConstraints(constraints) => {
+            use VectorialStrafingConstraints::*;
+            let values = match *constraints {
+                VelocityYaw { tolerance }
+                | AvgVelocityYaw { tolerance }
+                | VelocityYawLocking { tolerance } => vec![tolerance],
+                Yaw { yaw, tolerance } => vec![yaw, tolerance],
+                YawRange { from, to } => vec![from, to],
+                LookAt { x, y, z, .. } => vec![x, y, z],
+            };
+            finite(Field::Constraints, &values)
+        }
+        Line::Change(change) => {
+            finite(Field::ChangeFinalValue, &[change.final_value])?;
+            finite(Field::ChangeDuration, &[change.over])
+        }
+        Line::TargetYawOverride(values)
+        | Line::RenderYawOverride(values)
+        | Line::PitchOverride(values)
+        | Line::RenderPitchOverride(values) => {
+            if values.is_empty() {
+                Err((Field::OverrideValues, Problem::Empty))
+            } else {
+                finite(Field::OverrideValues, values)
+            }
+        }
+        Line::Unknown(text) => {
+            if text.is_empty() {
+                return Err((Field::Text, Problem::Empty));
+            }
+
+            no_line_break(Field::Text, text)?;
+            no_surrounding_whitespace(Field::Text, text)?;
+
+            match read::line_lenient(text) {
+                Ok((_, Line::Unknown(_))) => Ok(()),
+                _ => Err((Field::Text, Problem::KnownLine)),
+            }
+        }
+        Line::SharedSeed(_)
+        | Line::Buttons(_)
+        | Line::Reset { .. }
+        | Line::VectorialStrafing(_) => Ok(()),
+    }
+}
+
+/// Returns the field and the problem of the first value in the frame bulk that can't be written.
+pub(crate) fn validate_frame_bulk(frame_bulk: &FrameBulk) -> Result<(), (Field, Problem)> {
+    validate_movement(frame_bulk.auto_actions.movement)?;
+
+    if all_consuming(recognize_float::<_, ()>)(frame_bulk.frame_time.as_str()).is_err() {
+        return Err((Field::FrameTime, Problem::InvalidFrameTime));
+    }
+
+    if let Some(pitch) = frame_bulk.pitch {
+        finite(Field::Pitch, &[pitch])?;
+    }
+
+    if let Some(command) = &frame_bulk.console_command {
+        no_line_break(Field::ConsoleCommand, command)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the field and the problem of the first value in the movement that can't be written.
+pub(crate) fn validate_movement(movement: Option<AutoMovement>) -> Result<(), (Field, Problem)> {
+    match movement {
+        None => {}
+        Some(AutoMovement::SetYaw(yaw)) => finite(Field::YawField, &[yaw])?,
+        Some(AutoMovement::Strafe(StrafeSettings { type_, dir })) => {
+            match type_ {
+                StrafeType::ConstYawspeed(yawspeed) => {
+                    if !matches!(dir, StrafeDir::Left | StrafeDir::Right | StrafeDir::Best) {
+                        return Err((Field::Strafing, Problem::UnsupportedConstantYawspeedDir));
+                    }
+
+                    finite(Field::YawField, &[yawspeed])?;
+                    // Negative zero is written with a minus sign too.
+                    if yawspeed.is_sign_negative() {
+                        return Err((Field::YawField, Problem::NegativeYawspeed));
+                    }
+                }
+                StrafeType::MaxAccelYawOffset {
+                    start,
+                    target,
+                    accel,
+                } => finite(Field::YawField, &[start, target, accel])?,
+                StrafeType::MaxAccel
+                | StrafeType::MaxAngle
+                | StrafeType::MaxDeccel
+                | StrafeType::ConstSpeed => {}
+            }
+
+            match dir {
+                StrafeDir::Yaw(yaw) | StrafeDir::Line { yaw } => finite(Field::YawField, &[yaw])?,
+                StrafeDir::Point { x, y } => finite(Field::YawField, &[x, y])?,
+                StrafeDir::Left
+                | StrafeDir::Right
+                | StrafeDir::Best
+                | StrafeDir::LeftRight(_)
+                | StrafeDir::RightLeft(_) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn has_line_break(text: &str) -> bool {
+    text.contains(['\n', '\r'])
+}
+
+fn no_line_break(field: Field, text: &str) -> Result<(), (Field, Problem)> {
+    if has_line_break(text) {
+        Err((field, Problem::LineBreak))
+    } else {
+        Ok(())
+    }
+}
+
+fn has_surrounding_whitespace(text: &str) -> bool {
+    text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace)
+}
+
+fn no_surrounding_whitespace(field: Field, text: &str) -> Result<(), (Field, Problem)> {
+    if has_surrounding_whitespace(text) {
+        Err((field, Problem::SurroundingWhitespace))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the problem with the value of a property, if any.
+fn property_value(value: &str) -> Result<(), Problem> {
+    if has_line_break(value) {
+        Err(Problem::LineBreak)
+    } else if has_surrounding_whitespace(value) {
+        Err(Problem::SurroundingWhitespace)
+    } else {
+        Ok(())
+    }
+}
+
+fn finite(field: Field, values: &[f32]) -> Result<(), (Field, Problem)> {
+    if values.iter().all(|x| x.is_finite()) {
+        Ok(())
+    } else {
+        Err((field, Problem::NonFinite))
+    }
+}
+
+/// Returns an error for the first value in the script that can't be written.
+pub(crate) fn validate(hltas: &HLTAS) -> Result<(), ValidationError> {
+    validate_properties(&hltas.properties)?;
+
+    for (line_index, line) in hltas.lines.iter().enumerate() {
+        validate_line(line).map_err(|(field, problem)| ValidationError {
+            line_index: Some(line_index),
+            field,
+            problem,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Property(name) => write!(f, "property {:?}", name),
+            Field::Strafing => write!(f, "strafing settings"),
+            Field::FrameTime => write!(f, "frame time"),
+            Field::YawField => write!(f, "yaw field"),
+            Field::Pitch => write!(f, "pitch"),
+            Field::ConsoleCommand => write!(f, "console command"),
+            Field::SaveName => write!(f, "save name"),
+            Field::LGAGSTMinSpeed => write!(f, "lgagstminspeed value"),
+            Field::Comment => write!(f, "comment"),
+            Field::Constraints => write!(f, "vectorial strafing constraints"),
+            Field::ChangeFinalValue => write!(f, "change final value"),
+            Field::ChangeDuration => write!(f, "change duration"),
+            Field::OverrideValues => write!(f, "override values"),
+            Field::Text => write!(f, "unrecognized line"),
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnsupportedConstantYawspeedDir => write!(
+                f,
+                "constant yawspeed strafing only supports left, right and best directions"
+            ),
+            Problem::NegativeYawspeed => write!(f, "the yawspeed is negative"),
+            Problem::LineBreak => write!(f, "contains a line break"),
+            Problem::Whitespace => write!(f, "contains whitespace"),
+            Problem::Empty => write!(f, "is empty"),
+            Problem::InvalidFrameTime => write!(f, "the frame time is not a number"),
+            Problem::NonFinite => write!(f, "contains an infinite or NaN number"),
+            Problem::InvalidPropertyName => write!(
+                f,
+                "the name must consist of ASCII letters, digits and underscores"
+            ),
+            Problem::ReservedPropertyName => {
+                write!(f, "the name is reserved for a known property or keyword")
+            }
+            Problem::SurroundingWhitespace => write!(f, "starts or ends with whitespace"),
+            Problem::KnownLine => write!(f, "would be read back as a known line"),
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.line_index {
+            Some(index) => write!(f, "invalid {} in line {}", self.field, index)?,
+            None => write!(f, "invalid {}", self.field)?,
+        }
+        write!(f, ": {}", self.problem)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use super::*;
+    use crate::test_util::for_each_test_script;
+
+    fn frame_bulk() -> FrameBulk {
+        FrameBulk::with_frame_time("0.001".to_owned())
+    }
+
+    fn strafe(type_: StrafeType, dir: StrafeDir) -> Line {
+        let mut frame_bulk = frame_bulk();
+        frame_bulk.auto_actions.movement =
+            Some(AutoMovement::Strafe(StrafeSettings { type_, dir }));
+        Line::FrameBulk(frame_bulk)
+    }
+
+    fn check(line: Line) -> Result<(), (Field, Problem)> {
+        let result = validate_line(&line);
+
+        // Everything that passes must survive a round trip.
+        if result.is_ok() {
+            let hltas = HLTAS {
+                properties: Properties::default(),
+                lines: vec![line.clone()],
+            };
+            let mut buf = Vec::new();
+            hltas.to_writer(&mut buf).unwrap();
+            let written = String::from_utf8(buf).unwrap();
+            assert_eq!(HLTAS::from_str(&written).unwrap().lines, [line]);
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_data_is_valid() {
+        for_each_test_script(|_, contents| {
+            assert_eq!(HLTAS::from_str(contents).unwrap().validate(), Ok(()));
+        });
+    }
+
+    #[test]
+    fn constant_yawspeed() {
+        use StrafeDir::*;
+        let yawspeed = StrafeType::ConstYawspeed(10.);
+
+        for dir in [Left, Right, Best] {
+            assert_eq!(check(strafe(yawspeed, dir)), Ok(()));
+        }
+
+        let count = NonZeroU32::new(1).unwrap();
+        for dir in [
+            Yaw(0.),
+            Point { x: 0., y: 0. },
+            Line { yaw: 0. },
+            LeftRight(count),
+            RightLeft(count),
+        ] {
+            assert_eq!(
+                check(strafe(yawspeed, dir)),
+                Err((Field::Strafing, Problem::UnsupportedConstantYawspeedDir))
+            );
+        }
+
+        for yawspeed in [-1., -0.] {
+            assert_eq!(
+                check(strafe(StrafeType::ConstYawspeed(yawspeed), Left)),
+                Err((Field::YawField, Problem::NegativeYawspeed))
+            );
+        }
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(check(Line::Comment(" a // b".to_owned())), Ok(()));
+        assert_eq!(
+            check(Line::Comment("a\nb".to_owned())),
+            Err((Field::Comment, Problem::LineBreak))
+        );
+
+        let mut command = frame_bulk();
+        command.console_command = Some("echo a; echo b".to_owned());
+        assert_eq!(check(Line::FrameBulk(command.clone())), Ok(()));
+        command.console_command = Some("echo a\recho b".to_owned());
+        assert_eq!(
+            check(Line::FrameBulk(command)),
+            Err((Field::ConsoleCommand, Problem::LineBreak))
+        );
+
+        assert_eq!(check(Line::Save("a".to_owned())), Ok(()));
+        assert_eq!(
+            check(Line::Save("a b".to_owned())),
+            Err((Field::SaveName, Problem::Whitespace))
+        );
+        assert_eq!(
+            check(Line::Save(String::new())),
+            Err((Field::SaveName, Problem::Empty))
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        for frame_time in ["0.001", "1", "1e-3", ".5"] {
+            let mut frame_bulk = frame_bulk();
+            frame_bulk.frame_time = frame_time.to_owned();
+            assert_eq!(check(Line::FrameBulk(frame_bulk)), Ok(()));
+        }
+
+        for frame_time in ["", "abc", "0.001|", "0.001 "] {
+            let mut frame_bulk = frame_bulk();
+            frame_bulk.frame_time = frame_time.to_owned();
+            assert_eq!(
+                check(Line::FrameBulk(frame_bulk)),
+                Err((Field::FrameTime, Problem::InvalidFrameTime))
+            );
+        }
+
+        let mut frame_bulk = frame_bulk();
+        frame_bulk.pitch = Some(f32::NAN);
+        assert_eq!(
+            check(Line::FrameBulk(frame_bulk)),
+            Err((Field::Pitch, Problem::NonFinite))
+        );
+
+        assert_eq!(
+            check(Line::PitchOverride(Vec::new())),
+            Err((Field::OverrideValues, Problem::Empty))
+        );
+        assert_eq!(
+            check(Line::RenderYawOverride(vec![1., f32::INFINITY])),
+            Err((Field::OverrideValues, Problem::NonFinite))
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let mut hltas = HLTAS::default();
+        hltas.properties.demo = Some("a\nb".to_owned());
+        assert_eq!(
+            hltas.validate(),
+            Err(ValidationError {
+                line_index: None,
+                field: Field::Property("demo".to_owned()),
+                problem: Problem::LineBreak,
+            })
+        );
+
+        let custom = |name: &str, value: &str| {
+            let mut hltas = HLTAS::default();
+            hltas
+                .properties
+                .custom
+                .push((name.to_owned(), value.to_owned()));
+            hltas.validate().map_err(|error| error.problem)
+        };
+        assert_eq!(custom("runner", "someone else"), Ok(()));
+        assert_eq!(custom("a b", "1"), Err(Problem::InvalidPropertyName));
+        for name in ["frames", "version", "demo", "seed", "load_command"] {
+            assert_eq!(custom(name, "1"), Err(Problem::ReservedPropertyName));
+        }
+        assert_eq!(custom("runner", " a"), Err(Problem::SurroundingWhitespace));
+        assert_eq!(custom("runner", "a\t"), Err(Problem::SurroundingWhitespace));
+
+        let mut hltas = HLTAS::default();
+        hltas.properties.demo = Some(" a".to_owned());
+        assert_eq!(
+            hltas.validate().unwrap_err().problem,
+            Problem::SurroundingWhitespace
+        );
+    }
+
+    #[test]
+    fn unknown_lines() {
+        let check = |text: &str| {
+            let line = Line::Unknown(text.to_owned());
+            let result = validate_line(&line);
+
+            // Everything that passes must survive a round trip.
+            if result.is_ok() {
+                let hltas = HLTAS {
+                    properties: Properties::default(),
+                    lines: vec![line.clone()],
+                };
+                let mut buf = Vec::new();
+                hltas.to_writer(&mut buf).unwrap();
+                let written = String::from_utf8(buf).unwrap();
+                assert_eq!(HLTAS::from_str_lenient(&written).unwrap().0.lines, [line]);
+            }
+
+            result.map_err(|(_, problem)| problem)
+        };
+
+        assert_eq!(check("future_line 1 2"), Ok(()));
+        assert_eq!(check("seedling x"), Ok(()));
+        assert_eq!(check(""), Err(Problem::Empty));
+        assert_eq!(check("a\nb"), Err(Problem::LineBreak));
+        assert_eq!(check(" future_line"), Err(Problem::SurroundingWhitespace));
+        assert_eq!(check("future_line "), Err(Problem::SurroundingWhitespace));
+        assert_eq!(check("seed 5"), Err(Problem::KnownLine));
+        assert_eq!(check("seed x"), Err(Problem::KnownLine));
+        assert_eq!(check("// comment"), Err(Problem::KnownLine));
+        assert_eq!(
+            check("----------|------|------|0.001|-|-|5"),
+            Err(Problem::KnownLine)
+        );
+    }
+
+    #[test]
+    fn context() {
+        let error = ValidationError {
+            line_index: Some(0),
+            field: Field::OverrideValues,
+            problem: Problem::Empty,
+        };
+        assert_eq!(error.context(), Some(Context::InvalidOverrideValues));
+        assert_eq!(
+            error.to_string(),
+            "invalid override values in line 0: is empty"
+        );
+    }
+}
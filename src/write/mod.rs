@@ -10,7 +10,13 @@ use cookie_factory::{
     GenError, SerializeFn, WriteContext,
 };
 
-use crate::types::*;
+use crate::{
+    source_map::{Counting, Location, SourceMap},
+    types::*,
+};
+
+mod writer;
+pub use writer::{Writer, WriterError};
 
 fn property<S: AsRef<str>, W: Write>(name: S, value: impl SerializeFn<W>) -> impl SerializeFn<W> {
     tuple((string(name), string(" "), value))
@@ -423,6 +429,7 @@ fn line<W: Write>(line: &Line) -> impl SerializeFn<W> + '_ {
             string("render_pitch_override"),
             many_ref(pitches, |pitch| pair(string(" "), display(pitch))),
         ))(out),
+        Line::Unknown(text) => string(text)(out),
     }
 }
 
@@ -494,19 +501,48 @@ where
 /// assert_eq!(buf, contents.as_bytes());
 /// ```
 pub fn gen_hltas<W: Write>(w: W, hltas: &HLTAS) -> Result<(), GenError> {
+    let w = gen_header(w, &hltas.properties)?;
+    gen_lines(w, &hltas.lines)?;
+    Ok(())
+}
+
+/// Prints `HLTAS` into writer, returning the location of every line in the output.
+///
+/// See [`HLTAS::to_writer_with_source_map`] for an example.
+pub fn gen_hltas_with_source_map<W: Write>(w: W, hltas: &HLTAS) -> Result<SourceMap, GenError> {
+    let mut w = gen_header(Counting::new(w), &hltas.properties)?;
+    let mut source_map = SourceMap::default();
+
+    for l in &hltas.lines {
+        let start = w.bytes;
+        let line_number = w.lines;
+        w = gen_simple(line(l), w)?;
+        source_map.push(Location {
+            line: line_number,
+            column: 0,
+            span: start..w.bytes,
+        });
+        w = gen_simple(string("\n"), w)?;
+    }
+
+    Ok(source_map)
+}
+
+/// Prints the version, the properties and the `frames` line, returning the writer.
+fn gen_header<W: Write>(w: W, properties: &Properties) -> Result<W, GenError> {
     let mut w = gen_simple(string("version 1\n"), w)?;
 
-    if let Some(demo) = hltas.properties.demo.as_deref() {
+    if let Some(demo) = properties.demo.as_deref() {
         w = gen_simple(pair(property("demo", string(demo)), string("\n")), w)?;
     }
-    if let Some(save) = hltas.properties.save.as_deref() {
+    if let Some(save) = properties.save.as_deref() {
         w = gen_simple(pair(property("save", string(save)), string("\n")), w)?;
     }
-    if let Some(Seeds { shared, non_shared }) = hltas.properties.seeds {
+    if let Some(Seeds { shared, non_shared }) = properties.seeds {
         let seeds = tuple((display(shared), string(" "), display(non_shared)));
         w = gen_simple(pair(property("seed", seeds), string("\n")), w)?;
     }
-    if let Some(frametime_0ms) = hltas.properties.frametime_0ms.as_deref() {
+    if let Some(frametime_0ms) = properties.frametime_0ms.as_deref() {
         w = gen_simple(
             pair(
                 property("frametime0ms", string(frametime_0ms)),
@@ -515,7 +551,7 @@ pub fn gen_hltas<W: Write>(w: W, hltas: &HLTAS) -> Result<(), GenError> {
             w,
         )?;
     }
-    if let Some(hlstrafe_version) = hltas.properties.hlstrafe_version {
+    if let Some(hlstrafe_version) = properties.hlstrafe_version {
         w = gen_simple(
             pair(
                 property("hlstrafe_version", display(hlstrafe_version)),
@@ -524,16 +560,16 @@ pub fn gen_hltas<W: Write>(w: W, hltas: &HLTAS) -> Result<(), GenError> {
             w,
         )?;
     }
-    if let Some(load_command) = hltas.properties.load_command.as_deref() {
+    if let Some(load_command) = properties.load_command.as_deref() {
         w = gen_simple(
             pair(property("load_command", string(load_command)), string("\n")),
             w,
         )?;
     }
 
-    let w = gen_simple(string("frames\n"), w)?;
-
-    gen_lines(w, &hltas.lines)?;
+    for (name, value) in &properties.custom {
+        w = gen_simple(pair(property(name, string(value)), string("\n")), w)?;
+    }
 
-    Ok(())
+    gen_simple(string("frames\n"), w)
 }
@@ -0,0 +1,305 @@
+//! Streaming writing of `.hltas` scripts.
+
+use std::{
+    borrow::Borrow,
+    error,
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+};
+
+use cookie_factory::{gen_simple, GenError};
+
+use super::{gen_header, line_nl};
+use crate::{
+    types::{Line, Properties},
+    validate::{validate_line, validate_properties, ValidationError},
+};
+
+/// Error returned by [`Writer`].
+#[derive(Debug)]
+pub enum WriterError {
+    /// Writing to the underlying writer failed.
+    Io(io::Error),
+    /// Generating the output failed for a reason other than I/O.
+    Gen(GenError),
+    /// A value can't be written as a valid script. Only returned in checked mode.
+    Invalid(ValidationError),
+}
+
+/// Streaming writer of `.hltas` scripts.
+///
+/// The header is written when the writer is created, and lines are then written one at a time as
+/// they are pushed, so the whole script never needs to be kept in memory.
+///
+/// Every line results in several small writes, so if the underlying writer is unbuffered, like a
+/// [`File`](std::fs::File), consider wrapping it in a [`BufWriter`](std::io::BufWriter).
+///
+/// # Examples
+///
+/// ```
+/// # extern crate hltas;
+/// # fn foo() -> Result<(), hltas::write::WriterError> {
+/// use hltas::{
+///     types::{FrameBulk, Line, Properties},
+///     write::Writer,
+/// };
+///
+/// let properties = Properties {
+///     demo: Some("test".to_owned()),
+///     ..Properties::default()
+/// };
+///
+/// let mut writer = Writer::new(Vec::new(), &properties)?;
+/// writer.write_line(&Line::FrameBulk(FrameBulk::with_frame_time("0.001".to_owned())))?;
+/// writer.write_line(&Line::Reset { non_shared_seed: 1234 })?;
+///
+/// let buf = writer.into_inner()?;
+/// assert_eq!(
+///     buf,
+///     b"version 1\ndemo test\nframes\n----------|------|------|0.001|-|-|1\nreset 1234\n"
+/// );
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+///
+/// Lines can also be produced on other threads and sent to the writer through a channel:
+///
+/// ```
+/// # extern crate hltas;
+/// # fn foo() -> Result<(), hltas::write::WriterError> {
+/// use std::{sync::mpsc, thread};
+///
+/// use hltas::{
+///     types::{Line, Properties},
+///     write::Writer,
+/// };
+///
+/// let (sender, receiver) = mpsc::channel();
+/// let generator = thread::spawn(move || {
+///     for seed in 0..3 {
+///         sender.send(Line::Reset { non_shared_seed: seed }).unwrap();
+///     }
+/// });
+///
+/// let mut writer = Writer::new(Vec::new(), &Properties::default())?;
+/// assert_eq!(writer.write_lines(receiver)?, 3);
+/// generator.join().unwrap();
+///
+/// let buf = writer.into_inner()?;
+/// assert_eq!(buf, b"version 1\nframes\nreset 0\nreset 1\nreset 2\n");
+/// # Ok(())
+/// # }
+/// # foo().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    inner: W,
+    lines_written: usize,
+    checked: bool,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new `Writer`, writing the version, the properties and the `frames` line.
+    pub fn new(inner: W, properties: &Properties) -> Result<Self, WriterError> {
+        let inner = gen_header(inner, properties)?;
+        Ok(Self {
+            inner,
+            lines_written: 0,
+            checked: false,
+        })
+    }
+
+    /// Creates a new `Writer` in checked mode, writing the version, the properties and the
+    /// `frames` line.
+    ///
+    /// In checked mode, the properties and every line are validated before they are written, and
+    /// values that can't be read back return [`WriterError::Invalid`] without writing anything.
+    /// The line index in the error is the number of lines written before it.
+    pub fn new_checked(inner: W, properties: &Properties) -> Result<Self, WriterError> {
+        validate_properties(properties).map_err(WriterError::Invalid)?;
+        let mut writer = Self::new(inner, properties)?;
+        writer.checked = true;
+        Ok(writer)
+    }
+
+    /// Writes a newline-terminated [`Line`].
+    pub fn write_line(&mut self, line: &Line) -> Result<(), WriterError> {
+        if self.checked {
+            validate_line(line).map_err(|(field, problem)| {
+                WriterError::Invalid(ValidationError {
+                    line_index: Some(self.lines_written),
+                    field,
+                    problem,
+                })
+            })?;
+        }
+
+        gen_simple(line_nl(line), &mut self.inner)?;
+        self.lines_written += 1;
+        Ok(())
+    }
+
+    /// Writes all lines from an iterator, returning the number of lines written.
+    ///
+    /// This works with anything that yields [`Line`]s or references to them, including an
+    /// [`mpsc::Receiver`](std::sync::mpsc::Receiver), in which case the lines are written as they
+    /// arrive until all senders are dropped.
+    pub fn write_lines<L, I>(&mut self, lines: I) -> Result<usize, WriterError>
+    where
+        L: Borrow<Line>,
+        I: IntoIterator<Item = L>,
+    {
+        let mut count = 0;
+        for line in lines {
+            self.write_line(line.borrow())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), WriterError> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Returns the number of lines written so far, not counting the header.
+    #[inline]
+    pub fn lines_written(&self) -> usize {
+        self.lines_written
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Writing to it directly may produce an invalid script.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, WriterError> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl Display for WriterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WriterError::Io(e) => write!(f, "error writing the script: {}", e),
+            WriterError::Gen(e) => write!(f, "error generating the script: {:?}", e),
+            WriterError::Invalid(e) => write!(f, "error validating the script: {}", e),
+        }
+    }
+}
+
+impl error::Error for WriterError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WriterError::Io(e) => Some(e),
+            WriterError::Gen(_) => None,
+            WriterError::Invalid(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for WriterError {
+    #[inline]
+    fn from(x: io::Error) -> Self {
+        Self::Io(x)
+    }
+}
+
+impl From<GenError> for WriterError {
+    #[inline]
+    fn from(x: GenError) -> Self {
+        match x {
+            GenError::IoError(e) => Self::Io(e),
+            x => Self::Gen(x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::for_each_test_script, HLTAS};
+
+    #[test]
+    fn matches_gen_hltas() {
+        for_each_test_script(|path, contents| {
+            let hltas = HLTAS::from_str(contents).unwrap();
+
+            let mut expected = Vec::new();
+            hltas.to_writer(&mut expected).unwrap();
+
+            let mut writer = Writer::new(Vec::new(), &hltas.properties).unwrap();
+            assert_eq!(writer.write_lines(&hltas.lines).unwrap(), hltas.lines.len());
+            assert_eq!(writer.lines_written(), hltas.lines.len());
+            assert_eq!(writer.into_inner().unwrap(), expected, "{}", path.display());
+        });
+    }
+
+    #[test]
+    fn io_error() {
+        struct Failing {
+            fail: bool,
+        }
+
+        impl Write for Failing {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.fail {
+                    Err(io::Error::other("oops"))
+                } else {
+                    Ok(buf.len())
+                }
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = Writer::new(Failing { fail: false }, &Properties::default()).unwrap();
+        writer.get_mut().fail = true;
+        let result = writer.write_line(&Line::Comment("a comment".to_owned()));
+        assert!(matches!(result, Err(WriterError::Io(_))));
+        assert_eq!(writer.lines_written(), 0);
+    }
+
+    #[test]
+    fn checked() {
+        let mut writer = Writer::new_checked(Vec::new(), &Properties::default()).unwrap();
+        writer
+            .write_line(&Line::Comment("a comment".to_owned()))
+            .unwrap();
+
+        let result = writer.write_line(&Line::Comment("two\nlines".to_owned()));
+        match result {
+            Err(WriterError::Invalid(error)) => assert_eq!(error.line_index, Some(1)),
+            _ => panic!("expected a validation error, got {:?}", result),
+        }
+        assert_eq!(writer.lines_written(), 1);
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            b"version 1\nframes\n//a comment\n"
+        );
+
+        let properties = Properties {
+            demo: Some("two\nlines".to_owned()),
+            ..Properties::default()
+        };
+        assert!(matches!(
+            Writer::new_checked(Vec::new(), &properties),
+            Err(WriterError::Invalid(_))
+        ));
+    }
+}